@@ -0,0 +1,162 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use async_trait::async_trait;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    pubkey::Pubkey,
+    stake::{self, state::StakeState},
+};
+
+use sns_reputation::{
+    state::{reputation_score::ReputationScore, user_vote::UserVote, Tag},
+    utils::MAX_STAKE_ACCOUNTS,
+};
+use sns_reputation_graph::loader::{
+    parse_reputation_scores, program_accounts_config, reputation_score_batches, staker_filter,
+    votee_filters,
+};
+
+use crate::error::ActionError;
+
+/// Minimum number of epochs a stake has to be active for to be counted by the program
+const MIN_STAKE_EPOCHS: u64 = 2;
+
+/// Chain data needed to build vote transactions
+#[async_trait]
+pub trait Chain: Send + Sync {
+    async fn latest_blockhash(&self) -> Result<Hash, ActionError>;
+
+    /// Returns the stake accounts of `voter` that the program accepts as vote weight
+    async fn stake_accounts(&self, voter: &Pubkey) -> Result<Vec<Pubkey>, ActionError>;
+
+    async fn slot(&self) -> Result<u64, ActionError>;
+
+    /// Returns the data of the account at `key`, if it exists
+    async fn account_data(&self, key: &Pubkey) -> Result<Option<Vec<u8>>, ActionError>;
+
+    /// Returns the votes cast on `subject`, pending commitments included
+    async fn votes_on(&self, subject: &Pubkey) -> Result<Vec<UserVote>, ActionError>;
+
+    /// Returns the ReputationScore of each of the `users` that has one, by user
+    async fn reputation_scores(
+        &self,
+        users: &[Pubkey],
+    ) -> Result<HashMap<Pubkey, ReputationScore>, ActionError>;
+}
+
+/// Returns the delegated stake of `account` if it is a stake account that `voter` has been
+/// staking with long enough
+pub fn eligible_stake(account: &Account, voter: &Pubkey, epoch: u64) -> Option<u64> {
+    match bincode::deserialize::<StakeState>(&account.data) {
+        Ok(StakeState::Stake(meta, stake))
+            if meta.authorized.staker == *voter
+                && epoch.saturating_sub(stake.delegation.activation_epoch) >= MIN_STAKE_EPOCHS =>
+        {
+            Some(stake.delegation.stake)
+        }
+        _ => None,
+    }
+}
+
+/// Chain data fetched from an RPC node
+pub struct RpcChain {
+    pub client: RpcClient,
+}
+
+impl RpcChain {
+    pub fn new(url: String) -> Self {
+        Self {
+            client: RpcClient::new(url),
+        }
+    }
+}
+
+#[async_trait]
+impl Chain for RpcChain {
+    async fn latest_blockhash(&self) -> Result<Hash, ActionError> {
+        self.client
+            .get_latest_blockhash()
+            .await
+            .map_err(|e| ActionError::Rpc(e.to_string()))
+    }
+
+    async fn stake_accounts(&self, voter: &Pubkey) -> Result<Vec<Pubkey>, ActionError> {
+        let config = program_accounts_config(vec![staker_filter(voter)]);
+        let accounts = self
+            .client
+            .get_program_accounts_with_config(&stake::program::ID, config)
+            .await
+            .map_err(|e| ActionError::Rpc(e.to_string()))?;
+        let epoch = self
+            .client
+            .get_epoch_info()
+            .await
+            .map_err(|e| ActionError::Rpc(e.to_string()))?
+            .epoch;
+        let mut eligible = accounts
+            .into_iter()
+            .filter_map(|(key, account)| Some((key, eligible_stake(&account, voter, epoch)?)))
+            .collect::<Vec<_>>();
+        // The program caps the number of stake accounts, keep the largest ones
+        eligible.sort_by_key(|(_, stake)| Reverse(*stake));
+        eligible.truncate(MAX_STAKE_ACCOUNTS);
+        Ok(eligible.into_iter().map(|(key, _)| key).collect())
+    }
+
+    async fn slot(&self) -> Result<u64, ActionError> {
+        self.client
+            .get_slot()
+            .await
+            .map_err(|e| ActionError::Rpc(e.to_string()))
+    }
+
+    async fn account_data(&self, key: &Pubkey) -> Result<Option<Vec<u8>>, ActionError> {
+        let account = self
+            .client
+            .get_account_with_commitment(key, self.client.commitment())
+            .await
+            .map_err(|e| ActionError::Rpc(e.to_string()))?
+            .value;
+        Ok(account.map(|account| account.data))
+    }
+
+    async fn votes_on(&self, subject: &Pubkey) -> Result<Vec<UserVote>, ActionError> {
+        let mut votes = vec![];
+        for filters in votee_filters(subject) {
+            let config = program_accounts_config(filters);
+            let accounts = self
+                .client
+                .get_program_accounts_with_config(&sns_reputation::ID, config)
+                .await
+                .map_err(|e| ActionError::Rpc(e.to_string()))?;
+            for (_, account) in accounts {
+                votes.push(
+                    UserVote::from_buffer(&account.data, Tag::UserVote)
+                        .map_err(|_| ActionError::InvalidAccountData)?,
+                );
+            }
+        }
+        Ok(votes)
+    }
+
+    async fn reputation_scores(
+        &self,
+        users: &[Pubkey],
+    ) -> Result<HashMap<Pubkey, ReputationScore>, ActionError> {
+        let mut scores = HashMap::new();
+        for (users, keys) in reputation_score_batches(&sns_reputation::ID, users) {
+            let accounts = self
+                .client
+                .get_multiple_accounts(&keys)
+                .await
+                .map_err(|e| ActionError::Rpc(e.to_string()))?;
+            scores.extend(
+                parse_reputation_scores(users, &keys, accounts)
+                    .map_err(|_| ActionError::InvalidAccountData)?,
+            );
+        }
+        Ok(scores)
+    }
+}
@@ -0,0 +1,44 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ActionError {
+    #[error("Invalid subject public key")]
+    InvalidSubject,
+    #[error("Invalid account public key")]
+    InvalidAccount,
+    #[error("You cannot vote for yourself")]
+    CannotVoteForYourself,
+    #[error("RPC error: {0}")]
+    Rpc(String),
+    #[error("Failed to serialize the transaction")]
+    Serialization,
+    #[error("Invalid program account data")]
+    InvalidAccountData,
+    #[error("Address lookup table not found")]
+    LookupTableNotFound,
+    #[error("The vote doesn't fit in a transaction")]
+    TransactionTooLarge,
+}
+
+impl IntoResponse for ActionError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            ActionError::InvalidSubject
+            | ActionError::InvalidAccount
+            | ActionError::CannotVoteForYourself
+            | ActionError::LookupTableNotFound => StatusCode::BAD_REQUEST,
+            ActionError::Rpc(_)
+            | ActionError::Serialization
+            | ActionError::InvalidAccountData
+            | ActionError::TransactionTooLarge => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        // The Actions spec expects errors as `{ "message": ... }`
+        (status, Json(json!({ "message": self.to_string() }))).into_response()
+    }
+}
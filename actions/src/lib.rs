@@ -0,0 +1,49 @@
+//! Solana Actions server for SNS Reputation votes.
+//!
+//! `GET /api/actions/vote/<subject>` returns the action metadata and
+//! `POST /api/actions/vote/<subject>?value=<upvote|downvote|novote>` returns an
+//! unsigned vote transaction for the wallet given in the request body. An optional
+//! `lookup_table=<address>` holding the stake accounts of the voter makes it a v0 transaction.
+//! `GET /api/score/<subject>/breakdown` lists the votes making up the score of the subject.
+
+pub mod chain;
+pub mod error;
+pub mod routes;
+pub mod transaction;
+
+use std::sync::Arc;
+
+use axum::{routing::get, Router};
+use tower_http::cors::{Any, CorsLayer};
+
+use crate::chain::Chain;
+
+/// State shared by the request handlers
+#[derive(Clone)]
+pub struct AppState {
+    /// Source of the blockhashes, stake accounts and votes
+    pub chain: Arc<dyn Chain>,
+    /// URL of the icon displayed by wallets
+    pub icon_url: String,
+}
+
+/// Builds the router of the service
+pub fn router(state: AppState) -> Router {
+    // Wallets and Blink clients call the service from any origin
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods(Any)
+        .allow_headers(Any);
+    Router::new()
+        .route("/actions.json", get(routes::actions_json))
+        .route(
+            "/api/actions/vote/:subject",
+            get(routes::get_vote).post(routes::post_vote),
+        )
+        .route(
+            "/api/score/:subject/breakdown",
+            get(routes::get_score_breakdown),
+        )
+        .layer(cors)
+        .with_state(state)
+}
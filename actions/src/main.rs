@@ -0,0 +1,27 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use sns_reputation_actions::{chain::RpcChain, router, AppState};
+
+const DEFAULT_RPC_URL: &str = "https://api.mainnet-beta.solana.com";
+const DEFAULT_ICON_URL: &str = "https://i.imgur.com/nn7LMNV.png";
+
+#[tokio::main]
+async fn main() {
+    let rpc_url = std::env::var("RPC_URL").unwrap_or_else(|_| DEFAULT_RPC_URL.to_string());
+    let icon_url = std::env::var("ICON_URL").unwrap_or_else(|_| DEFAULT_ICON_URL.to_string());
+    let addr: SocketAddr = std::env::var("BIND_ADDRESS")
+        .unwrap_or_else(|_| "0.0.0.0:8080".to_string())
+        .parse()
+        .expect("Invalid BIND_ADDRESS");
+
+    let state = AppState {
+        chain: Arc::new(RpcChain::new(rpc_url)),
+        icon_url,
+    };
+
+    println!("Listening on {addr}");
+    axum::Server::bind(&addr)
+        .serve(router(state).into_make_service())
+        .await
+        .unwrap();
+}
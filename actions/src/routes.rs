@@ -0,0 +1,241 @@
+use std::str::FromStr;
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sns_reputation::state::VoteValue;
+use sns_reputation_graph::explain::{explain_score, Contributor, VoteOrigin};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    error::ActionError,
+    transaction::{build_vote_transaction, encode_transaction},
+    AppState,
+};
+
+/// The vote requested by the action link
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum VoteChoice {
+    Upvote,
+    Downvote,
+    NoVote,
+}
+
+impl From<VoteChoice> for VoteValue {
+    fn from(choice: VoteChoice) -> Self {
+        match choice {
+            VoteChoice::Upvote => VoteValue::Upvote,
+            VoteChoice::Downvote => VoteValue::Downvote,
+            VoteChoice::NoVote => VoteValue::NoVote,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct VoteQuery {
+    pub value: VoteChoice,
+    /// Address lookup table holding the stake accounts of the voter, base58 encoded. The
+    /// transaction is a v0 transaction looking them up when given
+    pub lookup_table: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ActionPostRequest {
+    /// The wallet voting, base58 encoded
+    pub account: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ActionPostResponse {
+    /// Base64 encoded unsigned transaction
+    pub transaction: String,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LinkedAction {
+    pub label: String,
+    pub href: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ActionLinks {
+    pub actions: Vec<LinkedAction>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ActionGetResponse {
+    pub icon: String,
+    pub title: String,
+    pub description: String,
+    pub label: String,
+    pub links: ActionLinks,
+}
+
+#[derive(Deserialize)]
+pub struct BreakdownQuery {
+    /// Maximum number of contributors returned, all of them by default
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct VoterScoreResponse {
+    pub upvote: u64,
+    pub downvote: u64,
+    /// Scaled by `SCORE_SCALE`
+    pub wilson_score: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub struct ContributorResponse {
+    pub voter: String,
+    /// Weighted value of the vote, negative for downvotes
+    pub weight: i64,
+    /// Share of the upvote total for upvotes, of the downvote total for downvotes
+    pub share: f64,
+    /// Slots since the value of the vote was last set. UserVote accounts in the layouts before
+    /// the third one didn't record that slot, their age is the one of their commitment, `None`
+    /// when they were cast without a commitment
+    pub age_slots: Option<u64>,
+    /// Curator the vote was propagated from, if any
+    pub followed_curator: Option<String>,
+    /// Scaled by `CREDIBILITY_SCALE`
+    pub credibility_multiplier: u64,
+    pub voter_score: Option<VoterScoreResponse>,
+}
+
+impl From<&Contributor> for ContributorResponse {
+    fn from(contributor: &Contributor) -> Self {
+        Self {
+            voter: contributor.voter.to_string(),
+            weight: contributor.weight,
+            share: contributor.share,
+            age_slots: contributor.age_slots,
+            followed_curator: match contributor.origin {
+                VoteOrigin::Direct => None,
+                VoteOrigin::Curator(curator) => Some(curator.to_string()),
+            },
+            credibility_multiplier: contributor.credibility_multiplier,
+            voter_score: contributor.voter_score.map(|score| VoterScoreResponse {
+                upvote: score.upvote,
+                downvote: score.downvote,
+                wilson_score: score.wilson_score,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub struct ScoreBreakdownResponse {
+    pub subject: String,
+    pub upvote: u64,
+    pub downvote: u64,
+    /// The votes cast on the subject, heaviest first
+    pub contributors: Vec<ContributorResponse>,
+}
+
+fn parse_subject(subject: &str) -> Result<Pubkey, ActionError> {
+    Pubkey::from_str(subject).map_err(|_| ActionError::InvalidSubject)
+}
+
+/// Maps website paths to the action API, see the `actions.json` section of the Actions spec
+pub async fn actions_json() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "rules": [
+            { "pathPattern": "/vote/*", "apiPath": "/api/actions/vote/*" },
+            { "pathPattern": "/api/actions/**", "apiPath": "/api/actions/**" }
+        ]
+    }))
+}
+
+pub async fn get_vote(
+    State(state): State<AppState>,
+    Path(subject): Path<String>,
+) -> Result<Json<ActionGetResponse>, ActionError> {
+    let subject = parse_subject(&subject)?;
+    let href = |value: &str| format!("/api/actions/vote/{subject}?value={value}");
+    Ok(Json(ActionGetResponse {
+        icon: state.icon_url.clone(),
+        title: "SNS Reputation".to_string(),
+        description: format!(
+            "Vote on the reputation of {subject}. Votes are weighted by the SOL you stake."
+        ),
+        label: "Vote".to_string(),
+        links: ActionLinks {
+            actions: vec![
+                LinkedAction {
+                    label: "Upvote".to_string(),
+                    href: href("upvote"),
+                },
+                LinkedAction {
+                    label: "Downvote".to_string(),
+                    href: href("downvote"),
+                },
+                LinkedAction {
+                    label: "Remove vote".to_string(),
+                    href: href("novote"),
+                },
+            ],
+        },
+    }))
+}
+
+pub async fn post_vote(
+    State(state): State<AppState>,
+    Path(subject): Path<String>,
+    Query(query): Query<VoteQuery>,
+    Json(request): Json<ActionPostRequest>,
+) -> Result<Json<ActionPostResponse>, ActionError> {
+    let subject = parse_subject(&subject)?;
+    let voter = Pubkey::from_str(&request.account).map_err(|_| ActionError::InvalidAccount)?;
+    let lookup_table = query
+        .lookup_table
+        .as_deref()
+        .map(|key| Pubkey::from_str(key).map_err(|_| ActionError::LookupTableNotFound))
+        .transpose()?;
+    let transaction = build_vote_transaction(
+        state.chain.as_ref(),
+        &voter,
+        &subject,
+        query.value.into(),
+        lookup_table.as_ref(),
+    )
+    .await?;
+    let message = match query.value {
+        VoteChoice::Upvote => "Upvote",
+        VoteChoice::Downvote => "Downvote",
+        VoteChoice::NoVote => "Vote removal",
+    };
+    Ok(Json(ActionPostResponse {
+        transaction: encode_transaction(&transaction)?,
+        message: Some(format!("{message} for {subject}")),
+    }))
+}
+
+/// Lists the votes making up the score of the subject
+pub async fn get_score_breakdown(
+    State(state): State<AppState>,
+    Path(subject): Path<String>,
+    Query(query): Query<BreakdownQuery>,
+) -> Result<Json<ScoreBreakdownResponse>, ActionError> {
+    let subject = parse_subject(&subject)?;
+    let votes = state.chain.votes_on(&subject).await?;
+    let voters = votes.iter().map(|vote| vote.voter).collect::<Vec<_>>();
+    let voter_scores = state.chain.reputation_scores(&voters).await?;
+    let slot = state.chain.slot().await?;
+    let breakdown = explain_score(&subject, &votes, &voter_scores, slot);
+    Ok(Json(ScoreBreakdownResponse {
+        subject: subject.to_string(),
+        upvote: breakdown.upvote,
+        downvote: breakdown.downvote,
+        contributors: breakdown
+            .contributors
+            .iter()
+            .take(query.limit.unwrap_or(usize::MAX))
+            .map(ContributorResponse::from)
+            .collect(),
+    }))
+}
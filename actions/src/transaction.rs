@@ -0,0 +1,205 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sns_reputation::{
+    instruction::{migrate, vote},
+    state::{
+        config::Config, reputation_score::ReputationScore, user_vote::UserVote,
+        voter_profile::VoterProfile, Tag, VoteValue,
+    },
+};
+use solana_address_lookup_table_program::state::AddressLookupTable;
+use solana_sdk::{
+    address_lookup_table_account::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, Message, VersionedMessage},
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Signature,
+    system_program,
+    transaction::VersionedTransaction,
+};
+
+use crate::{chain::Chain, error::ActionError};
+
+/// Returns true if `data` is a ReputationScore or UserVote account that has to be migrated
+/// before the program updates it
+fn needs_migration(data: &[u8]) -> bool {
+    if data.starts_with(&(Tag::ReputationScore as u64).to_le_bytes()) {
+        !ReputationScore::is_latest_layout(data)
+    } else if data.starts_with(&(Tag::UserVote as u64).to_le_bytes()) {
+        !UserVote::is_latest_layout(data)
+    } else {
+        false
+    }
+}
+
+/// Fetches the address lookup table at `key`
+async fn fetch_lookup_table(
+    chain: &dyn Chain,
+    key: &Pubkey,
+) -> Result<AddressLookupTableAccount, ActionError> {
+    let data = chain
+        .account_data(key)
+        .await?
+        .ok_or(ActionError::LookupTableNotFound)?;
+    let table =
+        AddressLookupTable::deserialize(&data).map_err(|_| ActionError::LookupTableNotFound)?;
+    Ok(AddressLookupTableAccount {
+        key: *key,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Compiles the unsigned transaction sending `instructions`, a v0 transaction when a
+/// `lookup_table` is given and a legacy one otherwise
+fn compile_transaction(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_table: Option<&AddressLookupTableAccount>,
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction, ActionError> {
+    let message = match lookup_table {
+        Some(lookup_table) => VersionedMessage::V0(
+            v0::Message::try_compile(
+                payer,
+                instructions,
+                std::slice::from_ref(lookup_table),
+                recent_blockhash,
+            )
+            .map_err(|_| ActionError::Serialization)?,
+        ),
+        None => VersionedMessage::Legacy(Message::new_with_blockhash(
+            instructions,
+            Some(payer),
+            &recent_blockhash,
+        )),
+    };
+    Ok(VersionedTransaction {
+        signatures: vec![Signature::default(); message.header().num_required_signatures as usize],
+        message,
+    })
+}
+
+/// Builds the unsigned transaction in which `voter` votes `vote_value` on `subject`, the voter
+/// paying for the fees and the created accounts. Undoing a vote refunds its rent to whoever
+/// paid for it. Accounts using an outdated layout are migrated first.
+///
+/// The stake accounts of the voter are looked up from `lookup_table` when given, in a v0
+/// transaction. Otherwise, or when some of them are missing from the table, the smallest stake
+/// accounts are left out until the transaction fits in a packet
+pub async fn build_vote_transaction(
+    chain: &dyn Chain,
+    voter: &Pubkey,
+    subject: &Pubkey,
+    vote_value: VoteValue,
+    lookup_table: Option<&Pubkey>,
+) -> Result<VersionedTransaction, ActionError> {
+    if voter == subject {
+        return Err(ActionError::CannotVoteForYourself);
+    }
+    let program_id = sns_reputation::ID;
+    let (user_vote_state_account, _) = UserVote::find_key(&program_id, &(*subject, *voter));
+    let (config_account, _) = Config::find_key(&program_id);
+    let (voter_profile_account, _) = VoterProfile::find_key(&program_id, voter);
+
+    // Undoing a vote doesn't need any weight
+    let (mut voter_stake_accounts, voter_reputation_account) = if vote_value == VoteValue::NoVote {
+        (vec![], None)
+    } else {
+        // Voters who were voted on have their votes scaled by their credibility
+        let (voter_reputation_account, _) = ReputationScore::find_key(&program_id, voter);
+        let voter_reputation_account = chain
+            .account_data(&voter_reputation_account)
+            .await?
+            .map(|_| voter_reputation_account);
+        (chain.stake_accounts(voter).await?, voter_reputation_account)
+    };
+
+    let user_vote_data = chain.account_data(&user_vote_state_account).await?;
+    let user_vote = user_vote_data
+        .as_deref()
+        .map(|data| UserVote::from_buffer(data, Tag::UserVote))
+        .transpose()
+        .map_err(|_| ActionError::InvalidAccountData)?;
+
+    // The program only closes the UserVote into the account that paid for it
+    let rent_payer = match &user_vote {
+        Some(user_vote) if vote_value == VoteValue::NoVote => *user_vote.rent_destination(),
+        _ => *voter,
+    };
+
+    // A vote already cast is changed in the account it was tallied in
+    let reputation_state_account = match &user_vote {
+        Some(user_vote) if user_vote.reputation_account != Pubkey::default() => {
+            user_vote.reputation_account
+        }
+        _ => ReputationScore::find_key(&program_id, subject).0,
+    };
+
+    let mut instructions = vec![];
+    let reputation_state_data = chain.account_data(&reputation_state_account).await?;
+    for (state_account, data) in [
+        (&reputation_state_account, &reputation_state_data),
+        (&user_vote_state_account, &user_vote_data),
+    ] {
+        if data.as_deref().is_some_and(needs_migration) {
+            instructions.push(migrate(
+                migrate::Accounts {
+                    system_program: &system_program::ID,
+                    rent_payer: voter,
+                    state_account,
+                },
+                migrate::Params {},
+            ));
+        }
+    }
+
+    let lookup_table = match lookup_table {
+        Some(key) => Some(fetch_lookup_table(chain, key).await?),
+        None => None,
+    };
+    let recent_blockhash = chain.latest_blockhash().await?;
+    let migrations = instructions.len();
+    loop {
+        instructions.truncate(migrations);
+        instructions.push(vote(
+            vote::Accounts {
+                system_program: &system_program::ID,
+                rent_payer: &rent_payer,
+                voter,
+                reputation_state_account: &reputation_state_account,
+                user_vote_state_account: &user_vote_state_account,
+                voter_profile_account: &voter_profile_account,
+                config_account: &config_account,
+                voter_reputation_account: voter_reputation_account.as_ref(),
+                voter_stake_accounts: &voter_stake_accounts,
+            },
+            vote::Params {
+                user_key: *subject,
+                vote_value,
+            },
+        ));
+
+        let transaction = compile_transaction(
+            voter,
+            &instructions,
+            lookup_table.as_ref(),
+            recent_blockhash,
+        )?;
+        let size =
+            bincode::serialized_size(&transaction).map_err(|_| ActionError::Serialization)?;
+        if size as usize <= PACKET_DATA_SIZE {
+            return Ok(transaction);
+        }
+        // The stake accounts are sorted by decreasing stake
+        if voter_stake_accounts.pop().is_none() {
+            return Err(ActionError::TransactionTooLarge);
+        }
+    }
+}
+
+/// Serializes an unsigned transaction as expected by the Actions spec
+pub fn encode_transaction(transaction: &VersionedTransaction) -> Result<String, ActionError> {
+    let bytes = bincode::serialize(transaction).map_err(|_| ActionError::Serialization)?;
+    Ok(STANDARD.encode(bytes))
+}
@@ -0,0 +1,591 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sns_reputation::{
+    entrypoint::process_instruction,
+    instruction::vote,
+    state::{
+        config::Config, reputation_score::ReputationScore, user_vote::UserVote,
+        voter_profile::VoterProfile, Tag, VoteValue,
+    },
+};
+use sns_reputation_actions::{
+    chain::Chain,
+    error::ActionError,
+    router,
+    routes::{ActionGetResponse, ActionPostResponse, ScoreBreakdownResponse},
+    transaction::build_vote_transaction,
+    AppState,
+};
+use solana_address_lookup_table_program::state::{AddressLookupTable, LookupTableMeta};
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    message::VersionedMessage,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, VersionedTransaction},
+};
+use tokio::sync::Mutex;
+use tower::ServiceExt;
+
+/// Serves chain data from a `solana-program-test` bank
+struct BanksChain {
+    client: Mutex<BanksClient>,
+    /// Banks can't be scanned, the votes are looked up from the voters
+    voters: Vec<Pubkey>,
+    stake_accounts: Vec<Pubkey>,
+}
+
+#[async_trait]
+impl Chain for BanksChain {
+    async fn latest_blockhash(&self) -> Result<Hash, ActionError> {
+        self.client
+            .lock()
+            .await
+            .get_latest_blockhash()
+            .await
+            .map_err(|e| ActionError::Rpc(e.to_string()))
+    }
+
+    async fn stake_accounts(&self, _voter: &Pubkey) -> Result<Vec<Pubkey>, ActionError> {
+        Ok(self.stake_accounts.clone())
+    }
+
+    async fn slot(&self) -> Result<u64, ActionError> {
+        self.client
+            .lock()
+            .await
+            .get_root_slot()
+            .await
+            .map_err(|e| ActionError::Rpc(e.to_string()))
+    }
+
+    async fn account_data(&self, key: &Pubkey) -> Result<Option<Vec<u8>>, ActionError> {
+        let account = self
+            .client
+            .lock()
+            .await
+            .get_account(*key)
+            .await
+            .map_err(|e| ActionError::Rpc(e.to_string()))?;
+        Ok(account.map(|account| account.data))
+    }
+
+    async fn votes_on(&self, subject: &Pubkey) -> Result<Vec<UserVote>, ActionError> {
+        let mut votes = vec![];
+        for voter in &self.voters {
+            let (key, _) = UserVote::find_key(&sns_reputation::ID, &(*subject, *voter));
+            if let Some(data) = self.account_data(&key).await? {
+                votes.push(UserVote::from_buffer(&data, Tag::UserVote).unwrap());
+            }
+        }
+        Ok(votes)
+    }
+
+    async fn reputation_scores(
+        &self,
+        users: &[Pubkey],
+    ) -> Result<HashMap<Pubkey, ReputationScore>, ActionError> {
+        let mut scores = HashMap::new();
+        for user in users {
+            let (key, _) = ReputationScore::find_key(&sns_reputation::ID, user);
+            if let Some(data) = self.account_data(&key).await? {
+                let score = ReputationScore::from_buffer(&data, Tag::ReputationScore).unwrap();
+                scores.insert(*user, score);
+            }
+        }
+        Ok(scores)
+    }
+}
+
+async fn body_json<T: serde::de::DeserializeOwned>(response: axum::response::Response) -> T {
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn test_vote_action() {
+    let program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let mut ctx = program_test.start_with_context().await;
+    let voter = ctx.payer.pubkey();
+    let app = router(AppState {
+        chain: Arc::new(BanksChain {
+            client: Mutex::new(ctx.banks_client.clone()),
+            voters: vec![voter],
+            // Votes have a weight of 1 with the devnet feature
+            stake_accounts: vec![],
+        }),
+        icon_url: "https://example.com/icon.png".to_string(),
+    });
+
+    let subject = Pubkey::new_unique();
+
+    // Metadata
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get(format!("/api/actions/vote/{subject}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let metadata: ActionGetResponse = body_json(response).await;
+    assert_eq!(metadata.links.actions.len(), 3);
+    assert_eq!(
+        metadata.links.actions[0].href,
+        format!("/api/actions/vote/{subject}?value=upvote")
+    );
+
+    // Invalid subject
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get("/api/actions/vote/not-a-key")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Unsigned transaction
+    let response = app
+        .clone()
+        .oneshot(
+            Request::post(format!("/api/actions/vote/{subject}?value=upvote"))
+                .header("content-type", "application/json")
+                .body(Body::from(format!(r#"{{"account":"{voter}"}}"#)))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let post: ActionPostResponse = body_json(response).await;
+
+    let bytes = STANDARD.decode(post.transaction).unwrap();
+    let mut transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(transaction.message.account_keys[0], voter);
+
+    // The wallet signs and sends the transaction
+    let recent_blockhash = transaction.message.recent_blockhash;
+    transaction.sign(&[&ctx.payer], recent_blockhash);
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let (reputation_state, _) = ReputationScore::find_key(&sns_reputation::ID, &subject);
+    let account = ctx
+        .banks_client
+        .get_account(reputation_state)
+        .await
+        .unwrap()
+        .unwrap();
+    let score = ReputationScore::from_buffer(&account.data, Tag::ReputationScore).unwrap();
+    assert_eq!(score.upvote, 1);
+
+    // The vote shows up in the breakdown of the score
+    let response = app
+        .clone()
+        .oneshot(
+            Request::get(format!("/api/score/{subject}/breakdown"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let breakdown: ScoreBreakdownResponse = body_json(response).await;
+    assert_eq!((breakdown.upvote, breakdown.downvote), (1, 0));
+    assert_eq!(breakdown.contributors.len(), 1);
+    let contributor = &breakdown.contributors[0];
+    assert_eq!(contributor.voter, voter.to_string());
+    assert_eq!((contributor.weight, contributor.share), (1, 1.0));
+    // The vote was cast in the current slot
+    assert_eq!(contributor.age_slots, Some(0));
+    assert_eq!(contributor.followed_curator, None);
+    // The voter was never voted on
+    assert_eq!(contributor.voter_score, None);
+
+    // Voting for yourself is rejected before building the transaction
+    let response = app
+        .oneshot(
+            Request::post(format!("/api/actions/vote/{voter}?value=upvote"))
+                .header("content-type", "application/json")
+                .body(Body::from(format!(r#"{{"account":"{voter}"}}"#)))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_undo_sponsored_vote() {
+    let mut program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let voter = Keypair::new();
+    program_test.add_account(
+        voter.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+    let sponsor = ctx.payer.pubkey();
+    let app = router(AppState {
+        chain: Arc::new(BanksChain {
+            client: Mutex::new(ctx.banks_client.clone()),
+            voters: vec![voter.pubkey()],
+            // Votes have a weight of 1 with the devnet feature
+            stake_accounts: vec![],
+        }),
+        icon_url: "https://example.com/icon.png".to_string(),
+    });
+
+    // The sponsor pays for the UserVote of the voter
+    let subject = Pubkey::new_unique();
+    let program_id = sns_reputation::ID;
+    let (user_vote_key, _) = UserVote::find_key(&program_id, &(subject, voter.pubkey()));
+    let instruction = vote(
+        vote::Accounts {
+            system_program: &system_program::ID,
+            rent_payer: &sponsor,
+            voter: &voter.pubkey(),
+            reputation_state_account: &ReputationScore::find_key(&program_id, &subject).0,
+            user_vote_state_account: &user_vote_key,
+            voter_profile_account: &VoterProfile::find_key(&program_id, &voter.pubkey()).0,
+            config_account: &Config::find_key(&program_id).0,
+            voter_reputation_account: None,
+            voter_stake_accounts: &[],
+        },
+        vote::Params {
+            user_key: subject,
+            vote_value: VoteValue::Upvote,
+        },
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&sponsor),
+        &[&ctx.payer, &voter],
+        ctx.last_blockhash,
+    );
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let sponsor_lamports = ctx.banks_client.get_balance(sponsor).await.unwrap();
+
+    // The voter undoes the vote from the action
+    let response = app
+        .oneshot(
+            Request::post(format!("/api/actions/vote/{subject}?value=novote"))
+                .header("content-type", "application/json")
+                .body(Body::from(format!(r#"{{"account":"{}"}}"#, voter.pubkey())))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let post: ActionPostResponse = body_json(response).await;
+    let bytes = STANDARD.decode(post.transaction).unwrap();
+    let mut transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+    let recent_blockhash = transaction.message.recent_blockhash;
+    transaction.sign(&[&voter], recent_blockhash);
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The rent went back to the sponsor
+    assert!(ctx
+        .banks_client
+        .get_account(user_vote_key)
+        .await
+        .unwrap()
+        .is_none());
+    let refunded = ctx.banks_client.get_balance(sponsor).await.unwrap();
+    assert!(refunded > sponsor_lamports);
+}
+
+#[tokio::test]
+async fn test_migrate_before_voting() {
+    let mut program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let voter = Keypair::new();
+    let subject = Pubkey::new_unique();
+    let program_id = sns_reputation::ID;
+    let (reputation_state, reputation_nonce) = ReputationScore::find_key(&program_id, &subject);
+    let (user_vote_key, _) = UserVote::find_key(&program_id, &(subject, voter.pubkey()));
+    program_test.add_account(
+        voter.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+
+    // An upvote cast with the first version of the program
+    let mut reputation_data = (Tag::ReputationScore as u64).to_le_bytes().to_vec();
+    reputation_data.push(reputation_nonce);
+    reputation_data.extend_from_slice(&1u64.to_le_bytes());
+    reputation_data.extend_from_slice(&0u64.to_le_bytes());
+    let mut user_vote_data = (Tag::UserVote as u64).to_le_bytes().to_vec();
+    user_vote_data.extend_from_slice(&1i64.to_le_bytes());
+    user_vote_data.extend_from_slice(subject.as_ref());
+    user_vote_data.extend_from_slice(voter.pubkey().as_ref());
+    for (key, data) in [
+        (reputation_state, reputation_data),
+        (user_vote_key, user_vote_data),
+    ] {
+        program_test.add_account(
+            key,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: program_id,
+                ..Account::default()
+            },
+        );
+    }
+    let mut ctx = program_test.start_with_context().await;
+    let app = router(AppState {
+        chain: Arc::new(BanksChain {
+            client: Mutex::new(ctx.banks_client.clone()),
+            voters: vec![voter.pubkey()],
+            // Votes have a weight of 1 with the devnet feature
+            stake_accounts: vec![],
+        }),
+        icon_url: "https://example.com/icon.png".to_string(),
+    });
+
+    let response = app
+        .oneshot(
+            Request::post(format!("/api/actions/vote/{subject}?value=downvote"))
+                .header("content-type", "application/json")
+                .body(Body::from(format!(r#"{{"account":"{}"}}"#, voter.pubkey())))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let post: ActionPostResponse = body_json(response).await;
+    let bytes = STANDARD.decode(post.transaction).unwrap();
+    let mut transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+    // Both accounts are migrated before the vote
+    assert_eq!(transaction.message.instructions.len(), 3);
+    let recent_blockhash = transaction.message.recent_blockhash;
+    transaction.sign(&[&voter], recent_blockhash);
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = ctx
+        .banks_client
+        .get_account(reputation_state)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(ReputationScore::is_latest_layout(&account.data));
+    let score = ReputationScore::from_buffer(&account.data, Tag::ReputationScore).unwrap();
+    assert_eq!((score.upvote, score.downvote), (0, 1));
+    let account = ctx
+        .banks_client
+        .get_account(user_vote_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(UserVote::is_latest_layout(&account.data));
+}
+
+#[tokio::test]
+async fn test_voter_credibility() {
+    let mut program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let voter = Keypair::new();
+    let program_id = sns_reputation::ID;
+    program_test.add_account(
+        voter.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    // The voter is well regarded
+    let (voter_reputation_key, nonce) = ReputationScore::find_key(&program_id, &voter.pubkey());
+    let mut data = vec![0; ReputationScore::space()];
+    ReputationScore {
+        nonce,
+        upvote: 80,
+        downvote: 20,
+        upvoters: 80,
+        downvoters: 20,
+    }
+    .save(&mut data)
+    .unwrap();
+    program_test.add_account(
+        voter_reputation_key,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut ctx = program_test.start_with_context().await;
+    let app = router(AppState {
+        chain: Arc::new(BanksChain {
+            client: Mutex::new(ctx.banks_client.clone()),
+            voters: vec![voter.pubkey()],
+            // Votes have a weight of 1 with the devnet feature
+            stake_accounts: vec![],
+        }),
+        icon_url: "https://example.com/icon.png".to_string(),
+    });
+
+    let subject = Pubkey::new_unique();
+    let response = app
+        .oneshot(
+            Request::post(format!("/api/actions/vote/{subject}?value=upvote"))
+                .header("content-type", "application/json")
+                .body(Body::from(format!(r#"{{"account":"{}"}}"#, voter.pubkey())))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let post: ActionPostResponse = body_json(response).await;
+    let bytes = STANDARD.decode(post.transaction).unwrap();
+    let mut transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+    let recent_blockhash = transaction.message.recent_blockhash;
+    transaction.sign(&[&voter], recent_blockhash);
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The vote is scaled by the credibility of the voter
+    let (user_vote_key, _) = UserVote::find_key(&program_id, &(subject, voter.pubkey()));
+    let account = ctx
+        .banks_client
+        .get_account(user_vote_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let vote = UserVote::from_buffer(&account.data, Tag::UserVote).unwrap();
+    assert_eq!(vote.credibility_multiplier, 12_111);
+}
+
+#[tokio::test]
+async fn test_many_stake_accounts() {
+    let mut program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let voter = Pubkey::new_unique();
+    let subject = Pubkey::new_unique();
+    let stake_accounts = (0..32).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+
+    // The voter keeps their stake accounts in a lookup table
+    let lookup_table = Pubkey::new_unique();
+    let data = AddressLookupTable {
+        meta: LookupTableMeta::new(voter),
+        addresses: stake_accounts.clone().into(),
+    }
+    .serialize_for_tests()
+    .unwrap();
+    program_test.add_account(
+        lookup_table,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: solana_address_lookup_table_program::ID,
+            ..Account::default()
+        },
+    );
+    let ctx = program_test.start_with_context().await;
+    let chain = BanksChain {
+        client: Mutex::new(ctx.banks_client.clone()),
+        voters: vec![voter],
+        stake_accounts: stake_accounts.clone(),
+    };
+    let serialized_size = |transaction: &VersionedTransaction| {
+        bincode::serialized_size(transaction).unwrap() as usize
+    };
+
+    // A legacy transaction leaves out the smallest stake accounts
+    let transaction = build_vote_transaction(&chain, &voter, &subject, VoteValue::Upvote, None)
+        .await
+        .unwrap();
+    let VersionedMessage::Legacy(message) = &transaction.message else {
+        panic!("expected a legacy message");
+    };
+    assert!(serialized_size(&transaction) <= PACKET_DATA_SIZE);
+    let kept = stake_accounts
+        .iter()
+        .take_while(|key| message.account_keys.contains(key))
+        .count();
+    assert!(0 < kept && kept < stake_accounts.len());
+    assert!(!message.account_keys.contains(&stake_accounts[kept]));
+
+    // A v0 transaction looks all of them up from the table
+    let transaction = build_vote_transaction(
+        &chain,
+        &voter,
+        &subject,
+        VoteValue::Upvote,
+        Some(&lookup_table),
+    )
+    .await
+    .unwrap();
+    let VersionedMessage::V0(message) = &transaction.message else {
+        panic!("expected a v0 message");
+    };
+    assert!(serialized_size(&transaction) <= PACKET_DATA_SIZE);
+    assert_eq!(message.address_table_lookups.len(), 1);
+    assert_eq!(message.address_table_lookups[0].account_key, lookup_table);
+    assert_eq!(
+        message.address_table_lookups[0].readonly_indexes,
+        (0..stake_accounts.len() as u8).collect::<Vec<_>>()
+    );
+
+    // An unknown lookup table is rejected
+    let error = build_vote_transaction(
+        &chain,
+        &voter,
+        &subject,
+        VoteValue::Upvote,
+        Some(&Pubkey::new_unique()),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, ActionError::LookupTableNotFound));
+}
@@ -0,0 +1,37 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("Invalid public key {0}")]
+    InvalidKey(String),
+    #[error("Either the voter or the subject is required")]
+    MissingFilter,
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+}
+
+/// Body of the error responses
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub message: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            ApiError::InvalidKey(_) | ApiError::MissingFilter => StatusCode::BAD_REQUEST,
+            ApiError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = ErrorResponse {
+            message: self.to_string(),
+        };
+        (status, Json(body)).into_response()
+    }
+}
@@ -0,0 +1,59 @@
+//! REST API serving the scores and votes of SNS Reputation from the database of the indexer.
+//!
+//! `GET /score/<subject>` returns the score of a user, `GET /votes?voter=<voter>&subject=<subject>`
+//! the votes currently cast, `GET /leaderboard?limit=<n>` the users with the highest scores and
+//! `GET /history/<subject>` every vote cast on a user. `GET /openapi.json` describes the API.
+
+pub mod error;
+pub mod routes;
+
+use std::sync::Arc;
+
+use axum::{routing::get, Json, Router};
+use sns_reputation_indexer::db::Database;
+use tokio::sync::Mutex;
+use tower_http::cors::{Any, CorsLayer};
+use utoipa::OpenApi;
+
+use crate::{
+    error::ErrorResponse,
+    routes::{ScoreResponse, VoteResponse},
+};
+
+/// State shared by the request handlers
+#[derive(Clone)]
+pub struct AppState {
+    /// Database written by the indexer
+    pub database: Arc<Mutex<Database>>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "SNS Reputation API"),
+    paths(
+        routes::get_score,
+        routes::get_votes,
+        routes::get_leaderboard,
+        routes::get_history
+    ),
+    components(schemas(ScoreResponse, VoteResponse, ErrorResponse))
+)]
+pub struct ApiDoc;
+
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Builds the router of the service
+pub fn router(state: AppState) -> Router {
+    // Read-only API, callable from any origin
+    let cors = CorsLayer::new().allow_origin(Any).allow_methods(Any);
+    Router::new()
+        .route("/score/:subject", get(routes::get_score))
+        .route("/votes", get(routes::get_votes))
+        .route("/leaderboard", get(routes::get_leaderboard))
+        .route("/history/:subject", get(routes::get_history))
+        .route("/openapi.json", get(openapi))
+        .layer(cors)
+        .with_state(state)
+}
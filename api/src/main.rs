@@ -0,0 +1,28 @@
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+
+use sns_reputation_api::{router, AppState};
+use sns_reputation_indexer::db::Database;
+use tokio::sync::Mutex;
+
+#[tokio::main]
+async fn main() {
+    let database: PathBuf = std::env::var("DATABASE")
+        .unwrap_or_else(|_| "indexer.sqlite".to_string())
+        .into();
+    let addr: SocketAddr = std::env::var("BIND_ADDRESS")
+        .unwrap_or_else(|_| "0.0.0.0:8080".to_string())
+        .parse()
+        .expect("Invalid BIND_ADDRESS");
+
+    let state = AppState {
+        database: Arc::new(Mutex::new(
+            Database::open(&database).expect("Failed to open the database"),
+        )),
+    };
+
+    println!("Listening on {addr}");
+    axum::Server::bind(&addr)
+        .serve(router(state).into_make_service())
+        .await
+        .unwrap();
+}
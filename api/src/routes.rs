@@ -0,0 +1,199 @@
+use std::str::FromStr;
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sns_reputation::state::reputation_score::{ReputationScore, WILSON_Z_95};
+use sns_reputation_indexer::db::{DerivedScore, HistoryEntry};
+use solana_sdk::pubkey::Pubkey;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{error::ApiError, AppState};
+
+/// Number of users returned by the leaderboard when no limit is given
+pub const DEFAULT_LEADERBOARD_LIMIT: usize = 100;
+
+/// Largest number of users returned by the leaderboard
+pub const MAX_LEADERBOARD_LIMIT: usize = 1_000;
+
+/// Tallies of a user derived from the votes replayed by the indexer. Votes cast before the program
+/// logged their weight count as voters but not in the tallies, which then lag behind the
+/// ReputationScore account of the user
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ScoreResponse {
+    /// The user, base58 encoded
+    pub subject: String,
+    /// Sum of the weights of the upvotes cast on the user
+    pub upvote: u64,
+    /// Sum of the weights of the downvotes cast on the user
+    pub downvote: u64,
+    /// Number of upvotes cast on the user, votes of unknown weight included
+    pub upvoters: u64,
+    /// Number of downvotes cast on the user, votes of unknown weight included
+    pub downvoters: u64,
+    /// Lower bound of the share of upvoters at 95% confidence, from 0 to 10000
+    pub wilson_score: u64,
+}
+
+impl ScoreResponse {
+    fn new(subject: &Pubkey, score: &DerivedScore) -> Self {
+        let reputation = ReputationScore {
+            upvote: score.upvote,
+            downvote: score.downvote,
+            upvoters: score.upvoters,
+            downvoters: score.downvoters,
+            ..ReputationScore::default()
+        };
+        Self {
+            subject: subject.to_string(),
+            upvote: score.upvote,
+            downvote: score.downvote,
+            upvoters: score.upvoters,
+            downvoters: score.downvoters,
+            wilson_score: reputation.wilson_score(WILSON_Z_95),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct VoteResponse {
+    pub voter: String,
+    pub subject: String,
+    /// 1 for an upvote, -1 for a downvote and 0 when the vote is undone
+    pub value: i64,
+    /// Weight of the vote, unknown for transactions executed before the program logged it
+    pub weight: Option<u64>,
+    /// Slot of the transaction casting the vote
+    pub slot: u64,
+    /// Unix timestamp of the transaction casting the vote, when known
+    pub block_time: Option<i64>,
+    /// Signature of the transaction casting the vote
+    pub signature: String,
+}
+
+impl From<&HistoryEntry> for VoteResponse {
+    fn from(entry: &HistoryEntry) -> Self {
+        Self {
+            voter: entry.voter.to_string(),
+            subject: entry.subject.to_string(),
+            value: entry.value,
+            weight: entry.weight,
+            slot: entry.slot,
+            block_time: entry.block_time,
+            signature: entry.signature.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VotesQuery {
+    /// Only list the votes cast by this voter, base58 encoded
+    pub voter: Option<String>,
+    /// Only list the votes cast on this user, base58 encoded
+    pub subject: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LeaderboardQuery {
+    /// Number of users to return, 100 by default and 1000 at most
+    pub limit: Option<usize>,
+}
+
+fn parse_key(key: &str) -> Result<Pubkey, ApiError> {
+    Pubkey::from_str(key).map_err(|_| ApiError::InvalidKey(key.to_string()))
+}
+
+/// Tallies of the votes currently cast on a user, derived from the votes replayed by the indexer
+#[utoipa::path(
+    get,
+    path = "/score/{subject}",
+    params(("subject" = String, Path, description = "The user, base58 encoded")),
+    responses(
+        (status = 200, description = "Score of the user, zero when it was never voted on", body = ScoreResponse),
+        (status = 400, description = "Invalid public key", body = ErrorResponse),
+    )
+)]
+pub async fn get_score(
+    State(state): State<AppState>,
+    Path(subject): Path<String>,
+) -> Result<Json<ScoreResponse>, ApiError> {
+    let subject = parse_key(&subject)?;
+    let score = state.database.lock().await.derived_score(&subject)?;
+    Ok(Json(ScoreResponse::new(&subject, &score)))
+}
+
+/// Votes currently cast by a voter, on a user, or by a voter on a user, oldest first
+#[utoipa::path(
+    get,
+    path = "/votes",
+    params(VotesQuery),
+    responses(
+        (status = 200, description = "The votes, undone votes being left out", body = [VoteResponse]),
+        (status = 400, description = "Invalid public key or missing filter", body = ErrorResponse),
+    )
+)]
+pub async fn get_votes(
+    State(state): State<AppState>,
+    Query(query): Query<VotesQuery>,
+) -> Result<Json<Vec<VoteResponse>>, ApiError> {
+    if query.voter.is_none() && query.subject.is_none() {
+        return Err(ApiError::MissingFilter);
+    }
+    let voter = query.voter.as_deref().map(parse_key).transpose()?;
+    let subject = query.subject.as_deref().map(parse_key).transpose()?;
+    let votes = state
+        .database
+        .lock()
+        .await
+        .current_votes(voter.as_ref(), subject.as_ref())?;
+    Ok(Json(votes.iter().map(VoteResponse::from).collect()))
+}
+
+/// Users with the highest Wilson score
+#[utoipa::path(
+    get,
+    path = "/leaderboard",
+    params(LeaderboardQuery),
+    responses(
+        (status = 200, description = "Scores of the users, highest first", body = [ScoreResponse]),
+    )
+)]
+pub async fn get_leaderboard(
+    State(state): State<AppState>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<Vec<ScoreResponse>>, ApiError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_LEADERBOARD_LIMIT)
+        .min(MAX_LEADERBOARD_LIMIT);
+    let scores = state.database.lock().await.leaderboard(limit)?;
+    Ok(Json(
+        scores
+            .iter()
+            .map(|(subject, score)| ScoreResponse::new(subject, score))
+            .collect(),
+    ))
+}
+
+/// Every vote cast, changed or undone on a user, oldest first
+#[utoipa::path(
+    get,
+    path = "/history/{subject}",
+    params(("subject" = String, Path, description = "The user, base58 encoded")),
+    responses(
+        (status = 200, description = "History of the votes on the user", body = [VoteResponse]),
+        (status = 400, description = "Invalid public key", body = ErrorResponse),
+    )
+)]
+pub async fn get_history(
+    State(state): State<AppState>,
+    Path(subject): Path<String>,
+) -> Result<Json<Vec<VoteResponse>>, ApiError> {
+    let subject = parse_key(&subject)?;
+    let history = state.database.lock().await.history(&subject)?;
+    Ok(Json(history.iter().map(VoteResponse::from).collect()))
+}
@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use sns_reputation_api::{
+    error::ErrorResponse,
+    router,
+    routes::{ScoreResponse, VoteResponse},
+    AppState,
+};
+use sns_reputation_indexer::{
+    db::{Database, IndexedTransaction},
+    parse::VoteRecord,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use tokio::sync::Mutex;
+use tower::ServiceExt;
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Vec<u8>) {
+    let response = app
+        .clone()
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, bytes.to_vec())
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(app: &Router, uri: &str) -> T {
+    let (status, body) = get(app, uri).await;
+    assert_eq!(status, StatusCode::OK, "{uri}");
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_api() {
+    let [alice, bob, carol, dave] = [0; 4].map(|_| Pubkey::new_unique());
+    let votes = [
+        (&alice, &carol, 1, 3),
+        (&bob, &carol, 1, 1),
+        (&alice, &dave, -1, 2),
+        // Bob changes his vote on Carol
+        (&bob, &carol, -1, 1),
+    ];
+    let mut db = Database::open_in_memory().unwrap();
+    for (slot, (voter, subject, value, weight)) in votes.into_iter().enumerate() {
+        db.index_transaction(&IndexedTransaction {
+            signature: Signature::new_unique(),
+            slot: slot as u64,
+            block_time: None,
+            votes: vec![VoteRecord {
+                instruction_index: 0,
+                voter: *voter,
+                subject: *subject,
+                value,
+                weight: Some(weight),
+            }],
+        })
+        .unwrap();
+    }
+    let app = router(AppState {
+        database: Arc::new(Mutex::new(db)),
+    });
+
+    let score: ScoreResponse = get_json(&app, &format!("/score/{carol}")).await;
+    assert_eq!(
+        (
+            score.upvote,
+            score.downvote,
+            score.upvoters,
+            score.downvoters
+        ),
+        (3, 1, 1, 1)
+    );
+    // Users never voted on have a zero score
+    let score: ScoreResponse = get_json(&app, &format!("/score/{alice}")).await;
+    assert_eq!(
+        (score.upvote, score.downvote, score.wilson_score),
+        (0, 0, 0)
+    );
+
+    let votes: Vec<VoteResponse> = get_json(&app, &format!("/votes?voter={alice}")).await;
+    assert_eq!(
+        votes
+            .iter()
+            .map(|vote| (vote.subject.clone(), vote.value))
+            .collect::<Vec<_>>(),
+        vec![(carol.to_string(), 1), (dave.to_string(), -1)]
+    );
+    let votes: Vec<VoteResponse> =
+        get_json(&app, &format!("/votes?voter={bob}&subject={carol}")).await;
+    assert_eq!(votes.len(), 1);
+    assert_eq!((votes[0].value, votes[0].slot), (-1, 3));
+
+    let leaderboard: Vec<ScoreResponse> = get_json(&app, "/leaderboard").await;
+    assert_eq!(
+        leaderboard
+            .iter()
+            .map(|score| score.subject.clone())
+            .collect::<Vec<_>>(),
+        vec![carol.to_string(), dave.to_string()]
+    );
+    let leaderboard: Vec<ScoreResponse> = get_json(&app, "/leaderboard?limit=1").await;
+    assert_eq!(leaderboard.len(), 1);
+
+    let history: Vec<VoteResponse> = get_json(&app, &format!("/history/{carol}")).await;
+    assert_eq!(
+        history
+            .iter()
+            .map(|vote| (vote.voter.clone(), vote.value))
+            .collect::<Vec<_>>(),
+        vec![
+            (alice.to_string(), 1),
+            (bob.to_string(), 1),
+            (bob.to_string(), -1)
+        ]
+    );
+
+    // Invalid requests
+    for uri in ["/score/not-a-key", "/votes", "/votes?voter=not-a-key"] {
+        let (status, body) = get(&app, uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert!(!error.message.is_empty());
+    }
+
+    let openapi: serde_json::Value = get_json(&app, "/openapi.json").await;
+    for path in [
+        "/score/{subject}",
+        "/votes",
+        "/leaderboard",
+        "/history/{subject}",
+    ] {
+        assert!(openapi["paths"][path]["get"].is_object(), "{path}");
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use solana_client::{rpc_client::RpcClient, rpc_filter::RpcFilterType};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use sns_reputation::state::{
+    reputation_score::ReputationScore,
+    reputation_shard::{ReputationShard, NUM_SHARDS},
+    user_vote::UserVote,
+    Tag,
+};
+use sns_reputation_graph::loader::{
+    parse_reputation_scores, program_accounts_config, reputation_score_batches, tag_filter,
+    votee_filters,
+};
+
+/// The parsed accounts holding the votes and tallies of the program
+#[derive(Debug, Default)]
+pub struct ProgramAccounts {
+    pub scores: Vec<(Pubkey, ReputationScore)>,
+    pub shards: Vec<(Pubkey, ReputationShard)>,
+    pub votes: Vec<(Pubkey, UserVote)>,
+}
+
+impl ProgramAccounts {
+    /// Parses the accounts of the program, skipping the ones that don't hold votes or tallies
+    pub fn parse(accounts: impl IntoIterator<Item = (Pubkey, Account)>) -> Result<Self> {
+        let mut parsed = Self::default();
+        for (key, account) in accounts {
+            let data = &account.data;
+            let tag = data.get(..8).map(|tag| tag[0]);
+            if tag == Some(Tag::ReputationScore as u8) {
+                let score = ReputationScore::from_buffer(data, Tag::ReputationScore)
+                    .with_context(|| format!("Invalid ReputationScore {key}"))?;
+                parsed.scores.push((key, score));
+            } else if tag == Some(Tag::ReputationShard as u8) {
+                let shard = ReputationShard::from_buffer(data, Tag::ReputationShard)
+                    .with_context(|| format!("Invalid ReputationShard {key}"))?;
+                parsed.shards.push((key, shard));
+            } else if tag == Some(Tag::UserVote as u8) {
+                let vote = UserVote::from_buffer(data, Tag::UserVote)
+                    .with_context(|| format!("Invalid UserVote {key}"))?;
+                parsed.votes.push((key, vote));
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+fn program_accounts(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    filters: Vec<RpcFilterType>,
+) -> Result<Vec<(Pubkey, Account)>> {
+    client
+        .get_program_accounts_with_config(program_id, program_accounts_config(filters))
+        .context("Failed to fetch the program accounts")
+}
+
+/// Fetches all the votes and tallies of the program
+pub fn fetch_all(client: &RpcClient, program_id: &Pubkey) -> Result<ProgramAccounts> {
+    let mut accounts = program_accounts(client, program_id, vec![tag_filter(Tag::UserVote)])?;
+    accounts.extend(program_accounts(
+        client,
+        program_id,
+        vec![tag_filter(Tag::ReputationScore)],
+    )?);
+    accounts.extend(program_accounts(
+        client,
+        program_id,
+        vec![tag_filter(Tag::ReputationShard)],
+    )?);
+    ProgramAccounts::parse(accounts)
+}
+
+/// Fetches the votes cast on `votee` and its tallies
+pub fn fetch_votee(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    votee: &Pubkey,
+) -> Result<ProgramAccounts> {
+    let mut accounts = vec![];
+    for filters in votee_filters(votee) {
+        accounts.extend(program_accounts(client, program_id, filters)?);
+    }
+
+    let mut tally_keys = vec![ReputationScore::find_key(program_id, votee).0];
+    tally_keys
+        .extend((0..NUM_SHARDS).map(|shard| ReputationShard::find_key(program_id, votee, shard).0));
+    let tallies = client
+        .get_multiple_accounts(&tally_keys)
+        .context("Failed to fetch the tallies")?;
+    accounts.extend(
+        tally_keys
+            .into_iter()
+            .zip(tallies)
+            .filter_map(|(key, account)| Some((key, account?))),
+    );
+
+    ProgramAccounts::parse(accounts)
+}
+
+/// Fetches the ReputationScore of each of the `users` that has one, by user
+pub fn fetch_scores(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    users: &[Pubkey],
+) -> Result<HashMap<Pubkey, ReputationScore>> {
+    let mut scores = HashMap::new();
+    for (users, keys) in reputation_score_batches(program_id, users) {
+        let accounts = client
+            .get_multiple_accounts(&keys)
+            .context("Failed to fetch the scores")?;
+        scores.extend(parse_reputation_scores(users, &keys, accounts)?);
+    }
+    Ok(scores)
+}
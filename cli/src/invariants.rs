@@ -0,0 +1,209 @@
+//! The tallies of a user, split between its ReputationScore and ReputationShard accounts,
+//! always add up to the weighted values of the UserVote accounts cast on it.
+
+use std::collections::{HashMap, HashSet};
+
+use solana_sdk::pubkey::Pubkey;
+
+use sns_reputation::state::reputation_score::ReputationScore;
+
+use crate::accounts::ProgramAccounts;
+
+/// Upvote and downvote totals, wide enough to sum tallies without overflowing
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tallies {
+    pub upvote: i128,
+    pub downvote: i128,
+}
+
+impl Tallies {
+    fn add_vote(&mut self, value: i64) {
+        if value > 0 {
+            self.upvote += value as i128;
+        } else {
+            self.downvote -= value as i128;
+        }
+    }
+
+    fn add(&mut self, upvote: i128, downvote: i128) {
+        self.upvote += upvote;
+        self.downvote += downvote;
+    }
+}
+
+/// A ReputationScore whose tallies don't match the votes
+#[derive(Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The ReputationScore PDA
+    pub score_key: Pubkey,
+    /// The user voted on, unknown for a score without any vote or shard
+    pub votee: Option<Pubkey>,
+    /// Tallies recomputed from the UserVote accounts
+    pub expected: Tallies,
+    /// Tallies of the ReputationScore and the ReputationShard accounts
+    pub actual: Tallies,
+}
+
+/// Recomputes the tallies of every user from the votes cast on it and returns the users whose
+/// ReputationScore and ReputationShard accounts don't add up to them
+pub fn check_invariants(program_id: &Pubkey, accounts: &ProgramAccounts) -> Vec<Mismatch> {
+    let mut expected = HashMap::<Pubkey, Tallies>::new();
+    for (_, vote) in &accounts.votes {
+        expected.entry(vote.votee).or_default().add_vote(vote.value);
+    }
+
+    let mut sharded = HashMap::<Pubkey, Tallies>::new();
+    for (_, shard) in &accounts.shards {
+        sharded
+            .entry(shard.user_key)
+            .or_default()
+            .add(shard.upvote as i128, shard.downvote as i128);
+    }
+
+    let scores = accounts
+        .scores
+        .iter()
+        .map(|(key, score)| (*key, score))
+        .collect::<HashMap<_, _>>();
+
+    let votees = expected
+        .keys()
+        .chain(sharded.keys())
+        .copied()
+        .collect::<HashSet<_>>();
+
+    let mut checked_scores = HashSet::new();
+    let mut mismatches = vec![];
+    for votee in votees {
+        let (score_key, _) = ReputationScore::find_key(program_id, &votee);
+        checked_scores.insert(score_key);
+
+        let mut actual = sharded.get(&votee).copied().unwrap_or_default();
+        if let Some(score) = scores.get(&score_key) {
+            actual.add(score.upvote as i128, score.downvote as i128);
+        }
+        let expected = expected.get(&votee).copied().unwrap_or_default();
+        if actual != expected {
+            mismatches.push(Mismatch {
+                score_key,
+                votee: Some(votee),
+                expected,
+                actual,
+            });
+        }
+    }
+
+    // Scores of users that nobody votes on anymore have to be empty
+    for (score_key, score) in &accounts.scores {
+        if checked_scores.contains(score_key) || (score.upvote == 0 && score.downvote == 0) {
+            continue;
+        }
+        let mut actual = Tallies::default();
+        actual.add(score.upvote as i128, score.downvote as i128);
+        mismatches.push(Mismatch {
+            score_key: *score_key,
+            votee: None,
+            expected: Tallies::default(),
+            actual,
+        });
+    }
+
+    mismatches.sort_by_key(|mismatch| mismatch.score_key);
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use sns_reputation::state::{reputation_shard::ReputationShard, user_vote::UserVote};
+
+    use super::*;
+
+    fn vote(votee: Pubkey, value: i64) -> (Pubkey, UserVote) {
+        (
+            Pubkey::new_unique(),
+            UserVote {
+                value,
+                votee,
+                voter: Pubkey::new_unique(),
+                ..UserVote::default()
+            },
+        )
+    }
+
+    fn score(
+        program_id: &Pubkey,
+        votee: &Pubkey,
+        upvote: u64,
+        downvote: u64,
+    ) -> (Pubkey, ReputationScore) {
+        let (key, nonce) = ReputationScore::find_key(program_id, votee);
+        (
+            key,
+            ReputationScore {
+                nonce,
+                upvote,
+                downvote,
+                ..ReputationScore::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_check_invariants() {
+        let program_id = Pubkey::new_unique();
+        let consistent = Pubkey::new_unique();
+        let drifted = Pubkey::new_unique();
+        let forgotten = Pubkey::new_unique();
+
+        let accounts = ProgramAccounts {
+            scores: vec![
+                score(&program_id, &consistent, 3, 0),
+                score(&program_id, &drifted, 5, 1),
+                score(&program_id, &forgotten, 1, 0),
+            ],
+            shards: vec![(
+                Pubkey::new_unique(),
+                ReputationShard {
+                    user_key: consistent,
+                    upvote: -1,
+                    downvote: 2,
+                    ..ReputationShard::default()
+                },
+            )],
+            votes: vec![
+                vote(consistent, 2),
+                vote(consistent, -2),
+                vote(drifted, 5),
+                // Pending commitments don't count yet
+                vote(drifted, 0),
+            ],
+        };
+
+        let mismatches = check_invariants(&program_id, &accounts);
+        let mut expected = vec![
+            Mismatch {
+                score_key: score(&program_id, &drifted, 0, 0).0,
+                votee: Some(drifted),
+                expected: Tallies {
+                    upvote: 5,
+                    downvote: 0,
+                },
+                actual: Tallies {
+                    upvote: 5,
+                    downvote: 1,
+                },
+            },
+            Mismatch {
+                score_key: score(&program_id, &forgotten, 0, 0).0,
+                votee: None,
+                expected: Tallies::default(),
+                actual: Tallies {
+                    upvote: 1,
+                    downvote: 0,
+                },
+            },
+        ];
+        expected.sort_by_key(|mismatch| mismatch.score_key);
+        assert_eq!(mismatches, expected);
+    }
+}
@@ -0,0 +1,4 @@
+//! Off-chain tooling for the SNS Reputation program.
+
+pub mod accounts;
+pub mod invariants;
@@ -0,0 +1,440 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use anyhow::Result;
+use clap::{Parser, Subcommand, ValueEnum};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+use sns_reputation_cli::{accounts, invariants::check_invariants};
+use sns_reputation_graph::{
+    collusion::{detect_collusion, CollusionConfig},
+    eigentrust::{eigentrust, EigenTrustConfig},
+    explain::{explain_score, VoteOrigin},
+    loader,
+    pagerank::{signed_pagerank, PageRankConfig},
+    paths::{shortest_path, strongest_path},
+    TrustGraph,
+};
+
+#[derive(Parser)]
+#[command(about = "Command line tools for the SNS Reputation program")]
+struct Cli {
+    /// RPC node to read the accounts from
+    #[arg(
+        long,
+        short,
+        env = "RPC_URL",
+        default_value = "https://api.mainnet-beta.solana.com"
+    )]
+    url: String,
+
+    /// Address of the program
+    #[arg(long, default_value_t = sns_reputation::ID)]
+    program_id: Pubkey,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Recompute the ReputationScore tallies from the UserVote accounts and report mismatches
+    CheckInvariants {
+        /// Only check the tallies of this user
+        #[arg(long)]
+        votee: Option<Pubkey>,
+    },
+    /// Write the UserVote accounts to a snapshot file, for offline analysis
+    ExportVotes {
+        /// File to write the snapshot to
+        output: PathBuf,
+    },
+    /// List the votes making up the score of a user, heaviest first
+    ExplainScore {
+        subject: Pubkey,
+        /// Number of votes to print
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Compute the transitive trust of every wallet from the graph of the votes
+    TrustScores {
+        /// Read the votes from a snapshot written by `export-votes` instead of the RPC node
+        #[arg(long)]
+        snapshot: Option<PathBuf>,
+        /// Pre-trusted wallet, can be repeated. Every wallet is pre-trusted when none is given
+        #[arg(long = "seed")]
+        seeds: Vec<Pubkey>,
+        #[arg(long, value_enum, default_value_t = Algorithm::EigenTrust)]
+        algorithm: Algorithm,
+        /// Number of wallets to print, highest scores first
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Find a chain of votes leading from trusted wallets to another one
+    TrustPath {
+        /// Wallet the path is searched for
+        to: Pubkey,
+        /// Trusted wallet the path can start from, can be repeated
+        #[arg(long = "from", required = true)]
+        from: Vec<Pubkey>,
+        /// Find the path carrying the largest share of the votes rather than the fewest hops
+        #[arg(long)]
+        strongest: bool,
+        /// Read the votes from a snapshot written by `export-votes` instead of the RPC node
+        #[arg(long)]
+        snapshot: Option<PathBuf>,
+    },
+    /// Write the stake accounts of the voters to a snapshot file, for `detect-collusion`
+    ExportStakes {
+        /// File to write the snapshot to
+        output: PathBuf,
+        /// Read the voters from a snapshot written by `export-votes` instead of the RPC node
+        #[arg(long)]
+        snapshot: Option<PathBuf>,
+    },
+    /// Report the clusters of wallets suspected of manipulating the votes together
+    DetectCollusion {
+        /// Read the votes from a snapshot written by `export-votes` instead of the RPC node
+        #[arg(long)]
+        snapshot: Option<PathBuf>,
+        /// Stake accounts written by `export-stakes`, needed to find voters sharing a withdrawer
+        #[arg(long)]
+        stakes: Option<PathBuf>,
+        /// Voters are reported as similar when at least this share of their votes are the same
+        #[arg(long, default_value_t = CollusionConfig::default().min_similarity)]
+        min_similarity: f64,
+        /// Voters with fewer votes are never reported as similar
+        #[arg(long, default_value_t = CollusionConfig::default().min_votes)]
+        min_votes: usize,
+        /// Wallets with more voters are only used to refine the similarity of voters found to
+        /// overlap on other wallets, bounding the time taken to compare voters
+        #[arg(long, default_value_t = CollusionConfig::default().max_voters_per_votee)]
+        max_voters_per_votee: usize,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Algorithm {
+    /// Trust flowing along the upvotes only
+    EigenTrust,
+    /// Trust flowing along the upvotes, minus the distrust of the downvotes
+    SignedPagerank,
+}
+
+fn check_invariants_command(cli: &Cli, votee: Option<Pubkey>) -> Result<ExitCode> {
+    let client = RpcClient::new(cli.url.clone());
+    let accounts = match votee {
+        Some(votee) => accounts::fetch_votee(&client, &cli.program_id, &votee)?,
+        None => accounts::fetch_all(&client, &cli.program_id)?,
+    };
+
+    let mismatches = check_invariants(&cli.program_id, &accounts);
+    for mismatch in &mismatches {
+        let votee = mismatch
+            .votee
+            .map_or_else(|| "unknown votee".to_string(), |votee| votee.to_string());
+        println!(
+            "{} ({}): expected {} up / {} down from the votes, found {} up / {} down",
+            mismatch.score_key,
+            votee,
+            mismatch.expected.upvote,
+            mismatch.expected.downvote,
+            mismatch.actual.upvote,
+            mismatch.actual.downvote,
+        );
+    }
+    println!(
+        "Checked {} votes, {} scores and {} shards: {} mismatches",
+        accounts.votes.len(),
+        accounts.scores.len(),
+        accounts.shards.len(),
+        mismatches.len()
+    );
+
+    Ok(if mismatches.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn explain_score_command(cli: &Cli, subject: &Pubkey, limit: usize) -> Result<ExitCode> {
+    let client = RpcClient::new(cli.url.clone());
+    let votes = accounts::fetch_votee(&client, &cli.program_id, subject)?
+        .votes
+        .into_iter()
+        .map(|(_, vote)| vote)
+        .collect::<Vec<_>>();
+    let voters = votes.iter().map(|vote| vote.voter).collect::<Vec<_>>();
+    let voter_scores = accounts::fetch_scores(&client, &cli.program_id, &voters)?;
+    let slot = client.get_slot()?;
+    let breakdown = explain_score(subject, &votes, &voter_scores, slot);
+
+    println!(
+        "{}: {} up / {} down from {} votes",
+        subject,
+        breakdown.upvote,
+        breakdown.downvote,
+        breakdown.contributors.len()
+    );
+    for contributor in breakdown.contributors.iter().take(limit) {
+        let tally = if contributor.weight > 0 {
+            "upvotes"
+        } else {
+            "downvotes"
+        };
+        let age = contributor.age_slots.map_or_else(
+            || "age unknown".to_string(),
+            |age| format!("{age} slots old"),
+        );
+        let origin = match contributor.origin {
+            VoteOrigin::Direct => "direct".to_string(),
+            VoteOrigin::Curator(curator) => format!("following {curator}"),
+        };
+        let voter_score = contributor.voter_score.map_or_else(
+            || "voter unscored".to_string(),
+            |score| {
+                format!(
+                    "voter score {} up / {} down, Wilson {}",
+                    score.upvote, score.downvote, score.wilson_score
+                )
+            },
+        );
+        println!(
+            "{} {:+} ({:.1}% of the {}), {}, {}, {}",
+            contributor.voter,
+            contributor.weight,
+            100.0 * contributor.share,
+            tally,
+            age,
+            origin,
+            voter_score
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn export_votes_command(cli: &Cli, output: &Path) -> Result<ExitCode> {
+    let client = RpcClient::new(cli.url.clone());
+    let accounts = loader::fetch_vote_accounts(&client, &cli.program_id)?;
+    loader::write_snapshot(output, &accounts)?;
+    println!("Wrote {} votes to {}", accounts.len(), output.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Loads the graph of the votes from `snapshot`, or from the RPC node when none is given
+fn load_trust_graph(cli: &Cli, snapshot: Option<&Path>) -> Result<TrustGraph> {
+    let accounts = match snapshot {
+        Some(path) => loader::read_snapshot(path)?,
+        None => {
+            let client = RpcClient::new(cli.url.clone());
+            loader::fetch_vote_accounts(&client, &cli.program_id)?
+        }
+    };
+    let votes = loader::parse_votes(&accounts)?;
+    Ok(TrustGraph::from_votes(&votes))
+}
+
+fn trust_scores_command(
+    cli: &Cli,
+    snapshot: Option<&Path>,
+    seeds: &[Pubkey],
+    algorithm: Algorithm,
+    limit: usize,
+) -> Result<ExitCode> {
+    let graph = load_trust_graph(cli, snapshot)?;
+    for seed in seeds.iter().filter(|seed| graph.index_of(seed).is_none()) {
+        eprintln!("Warning: the seed {seed} never voted nor was voted on, it is ignored");
+    }
+    let (scores, iterations, converged) = match algorithm {
+        Algorithm::EigenTrust => {
+            let result = eigentrust(&graph, seeds, EigenTrustConfig::default())?;
+            (result.scores, result.iterations, result.converged)
+        }
+        Algorithm::SignedPagerank => {
+            let result = signed_pagerank(&graph, seeds, PageRankConfig::default())?;
+            (result.scores(), result.iterations, result.converged)
+        }
+    };
+    if !converged {
+        eprintln!("Warning: the scores did not converge after {iterations} iterations");
+    }
+
+    for (wallet, score) in graph.ranked(&scores).into_iter().take(limit) {
+        println!("{wallet} {score:.6e}");
+    }
+    println!(
+        "Ranked {} wallets from {} votes in {} iterations",
+        graph.len(),
+        graph.edges().len(),
+        iterations
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn trust_path_command(
+    cli: &Cli,
+    to: &Pubkey,
+    from: &[Pubkey],
+    strongest: bool,
+    snapshot: Option<&Path>,
+) -> Result<ExitCode> {
+    let graph = load_trust_graph(cli, snapshot)?;
+    let path = if strongest {
+        strongest_path(&graph, from, to)
+    } else {
+        shortest_path(&graph, from, to)
+    };
+    let Some(path) = path else {
+        println!("No trust path leads to {to}");
+        return Ok(ExitCode::FAILURE);
+    };
+
+    for hop in &path.hops {
+        println!(
+            "{} -> {} {:+} ({:.1}% of the voting weight of the voter)",
+            hop.voter,
+            hop.votee,
+            hop.weight,
+            100.0 * hop.share
+        );
+    }
+    println!(
+        "{} path of {} hops with a strength of {:.6e}",
+        if path.is_distrust() {
+            "Distrust"
+        } else {
+            "Trust"
+        },
+        path.hops.len(),
+        path.strength()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn export_stakes_command(cli: &Cli, output: &Path, snapshot: Option<&Path>) -> Result<ExitCode> {
+    let graph = load_trust_graph(cli, snapshot)?;
+    let voters = graph
+        .edges()
+        .iter()
+        .map(|edge| graph.nodes()[edge.from])
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let client = RpcClient::new(cli.url.clone());
+    let accounts = loader::fetch_stake_accounts(&client, &voters)?;
+    loader::write_snapshot(output, &accounts)?;
+    println!(
+        "Wrote {} stake accounts of {} voters to {}",
+        accounts.len(),
+        voters.len(),
+        output.display()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn detect_collusion_command(
+    cli: &Cli,
+    snapshot: Option<&Path>,
+    stakes: Option<&Path>,
+    config: CollusionConfig,
+) -> Result<ExitCode> {
+    let graph = load_trust_graph(cli, snapshot)?;
+    let stake_accounts = match stakes {
+        Some(path) => loader::parse_stake_accounts(&loader::read_snapshot(path)?),
+        None => vec![],
+    };
+    let report = detect_collusion(&graph, &stake_accounts, config);
+
+    for ring in &report.rings {
+        println!(
+            "Vote ring of {} wallets: {} upvotes between them, {} mutual pairs, {:.1}% of their upvotes from inside the ring",
+            ring.members.len(),
+            ring.internal_upvotes,
+            ring.mutual_pairs,
+            100.0 * ring.internal_share
+        );
+        for member in &ring.members {
+            println!("  {member}");
+        }
+    }
+    for group in &report.similar_voters {
+        println!(
+            "{} voters casting near-identical votes:",
+            group.voters.len()
+        );
+        for (a, b, similarity) in &group.pairs {
+            println!("  {a} {b} {:.1}% identical", 100.0 * similarity);
+        }
+    }
+    for shared in &report.shared_withdrawers {
+        println!(
+            "{} voters staking with withdrawer {}:",
+            shared.voters.len(),
+            shared.withdrawer
+        );
+        for voter in &shared.voters {
+            println!("  {voter}");
+        }
+        for stake_account in &shared.stake_accounts {
+            println!("  stake account {stake_account}");
+        }
+    }
+    if stakes.is_none() {
+        eprintln!("Warning: no stake accounts given, shared withdrawers were not checked");
+    }
+    println!(
+        "Analysed {} votes of {} wallets: {} rings, {} groups of similar voters, {} shared withdrawers",
+        graph.edges().len(),
+        graph.len(),
+        report.rings.len(),
+        report.similar_voters.len(),
+        report.shared_withdrawers.len()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    match &cli.command {
+        Command::CheckInvariants { votee } => check_invariants_command(&cli, *votee),
+        Command::ExplainScore { subject, limit } => explain_score_command(&cli, subject, *limit),
+        Command::ExportVotes { output } => export_votes_command(&cli, output),
+        Command::TrustScores {
+            snapshot,
+            seeds,
+            algorithm,
+            limit,
+        } => trust_scores_command(&cli, snapshot.as_deref(), seeds, *algorithm, *limit),
+        Command::TrustPath {
+            to,
+            from,
+            strongest,
+            snapshot,
+        } => trust_path_command(&cli, to, from, *strongest, snapshot.as_deref()),
+        Command::ExportStakes { output, snapshot } => {
+            export_stakes_command(&cli, output, snapshot.as_deref())
+        }
+        Command::DetectCollusion {
+            snapshot,
+            stakes,
+            min_similarity,
+            min_votes,
+            max_voters_per_votee,
+        } => detect_collusion_command(
+            &cli,
+            snapshot.as_deref(),
+            stakes.as_deref(),
+            CollusionConfig {
+                min_similarity: *min_similarity,
+                min_votes: *min_votes,
+                max_voters_per_votee: *max_voters_per_votee,
+                ..CollusionConfig::default()
+            },
+        ),
+    }
+}
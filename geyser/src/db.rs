@@ -0,0 +1,517 @@
+//! SQLite tables mirroring the UserVote, ReputationScore and ReputationShard accounts of the
+//! program. Rows are keyed by account, ReputationScore accounts being found from their user with
+//! `ReputationScore::find_key`. The votes landing in the shards of a user only reach its
+//! ReputationScore once snapshotted, `Database::current_score` adds them to it.
+//!
+//! Closed accounts leave a tombstone with the slot and write version of the closing write, so
+//! that the writes notified out of order before it don't bring the rows back.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+use sns_reputation::state::{
+    reputation_score::ReputationScore, reputation_shard::ReputationShard, user_vote::UserVote, Tag,
+};
+
+const SCHEMA: &str = "
+PRAGMA journal_mode = WAL;
+PRAGMA synchronous = NORMAL;
+
+CREATE TABLE IF NOT EXISTS user_votes (
+    key TEXT PRIMARY KEY,
+    voter TEXT NOT NULL,
+    votee TEXT NOT NULL,
+    value INTEGER NOT NULL,
+    commit_slot INTEGER NOT NULL,
+    followed_curator TEXT,
+    credibility_multiplier INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    write_version INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS user_votes_voter ON user_votes (voter);
+CREATE INDEX IF NOT EXISTS user_votes_votee ON user_votes (votee);
+
+CREATE TABLE IF NOT EXISTS reputation_scores (
+    key TEXT PRIMARY KEY,
+    upvote INTEGER NOT NULL,
+    downvote INTEGER NOT NULL,
+    upvoters INTEGER NOT NULL,
+    downvoters INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    write_version INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS reputation_shards (
+    key TEXT PRIMARY KEY,
+    user_key TEXT NOT NULL,
+    shard INTEGER NOT NULL,
+    upvote INTEGER NOT NULL,
+    downvote INTEGER NOT NULL,
+    upvoters INTEGER NOT NULL,
+    downvoters INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    write_version INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS reputation_shards_user_key ON reputation_shards (user_key);
+
+CREATE TABLE IF NOT EXISTS closed_accounts (
+    key TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL,
+    write_version INTEGER NOT NULL
+);
+";
+
+/// Tables holding a row per mirrored account
+const ACCOUNT_TABLES: [&str; 3] = ["user_votes", "reputation_scores", "reputation_shards"];
+
+#[derive(Debug, Error)]
+pub enum DatabaseError {
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("Invalid program account {0}")]
+    InvalidAccount(Pubkey),
+    #[error("The tallies of {0} overflow")]
+    Overflow(Pubkey),
+}
+
+pub struct Database {
+    connection: Connection,
+}
+
+impl Database {
+    /// Opens the database at `path`, creating the tables if needed
+    pub fn open(path: &Path) -> Result<Self, DatabaseError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, DatabaseError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> Result<Self, DatabaseError> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Mirrors the program account `key` holding `data`, written at `slot`. Accounts holding
+    /// other data than votes, scores or shards are ignored, as well as updates older than the
+    /// row or than the closing of the account
+    pub fn upsert_account(
+        &self,
+        key: &Pubkey,
+        data: &[u8],
+        slot: u64,
+        write_version: u64,
+    ) -> Result<(), DatabaseError> {
+        if self.is_closed_since(key, slot, write_version)? {
+            return Ok(());
+        }
+        let tag = data.get(..8);
+        if tag == Some(&(Tag::UserVote as u64).to_le_bytes()[..]) {
+            let vote = UserVote::from_buffer(data, Tag::UserVote)
+                .map_err(|_| DatabaseError::InvalidAccount(*key))?;
+            self.upsert_vote(key, &vote, slot, write_version)
+        } else if tag == Some(&(Tag::ReputationScore as u64).to_le_bytes()[..]) {
+            let score = ReputationScore::from_buffer(data, Tag::ReputationScore)
+                .map_err(|_| DatabaseError::InvalidAccount(*key))?;
+            self.upsert_score(key, &score, slot, write_version)
+        } else if tag == Some(&(Tag::ReputationShard as u64).to_le_bytes()[..]) {
+            let shard = ReputationShard::from_buffer(data, Tag::ReputationShard)
+                .map_err(|_| DatabaseError::InvalidAccount(*key))?;
+            self.upsert_shard(key, &shard, slot, write_version)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns true if the account `key` was closed by a write newer than the given one
+    fn is_closed_since(
+        &self,
+        key: &Pubkey,
+        slot: u64,
+        write_version: u64,
+    ) -> Result<bool, DatabaseError> {
+        let closed = self
+            .connection
+            .query_row(
+                "SELECT 1 FROM closed_accounts WHERE key = ?1
+                    AND (slot, write_version) > (?2, ?3)",
+                params![key.to_string(), slot, write_version],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(closed.is_some())
+    }
+
+    fn upsert_vote(
+        &self,
+        key: &Pubkey,
+        vote: &UserVote,
+        slot: u64,
+        write_version: u64,
+    ) -> Result<(), DatabaseError> {
+        let followed_curator = vote
+            .is_followed()
+            .then(|| vote.followed_curator.to_string());
+        self.connection.execute(
+            "INSERT INTO user_votes (key, voter, votee, value, commit_slot, followed_curator,
+                credibility_multiplier, slot, write_version)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ON CONFLICT (key) DO UPDATE SET
+                voter = excluded.voter,
+                votee = excluded.votee,
+                value = excluded.value,
+                commit_slot = excluded.commit_slot,
+                followed_curator = excluded.followed_curator,
+                credibility_multiplier = excluded.credibility_multiplier,
+                slot = excluded.slot,
+                write_version = excluded.write_version
+            WHERE (excluded.slot, excluded.write_version)
+                > (user_votes.slot, user_votes.write_version)",
+            params![
+                key.to_string(),
+                vote.voter.to_string(),
+                vote.votee.to_string(),
+                vote.value,
+                vote.commit_slot,
+                followed_curator,
+                vote.credibility_multiplier,
+                slot,
+                write_version,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn upsert_score(
+        &self,
+        key: &Pubkey,
+        score: &ReputationScore,
+        slot: u64,
+        write_version: u64,
+    ) -> Result<(), DatabaseError> {
+        self.connection.execute(
+            "INSERT INTO reputation_scores (key, upvote, downvote, upvoters, downvoters, slot,
+                write_version)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT (key) DO UPDATE SET
+                upvote = excluded.upvote,
+                downvote = excluded.downvote,
+                upvoters = excluded.upvoters,
+                downvoters = excluded.downvoters,
+                slot = excluded.slot,
+                write_version = excluded.write_version
+            WHERE (excluded.slot, excluded.write_version)
+                > (reputation_scores.slot, reputation_scores.write_version)",
+            params![
+                key.to_string(),
+                score.upvote,
+                score.downvote,
+                score.upvoters,
+                score.downvoters,
+                slot,
+                write_version,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn upsert_shard(
+        &self,
+        key: &Pubkey,
+        shard: &ReputationShard,
+        slot: u64,
+        write_version: u64,
+    ) -> Result<(), DatabaseError> {
+        self.connection.execute(
+            "INSERT INTO reputation_shards (key, user_key, shard, upvote, downvote, upvoters,
+                downvoters, slot, write_version)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ON CONFLICT (key) DO UPDATE SET
+                user_key = excluded.user_key,
+                shard = excluded.shard,
+                upvote = excluded.upvote,
+                downvote = excluded.downvote,
+                upvoters = excluded.upvoters,
+                downvoters = excluded.downvoters,
+                slot = excluded.slot,
+                write_version = excluded.write_version
+            WHERE (excluded.slot, excluded.write_version)
+                > (reputation_shards.slot, reputation_shards.write_version)",
+            params![
+                key.to_string(),
+                shard.user_key.to_string(),
+                shard.shard,
+                shard.upvote,
+                shard.downvote,
+                shard.upvoters,
+                shard.downvoters,
+                slot,
+                write_version,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Removes the rows of an account closed at `slot`, unless they were written after it, and
+    /// keeps a tombstone so that older writes notified later are ignored
+    pub fn delete_account(
+        &self,
+        key: &Pubkey,
+        slot: u64,
+        write_version: u64,
+    ) -> Result<(), DatabaseError> {
+        let key = key.to_string();
+        self.connection.execute(
+            "INSERT INTO closed_accounts (key, slot, write_version)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT (key) DO UPDATE SET
+                slot = excluded.slot,
+                write_version = excluded.write_version
+            WHERE (excluded.slot, excluded.write_version)
+                > (closed_accounts.slot, closed_accounts.write_version)",
+            params![key, slot, write_version],
+        )?;
+        for table in ACCOUNT_TABLES {
+            self.connection.execute(
+                &format!("DELETE FROM {table} WHERE key = ?1 AND (slot, write_version) < (?2, ?3)"),
+                params![key, slot, write_version],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Tallies of the votes currently cast on `user`: its ReputationScore with the votes
+    /// landing in its shards since the last snapshot added
+    pub fn current_score(
+        &self,
+        program_id: &Pubkey,
+        user: &Pubkey,
+    ) -> Result<ReputationScore, DatabaseError> {
+        let (key, nonce) = ReputationScore::find_key(program_id, user);
+        let mut score = self
+            .connection
+            .query_row(
+                "SELECT upvote, downvote, upvoters, downvoters FROM reputation_scores
+                WHERE key = ?1",
+                [key.to_string()],
+                |row| {
+                    Ok(ReputationScore {
+                        nonce,
+                        upvote: row.get(0)?,
+                        downvote: row.get(1)?,
+                        upvoters: row.get(2)?,
+                        downvoters: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?
+            .unwrap_or(ReputationScore {
+                nonce,
+                ..ReputationScore::default()
+            });
+        let mut pending = self.connection.query_row(
+            "SELECT COALESCE(SUM(upvote), 0), COALESCE(SUM(downvote), 0),
+                COALESCE(SUM(upvoters), 0), COALESCE(SUM(downvoters), 0)
+            FROM reputation_shards WHERE user_key = ?1",
+            [user.to_string()],
+            |row| {
+                Ok(ReputationShard {
+                    upvote: row.get(0)?,
+                    downvote: row.get(1)?,
+                    upvoters: row.get(2)?,
+                    downvoters: row.get(3)?,
+                    ..ReputationShard::default()
+                })
+            },
+        )?;
+        pending
+            .aggregate_into(&mut score)
+            .map_err(|_| DatabaseError::Overflow(*user))?;
+        Ok(score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vote_data(vote: &UserVote) -> Vec<u8> {
+        let mut data = vec![0; UserVote::space()];
+        vote.save(&mut data).unwrap();
+        data
+    }
+
+    fn shard_data(shard: &ReputationShard) -> Vec<u8> {
+        // Tag, nonce, user, index and tallies
+        let mut data = vec![0; 8 + 1 + 32 + 1 + 4 * 8];
+        shard.save(&mut data).unwrap();
+        data
+    }
+
+    fn stored_vote(db: &Database, key: &Pubkey) -> Option<(i64, Option<String>, u64)> {
+        db.connection()
+            .query_row(
+                "SELECT value, followed_curator, slot FROM user_votes WHERE key = ?1",
+                [key.to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .ok()
+    }
+
+    #[test]
+    fn test_upsert_and_delete() {
+        let db = Database::open_in_memory().unwrap();
+        let (vote_key, score_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let curator = Pubkey::new_unique();
+        let vote = UserVote {
+            value: 3,
+            votee: Pubkey::new_unique(),
+            voter: Pubkey::new_unique(),
+            ..UserVote::default()
+        };
+
+        db.upsert_account(&vote_key, &vote_data(&vote), 10, 1)
+            .unwrap();
+        assert_eq!(stored_vote(&db, &vote_key), Some((3, None, 10)));
+
+        let changed = UserVote {
+            value: -3,
+            followed_curator: curator,
+            ..vote
+        };
+        db.upsert_account(&vote_key, &vote_data(&changed), 12, 5)
+            .unwrap();
+        // Updates older than the row are ignored
+        db.upsert_account(&vote_key, &vote_data(&vote), 11, 9)
+            .unwrap();
+        assert_eq!(
+            stored_vote(&db, &vote_key),
+            Some((-3, Some(curator.to_string()), 12))
+        );
+
+        let score = ReputationScore {
+            upvote: 7,
+            downvote: 2,
+            upvoters: 3,
+            downvoters: 1,
+            ..ReputationScore::default()
+        };
+        let mut data = vec![0; ReputationScore::space()];
+        score.save(&mut data).unwrap();
+        db.upsert_account(&score_key, &data, 12, 6).unwrap();
+        let stored: (u64, u64, u64, u64) = db
+            .connection()
+            .query_row(
+                "SELECT upvote, downvote, upvoters, downvoters FROM reputation_scores
+                WHERE key = ?1",
+                [score_key.to_string()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(stored, (7, 2, 3, 1));
+
+        // Other accounts of the program aren't mirrored
+        let other = Pubkey::new_unique();
+        db.upsert_account(&other, &(Tag::VoterProfile as u64).to_le_bytes(), 12, 7)
+            .unwrap();
+        assert!(matches!(
+            db.upsert_account(&other, &(Tag::UserVote as u64).to_le_bytes(), 12, 8),
+            Err(DatabaseError::InvalidAccount(key)) if key == other
+        ));
+
+        db.delete_account(&vote_key, 13, 10).unwrap();
+        db.delete_account(&score_key, 13, 11).unwrap();
+        assert_eq!(stored_vote(&db, &vote_key), None);
+        let scores: u64 = db
+            .connection()
+            .query_row("SELECT COUNT(*) FROM reputation_scores", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(scores, 0);
+
+        // Writes older than the closing of the account are ignored when notified after it
+        db.upsert_account(&vote_key, &vote_data(&changed), 12, 9)
+            .unwrap();
+        assert_eq!(stored_vote(&db, &vote_key), None);
+
+        // The account is created again, an older closing notified after it being ignored
+        db.upsert_account(&vote_key, &vote_data(&vote), 14, 12)
+            .unwrap();
+        db.delete_account(&vote_key, 13, 12).unwrap();
+        assert_eq!(stored_vote(&db, &vote_key), Some((3, None, 14)));
+    }
+
+    #[test]
+    fn test_current_score() {
+        let db = Database::open_in_memory().unwrap();
+        let program_id = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        assert_eq!(
+            db.current_score(&program_id, &user).unwrap(),
+            ReputationScore {
+                nonce: ReputationScore::find_key(&program_id, &user).1,
+                ..ReputationScore::default()
+            }
+        );
+
+        let (score_key, nonce) = ReputationScore::find_key(&program_id, &user);
+        let score = ReputationScore {
+            nonce,
+            upvote: 7,
+            downvote: 2,
+            upvoters: 3,
+            downvoters: 1,
+        };
+        let mut data = vec![0; ReputationScore::space()];
+        score.save(&mut data).unwrap();
+        db.upsert_account(&score_key, &data, 10, 1).unwrap();
+
+        // An upvote landed in a shard, and the downvote aggregated before was removed from
+        // another one
+        let shards = [(0, 2, 0, 1, 0), (3, 0, -2, 0, -1)].map(
+            |(shard, upvote, downvote, upvoters, downvoters)| ReputationShard {
+                nonce: 0,
+                user_key: user,
+                shard,
+                upvote,
+                downvote,
+                upvoters,
+                downvoters,
+            },
+        );
+        for (write_version, shard) in shards.iter().enumerate() {
+            let key = ReputationShard::find_key(&program_id, &user, shard.shard).0;
+            db.upsert_account(&key, &shard_data(shard), 11, write_version as u64)
+                .unwrap();
+        }
+        // The shards of other users aren't counted
+        let other_shard = ReputationShard {
+            user_key: Pubkey::new_unique(),
+            upvote: 5,
+            upvoters: 1,
+            ..ReputationShard::default()
+        };
+        db.upsert_account(&Pubkey::new_unique(), &shard_data(&other_shard), 11, 5)
+            .unwrap();
+
+        assert_eq!(
+            db.current_score(&program_id, &user).unwrap(),
+            ReputationScore {
+                nonce,
+                upvote: 9,
+                downvote: 0,
+                upvoters: 4,
+                downvoters: 0,
+            }
+        );
+    }
+}
@@ -0,0 +1,21 @@
+//! Geyser plugin mirroring the UserVote and ReputationScore accounts of the program into a
+//! SQLite database as the validator writes them, closed accounts being removed.
+
+pub mod db;
+pub mod plugin;
+
+use solana_geyser_plugin_interface::geyser_plugin_interface::GeyserPlugin;
+
+use crate::plugin::ReputationPlugin;
+
+/// Entrypoint looked up by the validator when loading the plugin
+///
+/// # Safety
+///
+/// The validator takes ownership of the returned plugin
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn _create_plugin() -> *mut dyn GeyserPlugin {
+    let plugin: Box<dyn GeyserPlugin> = Box::<ReputationPlugin>::default();
+    Box::into_raw(plugin)
+}
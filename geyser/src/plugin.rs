@@ -0,0 +1,200 @@
+use std::{fs::File, path::PathBuf, str::FromStr, sync::Mutex};
+
+use serde::Deserialize;
+use solana_geyser_plugin_interface::geyser_plugin_interface::{
+    GeyserPlugin, GeyserPluginError, ReplicaAccountInfoVersions, Result,
+};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::db::Database;
+
+/// Configuration file given to the validator with `--geyser-plugin-config`
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    /// SQLite database the accounts are written to, created if needed
+    pub database: PathBuf,
+    /// Address of the program, the mainnet deployment by default
+    pub program_id: Option<String>,
+}
+
+#[derive(Default)]
+pub struct ReputationPlugin {
+    program_id: Pubkey,
+    // The validator may notify from several threads
+    database: Option<Mutex<Database>>,
+}
+
+impl std::fmt::Debug for ReputationPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReputationPlugin")
+            .field("program_id", &self.program_id)
+            .finish()
+    }
+}
+
+fn config_error(msg: impl ToString) -> GeyserPluginError {
+    GeyserPluginError::ConfigFileReadError {
+        msg: msg.to_string(),
+    }
+}
+
+fn update_error(msg: impl ToString) -> GeyserPluginError {
+    GeyserPluginError::AccountsUpdateError {
+        msg: msg.to_string(),
+    }
+}
+
+impl ReputationPlugin {
+    /// Mirrors an account written by the validator, ignoring the accounts of other programs
+    pub fn update(
+        &self,
+        key: &[u8],
+        owner: &[u8],
+        lamports: u64,
+        data: &[u8],
+        slot: u64,
+        write_version: u64,
+    ) -> Result<()> {
+        if owner != self.program_id.as_ref() {
+            return Ok(());
+        }
+        let database = self
+            .database
+            .as_ref()
+            .ok_or_else(|| update_error("The plugin isn't loaded"))?
+            .lock()
+            .map_err(|_| update_error("The database lock is poisoned"))?;
+        let key = Pubkey::try_from(key).map_err(|_| update_error("Invalid account key"))?;
+        // Closing an account takes all of its lamports, its data being left as is
+        let result = if lamports == 0 {
+            database.delete_account(&key, slot, write_version)
+        } else {
+            database.upsert_account(&key, data, slot, write_version)
+        };
+        result.map_err(update_error)
+    }
+}
+
+impl GeyserPlugin for ReputationPlugin {
+    fn name(&self) -> &'static str {
+        "sns-reputation-geyser"
+    }
+
+    fn on_load(&mut self, config_file: &str) -> Result<()> {
+        let config: Config = serde_json::from_reader(File::open(config_file)?)
+            .map_err(|e| config_error(format!("Invalid config file: {e}")))?;
+        self.program_id = match &config.program_id {
+            Some(program_id) => Pubkey::from_str(program_id)
+                .map_err(|_| config_error(format!("Invalid program ID {program_id}")))?,
+            None => sns_reputation::ID,
+        };
+        let database = Database::open(&config.database).map_err(|e| {
+            config_error(format!("Failed to open {}: {e}", config.database.display()))
+        })?;
+        self.database = Some(Mutex::new(database));
+        log::info!(
+            "Indexing the accounts of {} into {}",
+            self.program_id,
+            config.database.display()
+        );
+        Ok(())
+    }
+
+    fn on_unload(&mut self) {
+        self.database = None;
+    }
+
+    fn update_account(
+        &mut self,
+        account: ReplicaAccountInfoVersions,
+        slot: u64,
+        _is_startup: bool,
+    ) -> Result<()> {
+        match account {
+            ReplicaAccountInfoVersions::V0_0_1(account) => self.update(
+                account.pubkey,
+                account.owner,
+                account.lamports,
+                account.data,
+                slot,
+                account.write_version,
+            ),
+            ReplicaAccountInfoVersions::V0_0_2(account) => self.update(
+                account.pubkey,
+                account.owner,
+                account.lamports,
+                account.data,
+                slot,
+                account.write_version,
+            ),
+        }
+    }
+
+    fn account_data_notifications_enabled(&self) -> bool {
+        true
+    }
+
+    fn transaction_notifications_enabled(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sns_reputation::state::user_vote::UserVote;
+
+    use super::*;
+
+    #[test]
+    fn test_plugin() {
+        let dir = std::env::temp_dir().join(format!("geyser-{}", Pubkey::new_unique()));
+        std::fs::create_dir(&dir).unwrap();
+        let config_file = dir.join("config.json");
+        let database = dir.join("reputation.sqlite");
+        let config = serde_json::json!({ "libpath": "unused", "database": database });
+        std::fs::write(&config_file, config.to_string()).unwrap();
+
+        let mut plugin = ReputationPlugin::default();
+        plugin.on_load(config_file.to_str().unwrap()).unwrap();
+        assert_eq!(plugin.program_id, sns_reputation::ID);
+
+        let vote = UserVote {
+            value: 1,
+            votee: Pubkey::new_unique(),
+            voter: Pubkey::new_unique(),
+            ..UserVote::default()
+        };
+        let mut data = vec![0; UserVote::space()];
+        vote.save(&mut data).unwrap();
+        let key = Pubkey::new_unique();
+        let count = |plugin: &ReputationPlugin| -> u64 {
+            let database = plugin.database.as_ref().unwrap().lock().unwrap();
+            database
+                .connection()
+                .query_row("SELECT COUNT(*) FROM user_votes", [], |row| row.get(0))
+                .unwrap()
+        };
+
+        // Accounts of other programs are ignored
+        let other_program = Pubkey::new_unique();
+        plugin
+            .update(key.as_ref(), other_program.as_ref(), 1, &data, 1, 1)
+            .unwrap();
+        assert_eq!(count(&plugin), 0);
+
+        let program_id = sns_reputation::ID;
+        plugin
+            .update(key.as_ref(), program_id.as_ref(), 1, &data, 2, 2)
+            .unwrap();
+        assert_eq!(count(&plugin), 1);
+
+        // The account is closed
+        plugin
+            .update(key.as_ref(), program_id.as_ref(), 0, &data, 3, 3)
+            .unwrap();
+        assert_eq!(count(&plugin), 0);
+
+        plugin.on_unload();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
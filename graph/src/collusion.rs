@@ -0,0 +1,475 @@
+//! Detection of sybil clusters in the votes: rings of wallets upvoting each other, voters casting
+//! nearly the same votes, and voters whose stake is controlled by the same withdrawer.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{graph::TrustGraph, loader::StakeAuthority};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CollusionConfig {
+    /// Smallest number of wallets in a reported ring
+    pub min_ring_size: usize,
+    /// Rings are reported when at least this share of the upvote weight received by their
+    /// members comes from other members
+    pub min_internal_share: f64,
+    /// Voters are similar when the share of their votes cast the same way on the same wallets
+    /// is at least this
+    pub min_similarity: f64,
+    /// Voters with fewer votes than this are never reported as similar
+    pub min_votes: usize,
+    /// Comparing the voters of a wallet takes a time quadratic in their number, so the
+    /// wallets with more voters than this are only used to refine the similarity of voters
+    /// found to overlap on other wallets. Voters overlapping only on such wallets are never
+    /// compared
+    pub max_voters_per_votee: usize,
+}
+
+impl Default for CollusionConfig {
+    fn default() -> Self {
+        Self {
+            min_ring_size: 2,
+            min_internal_share: 0.8,
+            min_similarity: 0.9,
+            min_votes: 3,
+            max_voters_per_votee: 1_000,
+        }
+    }
+}
+
+/// Wallets reachable from each other through upvotes, mostly upvoted by each other
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoteRing {
+    pub members: Vec<Pubkey>,
+    /// Number of upvotes cast between members
+    pub internal_upvotes: usize,
+    /// Number of pairs of members upvoting each other
+    pub mutual_pairs: usize,
+    /// Share of the upvote weight received by the members that comes from other members
+    pub internal_share: f64,
+}
+
+/// Voters casting nearly the same votes
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimilarVoters {
+    pub voters: Vec<Pubkey>,
+    /// The pairs of voters found similar, with their similarity
+    pub pairs: Vec<(Pubkey, Pubkey, f64)>,
+}
+
+/// Voters staking through stake accounts with the same withdrawer
+#[derive(Clone, Debug, PartialEq)]
+pub struct SharedWithdrawer {
+    pub withdrawer: Pubkey,
+    pub voters: Vec<Pubkey>,
+    pub stake_accounts: Vec<Pubkey>,
+}
+
+/// Suspected sybil clusters, with the evidence for each of them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CollusionReport {
+    pub rings: Vec<VoteRing>,
+    pub similar_voters: Vec<SimilarVoters>,
+    pub shared_withdrawers: Vec<SharedWithdrawer>,
+}
+
+impl CollusionReport {
+    pub fn is_empty(&self) -> bool {
+        self.rings.is_empty()
+            && self.similar_voters.is_empty()
+            && self.shared_withdrawers.is_empty()
+    }
+}
+
+/// Looks for sybil clusters among the votes of `graph` and the stake accounts of the voters
+pub fn detect_collusion(
+    graph: &TrustGraph,
+    stake_accounts: &[StakeAuthority],
+    config: CollusionConfig,
+) -> CollusionReport {
+    CollusionReport {
+        rings: find_vote_rings(graph, config),
+        similar_voters: find_similar_voters(graph, config),
+        shared_withdrawers: find_shared_withdrawers(graph, stake_accounts),
+    }
+}
+
+/// Strongly connected components of the upvotes, largest first
+fn upvote_components(graph: &TrustGraph) -> Vec<Vec<usize>> {
+    let mut upvotes = vec![vec![]; graph.len()];
+    let mut reversed = vec![vec![]; graph.len()];
+    for edge in graph.edges().iter().filter(|edge| edge.weight > 0) {
+        upvotes[edge.from].push(edge.to);
+        reversed[edge.to].push(edge.from);
+    }
+
+    // Kosaraju's algorithm, with explicit stacks as the graph can be deep
+    let mut visited = vec![false; graph.len()];
+    let mut order = Vec::with_capacity(graph.len());
+    for start in 0..graph.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut stack = vec![(start, 0)];
+        while let Some((node, next)) = stack.pop() {
+            if let Some(&to) = upvotes[node].get(next) {
+                stack.push((node, next + 1));
+                if !visited[to] {
+                    visited[to] = true;
+                    stack.push((to, 0));
+                }
+            } else {
+                order.push(node);
+            }
+        }
+    }
+
+    let mut component = vec![usize::MAX; graph.len()];
+    let mut components = vec![];
+    for &start in order.iter().rev() {
+        if component[start] != usize::MAX {
+            continue;
+        }
+        let id = components.len();
+        component[start] = id;
+        let mut members = vec![];
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            members.push(node);
+            for &from in &reversed[node] {
+                if component[from] == usize::MAX {
+                    component[from] = id;
+                    stack.push(from);
+                }
+            }
+        }
+        members.sort_unstable();
+        components.push(members);
+    }
+    components.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    components
+}
+
+fn find_vote_rings(graph: &TrustGraph, config: CollusionConfig) -> Vec<VoteRing> {
+    upvote_components(graph)
+        .into_iter()
+        .filter(|members| members.len() >= config.min_ring_size.max(2))
+        .filter_map(|members| {
+            let is_member = members.iter().copied().collect::<BTreeSet<_>>();
+            let upvotes = graph
+                .edges()
+                .iter()
+                .filter(|edge| edge.weight > 0 && is_member.contains(&edge.to))
+                .collect::<Vec<_>>();
+            let internal = upvotes
+                .iter()
+                .filter(|edge| is_member.contains(&edge.from))
+                .collect::<Vec<_>>();
+            let received = upvotes.iter().map(|edge| edge.weight as f64).sum::<f64>();
+            let internal_share =
+                internal.iter().map(|edge| edge.weight as f64).sum::<f64>() / received;
+            if internal_share < config.min_internal_share {
+                return None;
+            }
+            let pairs = internal
+                .iter()
+                .map(|edge| (edge.from, edge.to))
+                .collect::<BTreeSet<_>>();
+            let mutual_pairs = pairs
+                .iter()
+                .filter(|(from, to)| from < to && pairs.contains(&(*to, *from)))
+                .count();
+            Some(VoteRing {
+                members: members.iter().map(|node| graph.nodes()[*node]).collect(),
+                internal_upvotes: internal.len(),
+                mutual_pairs,
+                internal_share,
+            })
+        })
+        .collect()
+}
+
+/// Minimal union-find over the node indices
+struct Clusters(Vec<usize>);
+
+impl Clusters {
+    fn find(&mut self, node: usize) -> usize {
+        let mut root = node;
+        while self.0[root] != root {
+            root = self.0[root];
+        }
+        let mut node = node;
+        while self.0[node] != root {
+            node = std::mem::replace(&mut self.0[node], root);
+        }
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.0[a.max(b)] = a.min(b);
+    }
+}
+
+fn find_similar_voters(graph: &TrustGraph, config: CollusionConfig) -> Vec<SimilarVoters> {
+    let vote_counts = (0..graph.len())
+        .map(|node| graph.outgoing(node).count())
+        .collect::<Vec<_>>();
+
+    // Number of wallets on which each pair of voters voted, and on which they agreed
+    let mut voters_by_votee = BTreeMap::<usize, Vec<(usize, bool)>>::new();
+    for edge in graph.edges() {
+        if vote_counts[edge.from] >= config.min_votes {
+            voters_by_votee
+                .entry(edge.to)
+                .or_default()
+                .push((edge.from, edge.weight > 0));
+        }
+    }
+    let (popular_votees, votees) = voters_by_votee
+        .into_iter()
+        .partition::<Vec<_>, _>(|(_, voters)| voters.len() > config.max_voters_per_votee);
+
+    let mut overlaps = HashMap::<(usize, usize), (usize, usize)>::new();
+    for (_, voters) in &votees {
+        for (i, (a, a_upvoted)) in voters.iter().enumerate() {
+            for (b, b_upvoted) in &voters[i + 1..] {
+                let (common, agreed) = overlaps.entry(((*a).min(*b), (*a).max(*b))).or_default();
+                *common += 1;
+                if a_upvoted == b_upvoted {
+                    *agreed += 1;
+                }
+            }
+        }
+    }
+
+    // The votes on popular wallets are only looked up for the pairs found above
+    let mut popular_votes = HashMap::<usize, Vec<(usize, bool)>>::new();
+    let mut popular_upvoted = HashMap::<(usize, usize), bool>::new();
+    for (votee, voters) in popular_votees {
+        for (voter, upvoted) in voters {
+            popular_votes
+                .entry(voter)
+                .or_default()
+                .push((votee, upvoted));
+            popular_upvoted.insert((voter, votee), upvoted);
+        }
+    }
+    for ((a, b), (common, agreed)) in overlaps.iter_mut() {
+        for (votee, a_upvoted) in popular_votes.get(a).into_iter().flatten() {
+            if let Some(b_upvoted) = popular_upvoted.get(&(*b, *votee)) {
+                *common += 1;
+                if a_upvoted == b_upvoted {
+                    *agreed += 1;
+                }
+            }
+        }
+    }
+
+    let mut pairs = overlaps
+        .into_iter()
+        .filter_map(|((a, b), (common, agreed))| {
+            // Share of the wallets voted on by either voter that both voted on the same way
+            let similarity = agreed as f64 / (vote_counts[a] + vote_counts[b] - common) as f64;
+            (similarity >= config.min_similarity).then_some((a, b, similarity))
+        })
+        .collect::<Vec<_>>();
+    pairs.sort_by_key(|(a, b, _)| (*a, *b));
+
+    let mut clusters = Clusters((0..graph.len()).collect());
+    for (a, b, _) in &pairs {
+        clusters.union(*a, *b);
+    }
+    let mut groups = BTreeMap::<usize, SimilarVoters>::new();
+    for (a, b, similarity) in pairs {
+        let group = groups
+            .entry(clusters.find(a))
+            .or_insert_with(|| SimilarVoters {
+                voters: vec![],
+                pairs: vec![],
+            });
+        group
+            .pairs
+            .push((graph.nodes()[a], graph.nodes()[b], similarity));
+        group.voters.extend([graph.nodes()[a], graph.nodes()[b]]);
+    }
+    let mut groups = groups
+        .into_values()
+        .map(|mut group| {
+            group.voters.sort_unstable();
+            group.voters.dedup();
+            group
+        })
+        .collect::<Vec<_>>();
+    groups.sort_by(|a, b| {
+        b.voters
+            .len()
+            .cmp(&a.voters.len())
+            .then(a.voters.cmp(&b.voters))
+    });
+    groups
+}
+
+fn find_shared_withdrawers(
+    graph: &TrustGraph,
+    stake_accounts: &[StakeAuthority],
+) -> Vec<SharedWithdrawer> {
+    let voters = graph
+        .edges()
+        .iter()
+        .map(|edge| graph.nodes()[edge.from])
+        .collect::<BTreeSet<_>>();
+    let mut by_withdrawer = BTreeMap::<Pubkey, (BTreeSet<Pubkey>, BTreeSet<Pubkey>)>::new();
+    for stake in stake_accounts
+        .iter()
+        .filter(|stake| voters.contains(&stake.staker))
+    {
+        let (voters, accounts) = by_withdrawer.entry(stake.withdrawer).or_default();
+        voters.insert(stake.staker);
+        accounts.insert(stake.stake_account);
+    }
+    let mut shared = by_withdrawer
+        .into_iter()
+        .filter(|(_, (voters, _))| voters.len() >= 2)
+        .map(|(withdrawer, (voters, accounts))| SharedWithdrawer {
+            withdrawer,
+            voters: voters.into_iter().collect(),
+            stake_accounts: accounts.into_iter().collect(),
+        })
+        .collect::<Vec<_>>();
+    shared.sort_by_key(|shared| std::cmp::Reverse(shared.voters.len()));
+    shared
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::vote;
+
+    use super::*;
+
+    #[test]
+    fn test_vote_rings() {
+        let honest = [0; 4].map(|_| Pubkey::new_unique());
+        let ring = [0; 3].map(|_| Pubkey::new_unique());
+        let mut votes = vec![
+            // Honest wallets upvote each other but are mostly upvoted from outside
+            vote(&honest[0], &honest[1], 1),
+            vote(&honest[1], &honest[0], 1),
+            vote(&honest[2], &honest[0], 10),
+            vote(&honest[3], &honest[1], 10),
+            // The ring upvotes itself in a cycle, with a single outside upvote
+            vote(&ring[0], &ring[1], 100),
+            vote(&ring[1], &ring[2], 100),
+            vote(&ring[2], &ring[0], 100),
+            vote(&ring[1], &ring[0], 100),
+            vote(&honest[2], &ring[0], 1),
+        ];
+        // Downvotes don't link wallets
+        votes.push(vote(&honest[3], &ring[2], -5));
+        let graph = TrustGraph::from_votes(&votes);
+
+        let rings = find_vote_rings(&graph, CollusionConfig::default());
+        assert_eq!(rings.len(), 1);
+        let mut members = ring.to_vec();
+        members.sort();
+        assert_eq!(rings[0].members, members);
+        assert_eq!(rings[0].internal_upvotes, 4);
+        assert_eq!(rings[0].mutual_pairs, 1);
+        assert!((rings[0].internal_share - 400.0 / 401.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_similar_voters() {
+        let votees = [0; 4].map(|_| Pubkey::new_unique());
+        let sybils = [0; 3].map(|_| Pubkey::new_unique());
+        let other = Pubkey::new_unique();
+        let mut votes = vec![];
+        for sybil in &sybils {
+            votes.push(vote(sybil, &votees[0], 1));
+            votes.push(vote(sybil, &votees[1], 1));
+            votes.push(vote(sybil, &votees[2], -1));
+        }
+        // Same wallets, but not the same opinion on all of them
+        votes.push(vote(&other, &votees[0], 1));
+        votes.push(vote(&other, &votees[1], -1));
+        votes.push(vote(&other, &votees[2], -1));
+        votes.push(vote(&other, &votees[3], 1));
+        let graph = TrustGraph::from_votes(&votes);
+
+        let groups = find_similar_voters(&graph, CollusionConfig::default());
+        assert_eq!(groups.len(), 1);
+        let mut voters = sybils.to_vec();
+        voters.sort();
+        assert_eq!(groups[0].voters, voters);
+        assert_eq!(groups[0].pairs.len(), 3);
+        assert!(groups[0]
+            .pairs
+            .iter()
+            .all(|(_, _, similarity)| *similarity == 1.0));
+    }
+
+    #[test]
+    fn test_similar_voters_of_popular_votees() {
+        let popular = Pubkey::new_unique();
+        let votees = [0; 2].map(|_| Pubkey::new_unique());
+        let sybils = [0; 2].map(|_| Pubkey::new_unique());
+        let fans = [0; 3].map(|_| Pubkey::new_unique());
+        let mut votes = vec![];
+        for sybil in &sybils {
+            votes.push(vote(sybil, &popular, 1));
+            votes.push(vote(sybil, &votees[0], 1));
+            votes.push(vote(sybil, &votees[1], -1));
+        }
+        // Only voting on the same popular wallet doesn't make voters compared
+        for fan in &fans {
+            votes.push(vote(fan, &popular, 1));
+            for _ in 0..2 {
+                votes.push(vote(fan, &Pubkey::new_unique(), 1));
+            }
+        }
+        let graph = TrustGraph::from_votes(&votes);
+        let config = CollusionConfig {
+            max_voters_per_votee: 4,
+            ..CollusionConfig::default()
+        };
+
+        let groups = find_similar_voters(&graph, config);
+        assert_eq!(groups.len(), 1);
+        let mut voters = sybils.to_vec();
+        voters.sort();
+        assert_eq!(groups[0].voters, voters);
+        // The popular wallet still counts in the similarity of the pairs found
+        assert_eq!(groups[0].pairs, vec![(voters[0], voters[1], 1.0)]);
+    }
+
+    #[test]
+    fn test_shared_withdrawers() {
+        let votee = Pubkey::new_unique();
+        let voters = [0; 3].map(|_| Pubkey::new_unique());
+        let votes = voters.map(|voter| vote(&voter, &votee, 1));
+        let graph = TrustGraph::from_votes(&votes);
+        let withdrawer = Pubkey::new_unique();
+        let stake = |staker: &Pubkey, withdrawer: &Pubkey| StakeAuthority {
+            stake_account: Pubkey::new_unique(),
+            staker: *staker,
+            withdrawer: *withdrawer,
+        };
+        let stake_accounts = [
+            stake(&voters[0], &withdrawer),
+            stake(&voters[1], &withdrawer),
+            stake(&voters[2], &voters[2]),
+            // Wallets that didn't vote don't matter
+            stake(&Pubkey::new_unique(), &voters[2]),
+        ];
+
+        let shared = find_shared_withdrawers(&graph, &stake_accounts);
+        assert_eq!(shared.len(), 1);
+        assert_eq!(shared[0].withdrawer, withdrawer);
+        let mut expected = vec![voters[0], voters[1]];
+        expected.sort();
+        assert_eq!(shared[0].voters, expected);
+        assert_eq!(shared[0].stake_accounts.len(), 2);
+    }
+}
@@ -0,0 +1,111 @@
+//! EigenTrust: the trust of a wallet is the trust of its upvoters, each spreading its own trust
+//! over the wallets it upvotes in proportion to the weight of its votes. Downvotes carry no
+//! trust, as in the original algorithm.
+
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::graph::{iterate, Convergence, Iterated, TrustGraph};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EigenTrustConfig {
+    /// Share of the trust going back to the pre-trusted wallets at every iteration, which
+    /// bounds the trust a group of colluding wallets can give each other
+    pub pre_trust_weight: f64,
+    pub convergence: Convergence,
+}
+
+impl Default for EigenTrustConfig {
+    fn default() -> Self {
+        Self {
+            pre_trust_weight: 0.15,
+            convergence: Convergence::default(),
+        }
+    }
+}
+
+/// Computes the global trust of every wallet of `graph`, the scores adding up to 1. The
+/// pre-trusted `seeds` are trusted from the start, every wallet being when none is given. Fails
+/// when none of the `seeds` is in the graph
+pub fn eigentrust(
+    graph: &TrustGraph,
+    seeds: &[Pubkey],
+    config: EigenTrustConfig,
+) -> Result<Iterated> {
+    if graph.is_empty() {
+        return Ok(Iterated {
+            scores: vec![],
+            iterations: 0,
+            converged: true,
+        });
+    }
+    let pre_trust = graph.seed_distribution(seeds)?;
+
+    // Normalized local trust, the upvotes of each wallet adding up to 1
+    let local_trust = (0..graph.len())
+        .map(|node| {
+            let total = graph
+                .outgoing(node)
+                .map(|edge| edge.weight.max(0) as f64)
+                .sum::<f64>();
+            graph
+                .outgoing(node)
+                .filter(|edge| edge.weight > 0)
+                .map(|edge| (edge.to, edge.weight as f64 / total))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let a = config.pre_trust_weight;
+    Ok(iterate(pre_trust.clone(), config.convergence, |trust| {
+        let mut next = pre_trust.iter().map(|p| a * p).collect::<Vec<_>>();
+        // Wallets that didn't upvote anyone defer to the pre-trusted wallets
+        let mut deferred = 0.0;
+        for (node, upvotes) in local_trust.iter().enumerate() {
+            if upvotes.is_empty() {
+                deferred += trust[node];
+            }
+            for (to, share) in upvotes {
+                next[*to] += (1.0 - a) * trust[node] * share;
+            }
+        }
+        for (score, p) in next.iter_mut().zip(&pre_trust) {
+            *score += (1.0 - a) * deferred * p;
+        }
+        next
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::vote;
+
+    use super::*;
+
+    #[test]
+    fn test_eigentrust() {
+        let keys = [0; 5].map(|_| Pubkey::new_unique());
+        let [seed, trusted, stranger, ring_a, ring_b] = keys;
+        let votes = [
+            vote(&seed, &trusted, 10),
+            vote(&trusted, &stranger, 1),
+            vote(&trusted, &ring_a, -5),
+            // A ring upvoting itself without being upvoted by trusted wallets
+            vote(&ring_a, &ring_b, 1_000),
+            vote(&ring_b, &ring_a, 1_000),
+        ];
+        let graph = TrustGraph::from_votes(&votes);
+        let result = eigentrust(&graph, &[seed], EigenTrustConfig::default()).unwrap();
+        assert!(result.converged);
+        assert!((result.scores.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        let score = |key: &Pubkey| result.scores[graph.index_of(key).unwrap()];
+        assert!(score(&trusted) > score(&stranger));
+        assert!(score(&stranger) > 0.0);
+        assert_eq!(score(&ring_a), 0.0);
+        assert_eq!(score(&ring_b), 0.0);
+
+        let ranked = graph.ranked(&result.scores);
+        assert_eq!(ranked[0].0, seed);
+    }
+}
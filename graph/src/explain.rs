@@ -0,0 +1,198 @@
+//! Breakdown of the score of a user into the votes cast on it, answering who voted on it and how
+//! much each vote weighs. The program doesn't record a reason along with the votes, the origin
+//! of each vote being given instead.
+
+use std::collections::HashMap;
+
+use solana_sdk::pubkey::Pubkey;
+
+use sns_reputation::state::{
+    reputation_score::{ReputationScore, WILSON_Z_95},
+    user_vote::UserVote,
+};
+
+/// How a vote was cast
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoteOrigin {
+    /// Cast by the voter, directly or through a delegate or a relayer
+    Direct,
+    /// Propagated from a curator followed by the voter
+    Curator(Pubkey),
+}
+
+/// The own score of a voter
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VoterScore {
+    pub upvote: u64,
+    pub downvote: u64,
+    /// Lower bound of the share of upvoters at 95% confidence, scaled by `SCORE_SCALE`
+    pub wilson_score: u64,
+}
+
+impl From<&ReputationScore> for VoterScore {
+    fn from(score: &ReputationScore) -> Self {
+        Self {
+            upvote: score.upvote,
+            downvote: score.downvote,
+            wilson_score: score.wilson_score(WILSON_Z_95),
+        }
+    }
+}
+
+/// A vote counted in the score of a user
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contributor {
+    pub voter: Pubkey,
+    /// Weighted value of the vote, negative for downvotes
+    pub weight: i64,
+    /// Share of the upvote total for upvotes, of the downvote total for downvotes
+    pub share: f64,
+    /// Slots elapsed since the value of the vote was set, or since it was committed for the votes
+    /// set before the program recorded their slot. The age of the other votes is unknown
+    pub age_slots: Option<u64>,
+    pub origin: VoteOrigin,
+    /// Credibility multiplier of the voter applied to the weight, scaled by `CREDIBILITY_SCALE`
+    pub credibility_multiplier: u64,
+    /// Score of the voter, if it was voted on
+    pub voter_score: Option<VoterScore>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoreBreakdown {
+    pub subject: Pubkey,
+    /// Sum of the upvotes cast on the subject
+    pub upvote: u64,
+    /// Sum of the downvotes cast on the subject
+    pub downvote: u64,
+    /// The votes cast on the subject, heaviest first
+    pub contributors: Vec<Contributor>,
+}
+
+/// Breaks the score of `subject` down into the `votes` cast on it, at `slot`. Votes on other
+/// users and pending commitments are left out. `voter_scores` holds the ReputationScore of the
+/// voters that have one, by voter
+pub fn explain_score(
+    subject: &Pubkey,
+    votes: &[UserVote],
+    voter_scores: &HashMap<Pubkey, ReputationScore>,
+    slot: u64,
+) -> ScoreBreakdown {
+    let votes = votes
+        .iter()
+        .filter(|vote| vote.votee == *subject && vote.value != 0)
+        .collect::<Vec<_>>();
+    let total = |upvotes: bool| {
+        votes
+            .iter()
+            .filter(|vote| (vote.value > 0) == upvotes)
+            .map(|vote| vote.value.unsigned_abs())
+            .fold(0u64, u64::saturating_add)
+    };
+    let (upvote, downvote) = (total(true), total(false));
+
+    let mut contributors = votes
+        .into_iter()
+        .map(|vote| {
+            let total = if vote.value > 0 { upvote } else { downvote };
+            Contributor {
+                voter: vote.voter,
+                weight: vote.value,
+                share: vote.value.unsigned_abs() as f64 / total as f64,
+                age_slots: [vote.vote_slot, vote.commit_slot]
+                    .into_iter()
+                    .find(|vote_slot| *vote_slot != 0)
+                    .map(|vote_slot| slot.saturating_sub(vote_slot)),
+                origin: if vote.is_followed() {
+                    VoteOrigin::Curator(vote.followed_curator)
+                } else {
+                    VoteOrigin::Direct
+                },
+                credibility_multiplier: vote.credibility_multiplier,
+                voter_score: voter_scores.get(&vote.voter).map(VoterScore::from),
+            }
+        })
+        .collect::<Vec<_>>();
+    contributors.sort_by(|a, b| {
+        b.weight
+            .unsigned_abs()
+            .cmp(&a.weight.unsigned_abs())
+            .then(a.voter.cmp(&b.voter))
+    });
+
+    ScoreBreakdown {
+        subject: *subject,
+        upvote,
+        downvote,
+        contributors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sns_reputation::state::reputation_score::CREDIBILITY_SCALE;
+
+    use crate::graph::vote;
+
+    use super::*;
+
+    #[test]
+    fn test_explain_score() {
+        let subject = Pubkey::new_unique();
+        let [curator, small, large, critic] = [0; 4].map(|_| Pubkey::new_unique());
+        let votes = [
+            vote(&small, &subject, 1),
+            UserVote {
+                commit_slot: 100,
+                credibility_multiplier: CREDIBILITY_SCALE,
+                ..vote(&large, &subject, 3)
+            },
+            UserVote {
+                followed_curator: curator,
+                commit_slot: 20,
+                vote_slot: 120,
+                ..vote(&critic, &subject, -2)
+            },
+            // Votes on other users and pending commitments don't count
+            vote(&critic, &small, -5),
+            vote(&curator, &subject, 0),
+        ];
+        let voter_scores = HashMap::from([(
+            large,
+            ReputationScore {
+                upvote: 80,
+                downvote: 20,
+                upvoters: 80,
+                downvoters: 20,
+                ..ReputationScore::default()
+            },
+        )]);
+        let breakdown = explain_score(&subject, &votes, &voter_scores, 150);
+
+        assert_eq!((breakdown.upvote, breakdown.downvote), (4, 2));
+        let voters = breakdown
+            .contributors
+            .iter()
+            .map(|contributor| (contributor.voter, contributor.weight, contributor.share))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            voters,
+            vec![(large, 3, 0.75), (critic, -2, 1.0), (small, 1, 0.25)]
+        );
+
+        let [large, critic, small] = [0, 1, 2].map(|i| &breakdown.contributors[i]);
+        assert_eq!(large.age_slots, Some(50));
+        assert_eq!(critic.age_slots, Some(30));
+        assert_eq!(small.age_slots, None);
+        assert_eq!(critic.origin, VoteOrigin::Curator(curator));
+        assert_eq!(small.origin, VoteOrigin::Direct);
+        assert_eq!(
+            large.voter_score,
+            Some(VoterScore {
+                upvote: 80,
+                downvote: 20,
+                wilson_score: 7111,
+            })
+        );
+        assert_eq!(critic.voter_score, None);
+    }
+}
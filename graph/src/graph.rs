@@ -0,0 +1,239 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{bail, Result};
+use solana_sdk::pubkey::Pubkey;
+
+use sns_reputation::state::user_vote::UserVote;
+
+/// A vote seen as an edge of the trust graph
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edge {
+    /// Index of the voter
+    pub from: usize,
+    /// Index of the votee
+    pub to: usize,
+    /// Weighted value of the vote, negative for downvotes
+    pub weight: i64,
+}
+
+/// Directed graph of the votes, the wallets being indexed in the order of their keys so that
+/// every computation over the graph is deterministic
+#[derive(Clone, Debug, Default)]
+pub struct TrustGraph {
+    nodes: Vec<Pubkey>,
+    index: HashMap<Pubkey, usize>,
+    edges: Vec<Edge>,
+    /// Indices in `edges` of the votes cast by each node
+    outgoing: Vec<Vec<usize>>,
+}
+
+impl TrustGraph {
+    /// Builds the graph of `votes`. Pending commitments don't count as votes yet
+    pub fn from_votes<'a>(votes: impl IntoIterator<Item = &'a UserVote>) -> Self {
+        let votes = votes
+            .into_iter()
+            .filter(|vote| vote.value != 0)
+            .collect::<Vec<_>>();
+        let nodes = votes
+            .iter()
+            .flat_map(|vote| [vote.voter, vote.votee])
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let index = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (*key, i))
+            .collect::<HashMap<_, _>>();
+
+        let mut edges = votes
+            .iter()
+            .map(|vote| Edge {
+                from: index[&vote.voter],
+                to: index[&vote.votee],
+                weight: vote.value,
+            })
+            .collect::<Vec<_>>();
+        edges.sort_by_key(|edge| (edge.from, edge.to));
+
+        let mut outgoing = vec![vec![]; nodes.len()];
+        for (i, edge) in edges.iter().enumerate() {
+            outgoing[edge.from].push(i);
+        }
+
+        Self {
+            nodes,
+            index,
+            edges,
+            outgoing,
+        }
+    }
+
+    /// The wallets of the graph, in the order of their indices
+    pub fn nodes(&self) -> &[Pubkey] {
+        &self.nodes
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Index of `key` in the graph, if it voted or was voted on
+    pub fn index_of(&self, key: &Pubkey) -> Option<usize> {
+        self.index.get(key).copied()
+    }
+
+    /// All the votes, sorted by voter then votee
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// The votes cast by the wallet at `node`
+    pub fn outgoing(&self, node: usize) -> impl Iterator<Item = &Edge> {
+        self.outgoing[node].iter().map(|i| &self.edges[*i])
+    }
+
+    /// Distribution over the nodes, uniform over the `seeds` present in the graph or over all
+    /// the nodes when no seed is given. Fails when none of the given seeds is in the graph,
+    /// rather than silently trusting every wallet
+    pub fn seed_distribution(&self, seeds: &[Pubkey]) -> Result<Vec<f64>> {
+        if seeds.is_empty() {
+            return Ok(vec![1.0 / self.len() as f64; self.len()]);
+        }
+        let present = seeds
+            .iter()
+            .filter_map(|seed| self.index_of(seed))
+            .collect::<BTreeSet<_>>();
+        if present.is_empty() {
+            bail!("None of the {} seeds voted or was voted on", seeds.len());
+        }
+        let mut distribution = vec![0.0; self.len()];
+        for seed in &present {
+            distribution[*seed] = 1.0 / present.len() as f64;
+        }
+        Ok(distribution)
+    }
+
+    /// Pairs each wallet with its score, highest scores first
+    pub fn ranked(&self, scores: &[f64]) -> Vec<(Pubkey, f64)> {
+        let mut ranked = self
+            .nodes
+            .iter()
+            .copied()
+            .zip(scores.iter().copied())
+            .collect::<Vec<_>>();
+        ranked.sort_by(|(a_key, a), (b_key, b)| b.total_cmp(a).then(a_key.cmp(b_key)));
+        ranked
+    }
+}
+
+/// Stopping criteria of the iterative algorithms
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Convergence {
+    /// The iterations stop once the L1 distance between two iterations is below this
+    pub tolerance: f64,
+    /// The iterations stop after this many iterations even without converging
+    pub max_iterations: usize,
+}
+
+impl Default for Convergence {
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-10,
+            max_iterations: 200,
+        }
+    }
+}
+
+/// Result of an iterative algorithm
+#[derive(Clone, Debug, PartialEq)]
+pub struct Iterated {
+    /// Scores indexed like the nodes of the graph
+    pub scores: Vec<f64>,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// Iterates `step` from `initial` until the scores converge
+pub(crate) fn iterate(
+    initial: Vec<f64>,
+    convergence: Convergence,
+    mut step: impl FnMut(&[f64]) -> Vec<f64>,
+) -> Iterated {
+    let mut scores = initial;
+    for iteration in 1..=convergence.max_iterations {
+        let next = step(&scores);
+        let distance = next
+            .iter()
+            .zip(&scores)
+            .map(|(a, b)| (a - b).abs())
+            .sum::<f64>();
+        scores = next;
+        if distance < convergence.tolerance {
+            return Iterated {
+                scores,
+                iterations: iteration,
+                converged: true,
+            };
+        }
+    }
+    Iterated {
+        scores,
+        iterations: convergence.max_iterations,
+        converged: false,
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn vote(voter: &Pubkey, votee: &Pubkey, value: i64) -> UserVote {
+    UserVote {
+        value,
+        votee: *votee,
+        voter: *voter,
+        ..UserVote::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_votes() {
+        let keys = [0; 3].map(|_| Pubkey::new_unique());
+        let votes = [
+            vote(&keys[2], &keys[0], 5),
+            vote(&keys[0], &keys[1], -2),
+            // Pending commitments are ignored
+            vote(&keys[1], &Pubkey::new_unique(), 0),
+        ];
+        let graph = TrustGraph::from_votes(&votes);
+
+        assert_eq!(graph.len(), 3);
+        let edges = graph
+            .edges()
+            .iter()
+            .map(|edge| {
+                (
+                    graph.nodes()[edge.from],
+                    graph.nodes()[edge.to],
+                    edge.weight,
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut expected = vec![(keys[2], keys[0], 5), (keys[0], keys[1], -2)];
+        expected.sort();
+        assert_eq!(edges, expected);
+
+        let node = graph.index_of(&keys[0]).unwrap();
+        assert_eq!(graph.outgoing(node).count(), 1);
+        assert_eq!(graph.seed_distribution(&[keys[1]]).unwrap()[node], 0.0);
+        assert_eq!(graph.seed_distribution(&[]).unwrap()[node], 1.0 / 3.0);
+        // Seeds missing from the graph don't fall back to trusting everyone
+        assert!(graph.seed_distribution(&[Pubkey::new_unique()]).is_err());
+    }
+}
@@ -0,0 +1,12 @@
+//! Analysis of the trust graph formed by the UserVote accounts: every vote is a signed edge from
+//! the voter to the votee, weighted by the stake behind it.
+
+pub mod collusion;
+pub mod eigentrust;
+pub mod explain;
+pub mod graph;
+pub mod loader;
+pub mod pagerank;
+pub mod paths;
+
+pub use graph::TrustGraph;
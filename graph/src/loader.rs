@@ -0,0 +1,326 @@
+//! Loads the UserVote accounts, and the stake accounts of the voters, from an RPC node or from a
+//! snapshot file. The filters and batches of the RPC requests are shared with the other clients
+//! of the program, which send them with a blocking or an async RPC client.
+//!
+//! Snapshots hold the accounts in the format returned by `getProgramAccounts` with the base64
+//! encoding, so the result of a raw RPC request can be used as a snapshot as well.
+
+use std::{fs::File, io::BufReader, path::Path};
+
+use anyhow::{Context, Result};
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+    rpc_response::RpcKeyedAccount,
+};
+use solana_sdk::{
+    account::Account,
+    pubkey::Pubkey,
+    stake::{self, state::StakeState},
+};
+
+use sns_reputation::state::{reputation_score::ReputationScore, user_vote::UserVote, Tag};
+
+/// Offset of the votee in a UserVote account, after the value. Accounts using the latest
+/// layout store a version byte after the tag, which unversioned accounts don't have
+pub const VOTEE_OFFSETS: [usize; 2] = [8 + 8, 8 + 1 + 8];
+
+/// Maximum number of accounts fetched by a `getMultipleAccounts` request
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Offset of the staker authority in a stake account
+const STAKER_OFFSET: usize = 12;
+
+/// Config of a `getProgramAccounts` request for the accounts matching `filters`, encoded in
+/// base64
+pub fn program_accounts_config(filters: Vec<RpcFilterType>) -> RpcProgramAccountsConfig {
+    RpcProgramAccountsConfig {
+        filters: Some(filters),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    }
+}
+
+/// Matches the program accounts holding `tag`
+pub fn tag_filter(tag: Tag) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &(tag as u64).to_le_bytes()))
+}
+
+/// Matches the stake accounts staked by `staker`
+pub fn staker_filter(staker: &Pubkey) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(STAKER_OFFSET, staker.as_ref()))
+}
+
+/// Filters of the `getProgramAccounts` requests for the UserVote accounts cast on `votee`, one
+/// request per layout
+pub fn votee_filters(votee: &Pubkey) -> [Vec<RpcFilterType>; 2] {
+    VOTEE_OFFSETS.map(|offset| {
+        vec![
+            tag_filter(Tag::UserVote),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, votee.as_ref())),
+        ]
+    })
+}
+
+/// Splits the `users` into batches fitting in a `getMultipleAccounts` request, along with the
+/// keys of their ReputationScore
+pub fn reputation_score_batches<'a>(
+    program_id: &'a Pubkey,
+    users: &'a [Pubkey],
+) -> impl Iterator<Item = (&'a [Pubkey], Vec<Pubkey>)> + 'a {
+    users.chunks(MAX_MULTIPLE_ACCOUNTS).map(move |users| {
+        let keys = users
+            .iter()
+            .map(|user| ReputationScore::find_key(program_id, user).0)
+            .collect();
+        (users, keys)
+    })
+}
+
+/// Parses the ReputationScore `accounts` fetched for a batch of `users`, skipping the users that
+/// don't have one
+pub fn parse_reputation_scores(
+    users: &[Pubkey],
+    keys: &[Pubkey],
+    accounts: Vec<Option<Account>>,
+) -> Result<Vec<(Pubkey, ReputationScore)>> {
+    let mut scores = vec![];
+    for ((user, key), account) in users.iter().zip(keys).zip(accounts) {
+        if let Some(account) = account {
+            let score = ReputationScore::from_buffer(&account.data, Tag::ReputationScore)
+                .with_context(|| format!("Invalid ReputationScore {key}"))?;
+            scores.push((*user, score));
+        }
+    }
+    Ok(scores)
+}
+
+/// Fetches the UserVote accounts of the program
+pub fn fetch_vote_accounts(
+    client: &RpcClient,
+    program_id: &Pubkey,
+) -> Result<Vec<(Pubkey, Account)>> {
+    client
+        .get_program_accounts_with_config(
+            program_id,
+            program_accounts_config(vec![tag_filter(Tag::UserVote)]),
+        )
+        .context("Failed to fetch the UserVote accounts")
+}
+
+/// Authorities of a stake account
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StakeAuthority {
+    pub stake_account: Pubkey,
+    pub staker: Pubkey,
+    pub withdrawer: Pubkey,
+}
+
+/// Fetches the stake accounts staked by any of the `stakers`
+pub fn fetch_stake_accounts(
+    client: &RpcClient,
+    stakers: &[Pubkey],
+) -> Result<Vec<(Pubkey, Account)>> {
+    let mut accounts = vec![];
+    for staker in stakers {
+        let config = program_accounts_config(vec![staker_filter(staker)]);
+        accounts.extend(
+            client
+                .get_program_accounts_with_config(&stake::program::ID, config)
+                .with_context(|| format!("Failed to fetch the stake accounts of {staker}"))?,
+        );
+    }
+    Ok(accounts)
+}
+
+/// Reads the accounts of a snapshot file
+pub fn read_snapshot(path: &Path) -> Result<Vec<(Pubkey, Account)>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let keyed_accounts: Vec<RpcKeyedAccount> = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("Invalid snapshot {}", path.display()))?;
+    keyed_accounts
+        .into_iter()
+        .map(|keyed_account| {
+            let key = keyed_account
+                .pubkey
+                .parse()
+                .with_context(|| format!("Invalid account key {}", keyed_account.pubkey))?;
+            let account = keyed_account
+                .account
+                .decode()
+                .with_context(|| format!("Invalid account data for {key}"))?;
+            Ok((key, account))
+        })
+        .collect()
+}
+
+/// Writes `accounts` to a snapshot file, which can be read back with `read_snapshot`
+pub fn write_snapshot(path: &Path, accounts: &[(Pubkey, Account)]) -> Result<()> {
+    let keyed_accounts = accounts
+        .iter()
+        .map(|(key, account)| RpcKeyedAccount {
+            pubkey: key.to_string(),
+            account: UiAccount::encode(key, account, UiAccountEncoding::Base64, None, None),
+        })
+        .collect::<Vec<_>>();
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    serde_json::to_writer(file, &keyed_accounts)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Parses the UserVote accounts, skipping the accounts holding other data
+pub fn parse_votes(accounts: &[(Pubkey, Account)]) -> Result<Vec<UserVote>> {
+    accounts
+        .iter()
+        .filter(|(_, account)| {
+            account.data.get(..8) == Some(&(Tag::UserVote as u64).to_le_bytes()[..])
+        })
+        .map(|(key, account)| {
+            UserVote::from_buffer(&account.data, Tag::UserVote)
+                .with_context(|| format!("Invalid UserVote {key}"))
+        })
+        .collect()
+}
+
+/// Parses the authorities of the stake accounts, skipping the accounts that aren't initialized
+/// stake accounts
+pub fn parse_stake_accounts(accounts: &[(Pubkey, Account)]) -> Vec<StakeAuthority> {
+    accounts
+        .iter()
+        .filter(|(_, account)| account.owner == stake::program::ID)
+        .filter_map(|(key, account)| {
+            let authorized = bincode::deserialize::<StakeState>(&account.data)
+                .ok()?
+                .authorized()?;
+            Some(StakeAuthority {
+                stake_account: *key,
+                staker: authorized.staker,
+                withdrawer: authorized.withdrawer,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::stake::state::{Authorized, Lockup, Meta};
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let vote = UserVote {
+            value: -3,
+            votee: Pubkey::new_unique(),
+            voter: Pubkey::new_unique(),
+            ..UserVote::default()
+        };
+        let mut data = vec![0; UserVote::space()];
+        vote.save(&mut data).unwrap();
+        let accounts = vec![
+            (
+                Pubkey::new_unique(),
+                Account {
+                    data,
+                    ..Account::default()
+                },
+            ),
+            // Accounts holding other data are skipped
+            (
+                Pubkey::new_unique(),
+                Account {
+                    data: (Tag::VoterProfile as u64).to_le_bytes().to_vec(),
+                    ..Account::default()
+                },
+            ),
+        ];
+
+        let path = std::env::temp_dir().join(format!("votes-{}.json", Pubkey::new_unique()));
+        write_snapshot(&path, &accounts).unwrap();
+        let read = read_snapshot(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read, accounts);
+        assert_eq!(parse_votes(&read).unwrap(), vec![vote]);
+    }
+
+    #[test]
+    fn test_parse_reputation_scores() {
+        let program_id = Pubkey::new_unique();
+        let users = (0..MAX_MULTIPLE_ACCOUNTS + 1)
+            .map(|_| Pubkey::new_unique())
+            .collect::<Vec<_>>();
+        let batches = reputation_score_batches(&program_id, &users).collect::<Vec<_>>();
+        assert_eq!(batches.len(), 2);
+        let (batch, keys) = &batches[1];
+        assert_eq!(*batch, &users[MAX_MULTIPLE_ACCOUNTS..]);
+        assert_eq!(
+            keys,
+            &vec![ReputationScore::find_key(&program_id, &users[MAX_MULTIPLE_ACCOUNTS]).0]
+        );
+
+        let score = ReputationScore {
+            upvote: 3,
+            upvoters: 3,
+            ..ReputationScore::default()
+        };
+        let mut data = vec![0; ReputationScore::space()];
+        score.save(&mut data).unwrap();
+        let (batch, keys) = &batches[0];
+        let mut accounts = vec![None; batch.len()];
+        accounts[1] = Some(Account {
+            data,
+            ..Account::default()
+        });
+        // Users without a ReputationScore are skipped
+        assert_eq!(
+            parse_reputation_scores(batch, keys, accounts).unwrap(),
+            vec![(users[1], score)]
+        );
+    }
+
+    #[test]
+    fn test_parse_stake_accounts() {
+        let authorized = Authorized {
+            staker: Pubkey::new_unique(),
+            withdrawer: Pubkey::new_unique(),
+        };
+        let meta = Meta {
+            rent_exempt_reserve: 0,
+            authorized,
+            lockup: Lockup::default(),
+        };
+        let stake_account = |state: &StakeState| Account {
+            data: bincode::serialize(state).unwrap(),
+            owner: stake::program::ID,
+            ..Account::default()
+        };
+        let key = Pubkey::new_unique();
+        let accounts = vec![
+            (key, stake_account(&StakeState::Initialized(meta))),
+            (
+                Pubkey::new_unique(),
+                stake_account(&StakeState::Uninitialized),
+            ),
+        ];
+        assert_eq!(
+            parse_stake_accounts(&accounts),
+            vec![StakeAuthority {
+                stake_account: key,
+                staker: authorized.staker,
+                withdrawer: authorized.withdrawer,
+            }]
+        );
+        // The staker sits at the offset used to fetch the stake accounts
+        assert_eq!(
+            &accounts[0].1.data[STAKER_OFFSET..STAKER_OFFSET + 32],
+            authorized.staker.as_ref()
+        );
+    }
+}
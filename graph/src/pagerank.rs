@@ -0,0 +1,159 @@
+//! Signed PageRank: trust flows along the upvotes like in a personalized PageRank, while the
+//! downvotes of a wallet take away from their targets in proportion to the trust of that wallet.
+//! Distrust doesn't propagate further, the opinions of a distrusted wallet are simply worth
+//! little.
+
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::graph::{iterate, Convergence, TrustGraph};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PageRankConfig {
+    /// Probability of following a vote rather than jumping back to a seed
+    pub damping: f64,
+    pub convergence: Convergence,
+}
+
+impl Default for PageRankConfig {
+    fn default() -> Self {
+        Self {
+            damping: 0.85,
+            convergence: Convergence::default(),
+        }
+    }
+}
+
+/// Trust and distrust of the wallets, indexed like the nodes of the graph
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedPageRank {
+    /// Personalized PageRank over the upvotes
+    pub trust: Vec<f64>,
+    /// Trust taken away by the downvotes
+    pub distrust: Vec<f64>,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+impl SignedPageRank {
+    /// The trust of each wallet minus its distrust
+    pub fn scores(&self) -> Vec<f64> {
+        self.trust
+            .iter()
+            .zip(&self.distrust)
+            .map(|(trust, distrust)| trust - distrust)
+            .collect()
+    }
+}
+
+/// Computes the signed PageRank of every wallet of `graph`, the random walk jumping back to the
+/// `seeds`, or to any wallet when none is given. Each wallet splits its trust between all its
+/// votes in proportion to their weight, the share of the downvotes becoming the distrust of
+/// their targets. The trust adds up to 1, the distrust being bounded by it. Fails when none of
+/// the `seeds` is in the graph
+pub fn signed_pagerank(
+    graph: &TrustGraph,
+    seeds: &[Pubkey],
+    config: PageRankConfig,
+) -> Result<SignedPageRank> {
+    if graph.is_empty() {
+        return Ok(SignedPageRank {
+            trust: vec![],
+            distrust: vec![],
+            iterations: 0,
+            converged: true,
+        });
+    }
+    let teleport = graph.seed_distribution(seeds)?;
+
+    // Share of the trust of each wallet given to each of its votes, negative for downvotes
+    let shares = (0..graph.len())
+        .map(|node| {
+            let total = graph
+                .outgoing(node)
+                .map(|edge| edge.weight.unsigned_abs() as f64)
+                .sum::<f64>();
+            graph
+                .outgoing(node)
+                .map(|edge| (edge.to, edge.weight as f64 / total))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let d = config.damping;
+    let result = iterate(teleport.clone(), config.convergence, |trust| {
+        let mut next = teleport.iter().map(|p| (1.0 - d) * p).collect::<Vec<_>>();
+        // The walk jumps back to the seeds from wallets that didn't vote, and instead of
+        // following downvotes, so that the trust always adds up to 1
+        let mut dangling = 0.0;
+        for (node, votes) in shares.iter().enumerate() {
+            if votes.is_empty() {
+                dangling += trust[node];
+            }
+            for (to, share) in votes {
+                if *share > 0.0 {
+                    next[*to] += d * trust[node] * share;
+                } else {
+                    dangling -= trust[node] * share;
+                }
+            }
+        }
+        for (score, p) in next.iter_mut().zip(&teleport) {
+            *score += d * dangling * p;
+        }
+        next
+    });
+
+    let mut distrust = vec![0.0; graph.len()];
+    for (node, votes) in shares.iter().enumerate() {
+        for (to, share) in votes.iter().filter(|(_, share)| *share < 0.0) {
+            distrust[*to] -= d * result.scores[node] * share;
+        }
+    }
+
+    Ok(SignedPageRank {
+        trust: result.scores,
+        distrust,
+        iterations: result.iterations,
+        converged: result.converged,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::vote;
+
+    use super::*;
+
+    #[test]
+    fn test_signed_pagerank() {
+        let keys = [0; 5].map(|_| Pubkey::new_unique());
+        let [seed, trusted, scammer, ring_a, ring_b] = keys;
+        let votes = [
+            vote(&seed, &trusted, 10),
+            vote(&seed, &scammer, -10),
+            vote(&trusted, &scammer, -1),
+            // Downvotes of wallets nobody trusts don't matter
+            vote(&ring_a, &trusted, -1_000),
+            vote(&ring_a, &ring_b, 1_000),
+            vote(&ring_b, &ring_a, 1_000),
+        ];
+        let graph = TrustGraph::from_votes(&votes);
+        let result = signed_pagerank(&graph, &[seed], PageRankConfig::default()).unwrap();
+        assert!(result.converged);
+        assert!((result.trust.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        let scores = result.scores();
+        let score = |key: &Pubkey| scores[graph.index_of(key).unwrap()];
+        assert!(score(&trusted) > 0.0);
+        assert!(score(&scammer) < 0.0);
+        assert_eq!(score(&ring_a), 0.0);
+        assert_eq!(score(&ring_b), 0.0);
+        assert_eq!(result.distrust[graph.index_of(&trusted).unwrap()], 0.0);
+
+        // Without seeds every wallet is trusted a priori, and the ring reinforces itself
+        let unseeded = signed_pagerank(&graph, &[], PageRankConfig::default()).unwrap();
+        let index = |key: &Pubkey| graph.index_of(key).unwrap();
+        assert!(unseeded.trust[index(&ring_a)] > unseeded.trust[index(&seed)]);
+    }
+}
@@ -0,0 +1,233 @@
+//! Trust paths: chains of votes leading from trusted wallets to another one. Trust only flows
+//! through upvotes, so every hop of a path but the last one is an upvote, the last one being a
+//! downvote when the path carries distrust.
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+};
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::graph::{Edge, TrustGraph};
+
+/// A vote along a trust path
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hop {
+    pub voter: Pubkey,
+    pub votee: Pubkey,
+    /// Weighted value of the vote, negative for downvotes
+    pub weight: i64,
+    /// Share of the voting weight of the voter carried by this vote
+    pub share: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrustPath {
+    pub hops: Vec<Hop>,
+}
+
+impl TrustPath {
+    /// Product of the shares of the hops, from 0 to 1
+    pub fn strength(&self) -> f64 {
+        self.hops.iter().map(|hop| hop.share).product()
+    }
+
+    /// Whether the path ends with a downvote
+    pub fn is_distrust(&self) -> bool {
+        self.hops.last().is_some_and(|hop| hop.weight < 0)
+    }
+}
+
+/// Share of the voting weight of its voter carried by `edge`
+fn share(graph: &TrustGraph, edge: &Edge) -> f64 {
+    let total = graph
+        .outgoing(edge.from)
+        .map(|edge| edge.weight.unsigned_abs() as f64)
+        .sum::<f64>();
+    edge.weight.unsigned_abs() as f64 / total
+}
+
+/// Builds the path ending with `last` from the edge through which each node was reached
+fn build_path(graph: &TrustGraph, reached_by: &[Option<&Edge>], last: &Edge) -> TrustPath {
+    let mut hops = vec![];
+    let mut edge = Some(last);
+    while let Some(current) = edge {
+        hops.push(Hop {
+            voter: graph.nodes()[current.from],
+            votee: graph.nodes()[current.to],
+            weight: current.weight,
+            share: share(graph, current),
+        });
+        edge = reached_by[current.from];
+    }
+    hops.reverse();
+    TrustPath { hops }
+}
+
+/// Finds a path from any of the `from` wallets to `to` with the fewest hops, or `None` when
+/// `to` can't be reached. The path is empty when `to` is one of the `from` wallets
+pub fn shortest_path(graph: &TrustGraph, from: &[Pubkey], to: &Pubkey) -> Option<TrustPath> {
+    let target = graph.index_of(to)?;
+    let mut reached_by = vec![None; graph.len()];
+    let mut visited = vec![false; graph.len()];
+    let mut queue = VecDeque::new();
+    for source in from.iter().filter_map(|key| graph.index_of(key)) {
+        if source == target {
+            return Some(TrustPath { hops: vec![] });
+        }
+        if !visited[source] {
+            visited[source] = true;
+            queue.push_back(source);
+        }
+    }
+
+    while let Some(node) = queue.pop_front() {
+        for edge in graph.outgoing(node) {
+            if edge.to == target {
+                return Some(build_path(graph, &reached_by, edge));
+            }
+            // Downvotes end the paths, as distrust doesn't propagate
+            if edge.weight > 0 && !visited[edge.to] {
+                visited[edge.to] = true;
+                reached_by[edge.to] = Some(edge);
+                queue.push_back(edge.to);
+            }
+        }
+    }
+    None
+}
+
+/// Node reached with the given strength, ordered by strength for the heap
+struct Candidate {
+    strength: f64,
+    node: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.strength
+            .total_cmp(&other.strength)
+            .then(other.node.cmp(&self.node))
+    }
+}
+
+/// Finds the path from any of the `from` wallets to `to` with the largest strength, or `None`
+/// when `to` can't be reached. The path is empty when `to` is one of the `from` wallets
+pub fn strongest_path(graph: &TrustGraph, from: &[Pubkey], to: &Pubkey) -> Option<TrustPath> {
+    let target = graph.index_of(to)?;
+    let mut reached_by = vec![None; graph.len()];
+    let mut strengths = vec![0.0; graph.len()];
+    let mut done = vec![false; graph.len()];
+    let mut heap = BinaryHeap::new();
+    for source in from.iter().filter_map(|key| graph.index_of(key)) {
+        if source == target {
+            return Some(TrustPath { hops: vec![] });
+        }
+        strengths[source] = 1.0;
+        heap.push(Candidate {
+            strength: 1.0,
+            node: source,
+        });
+    }
+
+    // Dijkstra's algorithm, the strength of a path only decreasing with every hop. The target
+    // is reached through its strongest incoming vote, downvotes included
+    let mut best: Option<(f64, &Edge)> = None;
+    while let Some(Candidate { strength, node }) = heap.pop() {
+        if done[node] {
+            continue;
+        }
+        if best.is_some_and(|(best, _)| best >= strength) {
+            break;
+        }
+        done[node] = true;
+        for edge in graph.outgoing(node) {
+            let reached = strength * share(graph, edge);
+            if edge.to == target {
+                if best.is_none_or(|(best, _)| reached > best) {
+                    best = Some((reached, edge));
+                }
+            } else if edge.weight > 0 && !done[edge.to] && reached > strengths[edge.to] {
+                strengths[edge.to] = reached;
+                reached_by[edge.to] = Some(edge);
+                heap.push(Candidate {
+                    strength: reached,
+                    node: edge.to,
+                });
+            }
+        }
+    }
+    best.map(|(_, last)| build_path(graph, &reached_by, last))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::vote;
+
+    use super::*;
+
+    #[test]
+    fn test_trust_paths() {
+        let keys = [0; 6].map(|_| Pubkey::new_unique());
+        let [seed, a, b, c, target, isolated] = keys;
+        let votes = [
+            // Short but weak path through a
+            vote(&seed, &a, 1),
+            vote(&seed, &b, 9),
+            vote(&a, &target, -1),
+            // Longer but stronger path through b and c
+            vote(&b, &c, 4),
+            vote(&c, &target, 1),
+            // Trust doesn't flow through downvotes
+            vote(&seed, &isolated, -1),
+            vote(&isolated, &c, 1),
+            vote(&c, &isolated, 1),
+        ];
+        let graph = TrustGraph::from_votes(&votes);
+
+        let shortest = shortest_path(&graph, &[seed], &target).unwrap();
+        let hops = shortest
+            .hops
+            .iter()
+            .map(|hop| (hop.voter, hop.votee, hop.weight))
+            .collect::<Vec<_>>();
+        assert_eq!(hops, vec![(seed, a, 1), (a, target, -1)]);
+        assert!(shortest.is_distrust());
+        assert!((shortest.strength() - 1.0 / 11.0).abs() < 1e-12);
+
+        let strongest = strongest_path(&graph, &[seed], &target).unwrap();
+        let hops = strongest
+            .hops
+            .iter()
+            .map(|hop| (hop.voter, hop.votee, hop.weight))
+            .collect::<Vec<_>>();
+        assert_eq!(hops, vec![(seed, b, 9), (b, c, 4), (c, target, 1)]);
+        assert!(!strongest.is_distrust());
+        assert!((strongest.strength() - 9.0 / 11.0 / 2.0).abs() < 1e-12);
+
+        // The downvote reaches isolated, but nothing flows further from it
+        let to_isolated = shortest_path(&graph, &[seed], &isolated).unwrap();
+        assert_eq!(to_isolated.hops.len(), 1);
+        assert!(shortest_path(&graph, &[isolated], &seed).is_none());
+        assert!(strongest_path(&graph, &[target], &seed).is_none());
+        assert_eq!(
+            strongest_path(&graph, &[a, seed], &seed).unwrap().hops,
+            vec![]
+        );
+    }
+}
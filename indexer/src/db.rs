@@ -0,0 +1,392 @@
+//! SQLite database of the indexer. `vote_history` is append-only, holding every vote cast by the
+//! successful transactions of the program. The current votes and scores are derived from
+//! it by the `current_votes` and `derived_scores` views.
+
+use std::path::Path;
+
+use rusqlite::{functions::FunctionFlags, params, Connection, OptionalExtension, Row};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+use sns_reputation::state::reputation_score::{wilson_lower_bound, WILSON_Z_95};
+
+use crate::parse::VoteRecord;
+
+const SCHEMA: &str = "
+PRAGMA journal_mode = WAL;
+
+CREATE TABLE IF NOT EXISTS vote_history (
+    -- Order in which the votes were executed
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    voter TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    value INTEGER NOT NULL,
+    weight INTEGER,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    signature TEXT NOT NULL,
+    instruction_index INTEGER NOT NULL,
+    UNIQUE (signature, instruction_index)
+);
+CREATE INDEX IF NOT EXISTS vote_history_voter ON vote_history (voter, subject);
+CREATE INDEX IF NOT EXISTS vote_history_subject ON vote_history (subject);
+
+-- Last transaction indexed, from which the next run resumes
+CREATE TABLE IF NOT EXISTS cursor (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL
+);
+
+-- Latest vote of each voter on each subject, undone votes included
+CREATE VIEW IF NOT EXISTS current_votes AS
+SELECT * FROM vote_history AS vote
+WHERE id = (
+    SELECT MAX(id) FROM vote_history
+    WHERE voter = vote.voter AND subject = vote.subject
+);
+
+-- Votes of unknown weight count as voters but not in the tallies
+CREATE VIEW IF NOT EXISTS derived_scores AS
+SELECT
+    subject,
+    COALESCE(SUM(CASE WHEN value > 0 THEN weight END), 0) AS upvote,
+    COALESCE(SUM(CASE WHEN value < 0 THEN weight END), 0) AS downvote,
+    COUNT(CASE WHEN value > 0 THEN 1 END) AS upvoters,
+    COUNT(CASE WHEN value < 0 THEN 1 END) AS downvoters
+FROM current_votes
+WHERE value != 0
+GROUP BY subject;
+";
+
+/// A row of `vote_history`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub voter: Pubkey,
+    pub subject: Pubkey,
+    /// 1 for an upvote, -1 for a downvote and 0 when the vote is undone
+    pub value: i64,
+    /// Unknown for transactions executed before the program logged it
+    pub weight: Option<u64>,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub signature: Signature,
+}
+
+/// Tallies of a subject derived from the history of its votes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DerivedScore {
+    pub upvote: u64,
+    pub downvote: u64,
+    pub upvoters: u64,
+    pub downvoters: u64,
+}
+
+/// A transaction of the program, with the votes it cast
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedTransaction {
+    pub signature: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub votes: Vec<VoteRecord>,
+}
+
+pub struct Database {
+    connection: Connection,
+}
+
+fn parse_key<T: std::str::FromStr>(index: usize, value: String) -> rusqlite::Result<T> {
+    value.parse().map_err(|_| {
+        rusqlite::Error::FromSqlConversionFailure(
+            index,
+            rusqlite::types::Type::Text,
+            format!("Invalid key {value}").into(),
+        )
+    })
+}
+
+/// Reads a row of `vote_history` selected in the order of the fields of `HistoryEntry`
+fn history_entry(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        voter: parse_key(0, row.get(0)?)?,
+        subject: parse_key(1, row.get(1)?)?,
+        value: row.get(2)?,
+        weight: row.get(3)?,
+        slot: row.get(4)?,
+        block_time: row.get(5)?,
+        signature: parse_key(6, row.get(6)?)?,
+    })
+}
+
+impl Database {
+    /// Opens the database at `path`, creating the tables if needed
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> rusqlite::Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        // The Wilson score of the program, from its upvoters and downvoters, for the queries
+        // ordering the subjects by it
+        connection.create_scalar_function(
+            "wilson_score",
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let score = wilson_lower_bound(ctx.get(0)?, ctx.get(1)?, WILSON_Z_95);
+                Ok(score as i64)
+            },
+        )?;
+        Ok(Self { connection })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// The last transaction indexed, if any
+    pub fn cursor(&self) -> rusqlite::Result<Option<Signature>> {
+        self.connection
+            .query_row("SELECT signature FROM cursor WHERE id = 0", [], |row| {
+                parse_key(0, row.get(0)?)
+            })
+            .optional()
+    }
+
+    /// Appends the votes of `transaction` to the history and moves the cursor to it, unless it
+    /// is older than the cursor. Indexing a transaction again has no effect
+    pub fn index_transaction(&mut self, transaction: &IndexedTransaction) -> rusqlite::Result<()> {
+        let signature = transaction.signature.to_string();
+        let db_transaction = self.connection.transaction()?;
+        for vote in &transaction.votes {
+            db_transaction.execute(
+                "INSERT OR IGNORE INTO vote_history (voter, subject, value, weight, slot,
+                    block_time, signature, instruction_index)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    vote.voter.to_string(),
+                    vote.subject.to_string(),
+                    vote.value,
+                    vote.weight,
+                    transaction.slot,
+                    transaction.block_time,
+                    signature,
+                    vote.instruction_index,
+                ],
+            )?;
+        }
+        db_transaction.execute(
+            "INSERT INTO cursor (id, signature, slot) VALUES (0, ?1, ?2)
+            ON CONFLICT (id) DO UPDATE SET signature = excluded.signature, slot = excluded.slot
+            WHERE excluded.slot >= cursor.slot",
+            params![signature, transaction.slot],
+        )?;
+        db_transaction.commit()
+    }
+
+    /// The votes cast on `subject`, oldest first
+    pub fn history(&self, subject: &Pubkey) -> rusqlite::Result<Vec<HistoryEntry>> {
+        let mut statement = self.connection.prepare(
+            "SELECT voter, subject, value, weight, slot, block_time, signature FROM vote_history
+            WHERE subject = ?1 ORDER BY id",
+        )?;
+        let entries = statement
+            .query_map([subject.to_string()], history_entry)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    /// The votes currently cast by `voter` on `subject`, oldest first, either of them matching
+    /// any key when `None`. Undone votes are left out
+    pub fn current_votes(
+        &self,
+        voter: Option<&Pubkey>,
+        subject: Option<&Pubkey>,
+    ) -> rusqlite::Result<Vec<HistoryEntry>> {
+        let mut statement = self.connection.prepare(
+            "SELECT voter, subject, value, weight, slot, block_time, signature FROM current_votes
+            WHERE value != 0 AND (?1 IS NULL OR voter = ?1) AND (?2 IS NULL OR subject = ?2)
+            ORDER BY id",
+        )?;
+        let entries = statement
+            .query_map(
+                params![
+                    voter.map(|key| key.to_string()),
+                    subject.map(|key| key.to_string()),
+                ],
+                history_entry,
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    /// The tallies of `subject` derived from its history, zero when it was never voted on
+    pub fn derived_score(&self, subject: &Pubkey) -> rusqlite::Result<DerivedScore> {
+        let score = self
+            .connection
+            .query_row(
+                "SELECT upvote, downvote, upvoters, downvoters FROM derived_scores
+                WHERE subject = ?1",
+                [subject.to_string()],
+                |row| {
+                    Ok(DerivedScore {
+                        upvote: row.get(0)?,
+                        downvote: row.get(1)?,
+                        upvoters: row.get(2)?,
+                        downvoters: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(score.unwrap_or_default())
+    }
+
+    /// The tallies of the `limit` subjects with the highest Wilson score, ties being broken by
+    /// the highest upvote tally and then by subject
+    pub fn leaderboard(&self, limit: usize) -> rusqlite::Result<Vec<(Pubkey, DerivedScore)>> {
+        let mut statement = self.connection.prepare(
+            "SELECT subject, upvote, downvote, upvoters, downvoters FROM derived_scores
+            ORDER BY wilson_score(upvoters, downvoters) DESC, upvote DESC, subject
+            LIMIT ?1",
+        )?;
+        let scores = statement
+            .query_map([limit as i64], |row| {
+                Ok((
+                    parse_key(0, row.get(0)?)?,
+                    DerivedScore {
+                        upvote: row.get(1)?,
+                        downvote: row.get(2)?,
+                        upvoters: row.get(3)?,
+                        downvoters: row.get(4)?,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(scores)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let mut db = Database::open_in_memory().unwrap();
+        assert_eq!(db.cursor().unwrap(), None);
+
+        let [alice, bob, subject] = [0; 3].map(|_| Pubkey::new_unique());
+        let vote = |voter: &Pubkey, value: i64, weight: Option<u64>| VoteRecord {
+            instruction_index: 0,
+            voter: *voter,
+            subject,
+            value,
+            weight,
+        };
+        let transactions = [
+            (1, vote(&alice, 1, Some(10))),
+            (2, vote(&bob, -1, Some(4))),
+            // Alice changes her vote, then undoes it
+            (3, vote(&alice, -1, Some(10))),
+            (4, vote(&alice, 0, Some(0))),
+            // A vote of unknown weight
+            (5, vote(&Pubkey::new_unique(), 1, None)),
+        ]
+        .map(|(slot, vote)| IndexedTransaction {
+            signature: Signature::new_unique(),
+            slot,
+            block_time: None,
+            votes: vec![vote],
+        });
+        for transaction in &transactions {
+            db.index_transaction(transaction).unwrap();
+        }
+        // Indexing a transaction twice doesn't duplicate its votes
+        db.index_transaction(&transactions[0]).unwrap();
+
+        let history = db.history(&subject).unwrap();
+        assert_eq!(history.len(), 5);
+        assert_eq!(
+            history
+                .iter()
+                .map(|entry| (entry.slot, entry.value))
+                .collect::<Vec<_>>(),
+            vec![(1, 1), (2, -1), (3, -1), (4, 0), (5, 1)]
+        );
+        assert_eq!(history[0].signature, transactions[0].signature);
+
+        assert_eq!(
+            db.derived_score(&subject).unwrap(),
+            DerivedScore {
+                upvote: 0,
+                downvote: 4,
+                upvoters: 1,
+                downvoters: 1,
+            }
+        );
+        assert_eq!(db.derived_score(&alice).unwrap(), DerivedScore::default());
+        assert_eq!(db.cursor().unwrap(), Some(transactions[4].signature));
+
+        let current = db.current_votes(None, Some(&subject)).unwrap();
+        assert_eq!(
+            current
+                .iter()
+                .map(|entry| (entry.slot, entry.value))
+                .collect::<Vec<_>>(),
+            vec![(2, -1), (5, 1)]
+        );
+        assert_eq!(db.current_votes(Some(&bob), None).unwrap(), current[..1]);
+        assert!(db.current_votes(Some(&alice), None).unwrap().is_empty());
+        assert_eq!(
+            db.leaderboard(10).unwrap(),
+            vec![(subject, db.derived_score(&subject).unwrap())]
+        );
+    }
+
+    #[test]
+    fn test_leaderboard() {
+        let mut db = Database::open_in_memory().unwrap();
+        let [popular, disputed, new, unpopular] = [0; 4].map(|_| Pubkey::new_unique());
+        // Upvoters and downvoters of each subject, with the weight of their votes
+        let votes = [
+            (popular, 8, 0, 1),
+            (disputed, 5, 5, 100),
+            (new, 1, 0, 1_000),
+            (unpopular, 0, 3, 1),
+        ];
+        for (subject, upvoters, downvoters, weight) in votes {
+            let values = [vec![1; upvoters], vec![-1; downvoters]].concat();
+            db.index_transaction(&IndexedTransaction {
+                signature: Signature::new_unique(),
+                slot: 1,
+                block_time: None,
+                votes: values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(instruction_index, value)| VoteRecord {
+                        instruction_index,
+                        voter: Pubkey::new_unique(),
+                        subject,
+                        value,
+                        weight: Some(weight),
+                    })
+                    .collect(),
+            })
+            .unwrap();
+        }
+
+        let subjects = |limit| {
+            db.leaderboard(limit)
+                .unwrap()
+                .into_iter()
+                .map(|(subject, _)| subject)
+                .collect::<Vec<_>>()
+        };
+        // Ordered by the share of upvoters, the weight of the votes not counting
+        assert_eq!(subjects(10), vec![popular, disputed, new, unpopular]);
+        assert_eq!(subjects(2), vec![popular, disputed]);
+    }
+}
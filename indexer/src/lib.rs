@@ -0,0 +1,6 @@
+//! Indexer replaying the votes cast by the program into a SQLite history of the votes,
+//! from which the current votes and the scores are derived.
+
+pub mod db;
+pub mod parse;
+pub mod rpc;
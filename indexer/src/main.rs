@@ -0,0 +1,49 @@
+use std::{path::PathBuf, thread, time::Duration};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+use sns_reputation_indexer::{db::Database, rpc::sync};
+
+#[derive(Parser)]
+#[command(about = "Replays the votes of the SNS Reputation program into a SQLite history")]
+struct Cli {
+    /// RPC node to read the transactions from
+    #[arg(
+        long,
+        short,
+        env = "RPC_URL",
+        default_value = "https://api.mainnet-beta.solana.com"
+    )]
+    url: String,
+
+    /// Address of the program
+    #[arg(long, default_value_t = sns_reputation::ID)]
+    program_id: Pubkey,
+
+    /// SQLite database to write the history to, created if needed
+    #[arg(long, default_value = "indexer.sqlite")]
+    database: PathBuf,
+
+    /// Keep indexing the new transactions, polling the node every this many seconds
+    #[arg(long)]
+    poll_interval: Option<u64>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let client = RpcClient::new(cli.url);
+    let mut db = Database::open(&cli.database)
+        .with_context(|| format!("Failed to open {}", cli.database.display()))?;
+
+    loop {
+        let indexed = sync(&client, &mut db, &cli.program_id)?;
+        println!("Indexed {indexed} transactions");
+        let Some(interval) = cli.poll_interval else {
+            return Ok(());
+        };
+        thread::sleep(Duration::from_secs(interval));
+    }
+}
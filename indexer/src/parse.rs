@@ -0,0 +1,354 @@
+//! Decoding of the instructions casting votes executed by a transaction: Vote, DelegatedVote,
+//! RelayVote, RevealVote and PropagateCuratorVote. The weight of a vote isn't part of the
+//! instruction, it is read from the log the program writes once the vote is cast.
+
+use borsh::BorshDeserialize;
+use solana_sdk::pubkey::Pubkey;
+
+use sns_reputation::instruction::{
+    delegated_vote, propagate_curator_vote, relay_vote, reveal_vote, vote, ProgramInstruction,
+};
+
+/// Index of the voter in the accounts of a Vote or RevealVote instruction
+const VOTER_ACCOUNT_INDEX: usize = 2;
+
+/// Index of the staker the vote is attributed to in the accounts of a DelegatedVote instruction
+const STAKER_ACCOUNT_INDEX: usize = 3;
+
+/// Index of the voter who signed the vote in the accounts of a RelayVote instruction
+const RELAYED_VOTER_ACCOUNT_INDEX: usize = 3;
+
+/// Index of the follower in the accounts of a PropagateCuratorVote instruction
+const FOLLOWER_ACCOUNT_INDEX: usize = 2;
+
+/// An instruction executed by a transaction, either top-level or invoked by another program
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutedInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+/// A vote cast, changed or undone by one of the instructions casting votes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoteRecord {
+    /// Position of the instruction among the instructions executed by the transaction
+    pub instruction_index: usize,
+    pub voter: Pubkey,
+    pub subject: Pubkey,
+    /// 1 for an upvote, -1 for a downvote and 0 when the vote is undone
+    pub value: i64,
+    /// Weight of the vote, unknown for transactions executed before the program logged it
+    pub weight: Option<u64>,
+}
+
+/// Splits the `logs` of a transaction into the messages logged by each invocation of
+/// `program_id`, in the order of the invocations. Messages logged by the programs it invokes
+/// are left out
+pub fn program_logs<'a>(program_id: &Pubkey, logs: &'a [String]) -> Vec<Vec<&'a str>> {
+    let program_id = program_id.to_string();
+    let mut invocations: Vec<Vec<&str>> = vec![];
+    // Index in `invocations` of each program on the stack, `None` for other programs
+    let mut stack: Vec<Option<usize>> = vec![];
+    for line in logs {
+        if let Some(message) = line.strip_prefix("Program log: ") {
+            if let Some(Some(invocation)) = stack.last() {
+                invocations[*invocation].push(message);
+            }
+        } else if let Some((program, event)) = line
+            .strip_prefix("Program ")
+            .and_then(|line| line.split_once(' '))
+        {
+            if event.starts_with("invoke [") {
+                if program == program_id {
+                    invocations.push(vec![]);
+                    stack.push(Some(invocations.len() - 1));
+                } else {
+                    stack.push(None);
+                }
+            } else if event == "success" || event.starts_with("failed") {
+                stack.pop();
+            }
+        }
+    }
+    invocations
+}
+
+/// Decodes an instruction casting a vote into the index of the voter among its accounts, the
+/// subject and the value of the vote. A propagated vote takes the value of the curator's vote,
+/// which is only known from the logs
+fn decode_vote(data: &[u8]) -> Option<(usize, Pubkey, Option<i64>)> {
+    let (tag, data) = data.split_first()?;
+    let (voter_index, subject, vote_value) = if *tag == ProgramInstruction::Vote as u8 {
+        let params = vote::Params::try_from_slice(data).ok()?;
+        (
+            VOTER_ACCOUNT_INDEX,
+            params.user_key,
+            Some(params.vote_value),
+        )
+    } else if *tag == ProgramInstruction::DelegatedVote as u8 {
+        let params = delegated_vote::Params::try_from_slice(data).ok()?;
+        (
+            STAKER_ACCOUNT_INDEX,
+            params.user_key,
+            Some(params.vote_value),
+        )
+    } else if *tag == ProgramInstruction::RelayVote as u8 {
+        let params = relay_vote::Params::try_from_slice(data).ok()?;
+        (
+            RELAYED_VOTER_ACCOUNT_INDEX,
+            params.user_key,
+            Some(params.vote_value),
+        )
+    } else if *tag == ProgramInstruction::RevealVote as u8 {
+        let params = reveal_vote::Params::try_from_slice(data).ok()?;
+        (
+            VOTER_ACCOUNT_INDEX,
+            params.user_key,
+            Some(params.vote_value),
+        )
+    } else if *tag == ProgramInstruction::PropagateCuratorVote as u8 {
+        let params = propagate_curator_vote::Params::try_from_slice(data).ok()?;
+        (FOLLOWER_ACCOUNT_INDEX, params.user_key, None)
+    } else {
+        return None;
+    };
+    Some((
+        voter_index,
+        subject,
+        vote_value.map(|vote_value| vote_value as i64),
+    ))
+}
+
+/// Decodes the instructions of `program_id` casting votes among the `instructions` of a
+/// transaction, listed in the order in which they were executed, along with the `logs` of the
+/// transaction. Propagated votes executed before the program logged their value are left out
+pub fn parse_votes(
+    program_id: &Pubkey,
+    instructions: &[ExecutedInstruction],
+    logs: &[String],
+) -> Vec<VoteRecord> {
+    let logs = program_logs(program_id, logs);
+    instructions
+        .iter()
+        .enumerate()
+        .filter(|(_, instruction)| instruction.program_id == *program_id)
+        .enumerate()
+        .filter_map(|(invocation, (instruction_index, instruction))| {
+            let (voter_index, subject, value) = decode_vote(&instruction.data)?;
+            let logged_value = logs.get(invocation).and_then(|messages| {
+                messages.iter().find_map(|message| {
+                    message
+                        .strip_prefix(vote::VOTE_VALUE_LOG)?
+                        .parse::<i64>()
+                        .ok()
+                })
+            });
+            Some(VoteRecord {
+                instruction_index,
+                voter: *instruction.accounts.get(voter_index)?,
+                subject,
+                value: value.or_else(|| logged_value.map(i64::signum))?,
+                weight: logged_value.map(i64::unsigned_abs),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSerialize;
+    use sns_reputation::state::VoteValue;
+
+    use super::*;
+
+    fn instruction(
+        tag: ProgramInstruction,
+        params: &impl BorshSerialize,
+        voter_index: usize,
+        voter: &Pubkey,
+    ) -> ExecutedInstruction {
+        let mut data = vec![tag as u8];
+        params.serialize(&mut data).unwrap();
+        let mut accounts = vec![Pubkey::new_unique(); 10];
+        accounts[voter_index] = *voter;
+        ExecutedInstruction {
+            program_id: sns_reputation::ID,
+            accounts,
+            data,
+        }
+    }
+
+    fn vote_instruction(
+        voter: &Pubkey,
+        subject: &Pubkey,
+        vote_value: VoteValue,
+    ) -> ExecutedInstruction {
+        let params = vote::Params {
+            user_key: *subject,
+            vote_value,
+        };
+        instruction(
+            ProgramInstruction::Vote,
+            &params,
+            VOTER_ACCOUNT_INDEX,
+            voter,
+        )
+    }
+
+    #[test]
+    fn test_parse_votes() {
+        let program_id = sns_reputation::ID;
+        let (voter, subject) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let relayer = Pubkey::new_unique();
+        let instructions = [
+            vote_instruction(&voter, &subject, VoteValue::Downvote),
+            // Another program voting through CPI
+            ExecutedInstruction {
+                program_id: relayer,
+                accounts: vec![],
+                data: vec![],
+            },
+            vote_instruction(&relayer, &subject, VoteValue::Upvote),
+            ExecutedInstruction {
+                program_id,
+                accounts: vec![],
+                data: vec![ProgramInstruction::Aggregate as u8],
+            },
+        ];
+        let logs = [
+            format!("Program {program_id} invoke [1]"),
+            "Program log: Instruction: Vote Instruction".to_string(),
+            "Program log: Vote value: -42".to_string(),
+            format!("Program {program_id} consumed 20000 of 200000 compute units"),
+            format!("Program {program_id} success"),
+            format!("Program {relayer} invoke [1]"),
+            "Program log: Vote value: 1000".to_string(),
+            format!("Program {program_id} invoke [2]"),
+            "Program log: Vote value: 7".to_string(),
+            format!("Program {program_id} success"),
+            format!("Program {relayer} success"),
+            format!("Program {program_id} invoke [1]"),
+            format!("Program {program_id} success"),
+        ];
+
+        assert_eq!(
+            program_logs(&program_id, &logs),
+            vec![
+                vec!["Instruction: Vote Instruction", "Vote value: -42"],
+                vec!["Vote value: 7"],
+                vec![],
+            ]
+        );
+        assert_eq!(
+            parse_votes(&program_id, &instructions, &logs),
+            vec![
+                VoteRecord {
+                    instruction_index: 0,
+                    voter,
+                    subject,
+                    value: -1,
+                    weight: Some(42),
+                },
+                VoteRecord {
+                    instruction_index: 2,
+                    voter: relayer,
+                    subject,
+                    value: 1,
+                    weight: Some(7),
+                },
+            ]
+        );
+
+        // Transactions executed before the program logged the weight
+        let legacy_logs = [
+            format!("Program {program_id} invoke [1]"),
+            format!("Program {program_id} success"),
+        ];
+        let votes = parse_votes(&program_id, &instructions[..1], &legacy_logs);
+        assert_eq!(votes[0].weight, None);
+    }
+
+    #[test]
+    fn test_parse_other_votes() {
+        let program_id = sns_reputation::ID;
+        let subject = Pubkey::new_unique();
+        let [staker, relayed_voter, revealer, follower] = [0; 4].map(|_| Pubkey::new_unique());
+        let instructions = [
+            instruction(
+                ProgramInstruction::DelegatedVote,
+                &delegated_vote::Params {
+                    user_key: subject,
+                    vote_value: VoteValue::Downvote,
+                },
+                STAKER_ACCOUNT_INDEX,
+                &staker,
+            ),
+            instruction(
+                ProgramInstruction::RelayVote,
+                &relay_vote::Params {
+                    user_key: subject,
+                    vote_value: VoteValue::Upvote,
+                    nonce: 3,
+                    expiry: 1_000,
+                },
+                RELAYED_VOTER_ACCOUNT_INDEX,
+                &relayed_voter,
+            ),
+            instruction(
+                ProgramInstruction::RevealVote,
+                &reveal_vote::Params {
+                    user_key: subject,
+                    vote_value: VoteValue::Upvote,
+                    salt: [7; 32],
+                },
+                VOTER_ACCOUNT_INDEX,
+                &revealer,
+            ),
+            instruction(
+                ProgramInstruction::PropagateCuratorVote,
+                &propagate_curator_vote::Params { user_key: subject },
+                FOLLOWER_ACCOUNT_INDEX,
+                &follower,
+            ),
+        ];
+        let logs = ["-4", "5", "6", "-8"]
+            .iter()
+            .flat_map(|value| {
+                [
+                    format!("Program {program_id} invoke [1]"),
+                    format!("Program log: Vote value: {value}"),
+                    format!("Program {program_id} success"),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let record = |instruction_index, voter, value, weight| VoteRecord {
+            instruction_index,
+            voter,
+            subject,
+            value,
+            weight,
+        };
+        assert_eq!(
+            parse_votes(&program_id, &instructions, &logs),
+            vec![
+                record(0, staker, -1, Some(4)),
+                record(1, relayed_voter, 1, Some(5)),
+                record(2, revealer, 1, Some(6)),
+                // The value of a propagated vote is the one logged
+                record(3, follower, -1, Some(8)),
+            ]
+        );
+
+        // The value of a propagated vote is unknown without the log
+        let legacy_logs = [
+            format!("Program {program_id} invoke [1]"),
+            format!("Program {program_id} success"),
+        ];
+        assert_eq!(
+            parse_votes(&program_id, &instructions[3..], &legacy_logs),
+            vec![]
+        );
+    }
+}
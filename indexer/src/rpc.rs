@@ -0,0 +1,207 @@
+//! Walks the confirmed transactions of the program through an RPC node.
+
+use anyhow::{bail, Context, Result};
+use solana_client::{
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, instruction::CompiledInstruction, pubkey::Pubkey,
+    signature::Signature,
+};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiInnerInstructions, UiInstruction,
+    UiLoadedAddresses, UiTransactionEncoding,
+};
+
+use crate::{
+    db::{Database, IndexedTransaction},
+    parse::{parse_votes, ExecutedInstruction},
+};
+
+/// Returns the successful transactions of the program executed after `until`, or all of them
+/// when it is `None`, oldest first
+pub fn fetch_signatures(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    until: Option<Signature>,
+) -> Result<Vec<Signature>> {
+    let mut signatures = vec![];
+    let mut before = None;
+    // The node returns the newest transactions first, a page at a time
+    loop {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until,
+            limit: None,
+            commitment: Some(CommitmentConfig::confirmed()),
+        };
+        let page = client
+            .get_signatures_for_address_with_config(program_id, config)
+            .context("Failed to fetch the transactions of the program")?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = Some(last.signature.parse()?);
+        for status in page.into_iter().filter(|status| status.err.is_none()) {
+            signatures.push(status.signature.parse()?);
+        }
+    }
+    signatures.reverse();
+    Ok(signatures)
+}
+
+/// Lists the instructions executed by a transaction, each top-level instruction being followed
+/// by the instructions it invoked
+pub fn executed_instructions(
+    account_keys: &[Pubkey],
+    instructions: &[CompiledInstruction],
+    inner_instructions: &[UiInnerInstructions],
+) -> Result<Vec<ExecutedInstruction>> {
+    let key = |index: u8| -> Result<Pubkey> {
+        account_keys
+            .get(index as usize)
+            .copied()
+            .with_context(|| format!("Invalid account index {index}"))
+    };
+    let mut executed = vec![];
+    for (index, instruction) in instructions.iter().enumerate() {
+        executed.push(ExecutedInstruction {
+            program_id: key(instruction.program_id_index)?,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|index| key(*index))
+                .collect::<Result<_>>()?,
+            data: instruction.data.clone(),
+        });
+        let inner = inner_instructions
+            .iter()
+            .filter(|inner| inner.index as usize == index)
+            .flat_map(|inner| &inner.instructions);
+        for instruction in inner {
+            let UiInstruction::Compiled(instruction) = instruction else {
+                bail!("Inner instructions have to be requested in binary encoding");
+            };
+            executed.push(ExecutedInstruction {
+                program_id: key(instruction.program_id_index)?,
+                accounts: instruction
+                    .accounts
+                    .iter()
+                    .map(|index| key(*index))
+                    .collect::<Result<_>>()?,
+                data: bs58::decode(&instruction.data)
+                    .into_vec()
+                    .context("Invalid instruction data")?,
+            });
+        }
+    }
+    Ok(executed)
+}
+
+/// Decodes the votes cast by a confirmed transaction
+pub fn parse_transaction(
+    program_id: &Pubkey,
+    signature: Signature,
+    transaction: EncodedConfirmedTransactionWithStatusMeta,
+) -> Result<IndexedTransaction> {
+    let meta = transaction
+        .transaction
+        .meta
+        .with_context(|| format!("Missing status of {signature}"))?;
+    let decoded = transaction
+        .transaction
+        .transaction
+        .decode()
+        .with_context(|| format!("Invalid transaction {signature}"))?;
+
+    // Accounts loaded from lookup tables come after the static ones
+    let mut account_keys = decoded.message.static_account_keys().to_vec();
+    if let Some(UiLoadedAddresses { writable, readonly }) = Option::from(meta.loaded_addresses) {
+        for key in writable.iter().chain(&readonly) {
+            account_keys.push(key.parse()?);
+        }
+    }
+    let inner_instructions: Vec<_> = Option::from(meta.inner_instructions).unwrap_or_default();
+    let instructions = executed_instructions(
+        &account_keys,
+        decoded.message.instructions(),
+        &inner_instructions,
+    )?;
+    let logs: Vec<_> = Option::from(meta.log_messages).unwrap_or_default();
+
+    Ok(IndexedTransaction {
+        signature,
+        slot: transaction.slot,
+        block_time: transaction.block_time,
+        votes: parse_votes(program_id, &instructions, &logs),
+    })
+}
+
+/// Indexes the transactions of the program executed since the last run, returning the number of
+/// transactions indexed
+pub fn sync(client: &RpcClient, db: &mut Database, program_id: &Pubkey) -> Result<usize> {
+    let signatures = fetch_signatures(client, program_id, db.cursor()?)?;
+    for signature in &signatures {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        let transaction = client
+            .get_transaction_with_config(signature, config)
+            .with_context(|| format!("Failed to fetch the transaction {signature}"))?;
+        db.index_transaction(&parse_transaction(program_id, *signature, transaction)?)?;
+    }
+    Ok(signatures.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_executed_instructions() {
+        let keys = [0; 4].map(|_| Pubkey::new_unique());
+        let instructions = [
+            CompiledInstruction::new_from_raw_parts(1, vec![1], vec![0, 2]),
+            CompiledInstruction::new_from_raw_parts(3, vec![2], vec![]),
+        ];
+        // As returned by `getTransaction` with the base64 encoding
+        let inner_instructions: Vec<UiInnerInstructions> =
+            serde_json::from_value(serde_json::json!([{
+                "index": 0,
+                "instructions": [{
+                    "programIdIndex": 2,
+                    "accounts": [0],
+                    "data": bs58::encode([7, 8]).into_string(),
+                }],
+            }]))
+            .unwrap();
+
+        let executed = executed_instructions(&keys, &instructions, &inner_instructions).unwrap();
+        assert_eq!(
+            executed,
+            vec![
+                ExecutedInstruction {
+                    program_id: keys[1],
+                    accounts: vec![keys[0], keys[2]],
+                    data: vec![1],
+                },
+                ExecutedInstruction {
+                    program_id: keys[2],
+                    accounts: vec![keys[0]],
+                    data: vec![7, 8],
+                },
+                ExecutedInstruction {
+                    program_id: keys[3],
+                    accounts: vec![],
+                    data: vec![2],
+                },
+            ]
+        );
+
+        let invalid = [CompiledInstruction::new_from_raw_parts(4, vec![], vec![])];
+        assert!(executed_instructions(&keys, &invalid, &[]).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use sns_reputation::{
+    entrypoint::process_instruction,
+    instruction::{commit_vote, reveal_vote, vote},
+    state::{
+        config::Config,
+        reputation_score::ReputationScore,
+        user_vote::{UserVote, COMMIT_PERIOD_SLOTS},
+        voter_profile::VoterProfile,
+        VoteValue,
+    },
+};
+use sns_reputation_indexer::{
+    db::{Database, DerivedScore, IndexedTransaction},
+    parse::{parse_votes, ExecutedInstruction},
+};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    instruction::Instruction, pubkey::Pubkey, signature::Signer, system_program,
+    transaction::Transaction,
+};
+
+/// Executes `instruction` signed by the payer of `ctx`, and decodes the executed transaction as
+/// the indexer would if it was executed at `slot`
+async fn execute(
+    ctx: &mut ProgramTestContext,
+    instruction: Instruction,
+    slot: u64,
+) -> IndexedTransaction {
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        std::slice::from_ref(&instruction),
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer],
+        blockhash,
+    );
+    let signature = transaction.signatures[0];
+    let result = ctx
+        .banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    result.result.unwrap();
+    let logs = result.metadata.unwrap().log_messages;
+
+    let instructions = [ExecutedInstruction {
+        program_id: instruction.program_id,
+        accounts: instruction
+            .accounts
+            .iter()
+            .map(|account| account.pubkey)
+            .collect(),
+        data: instruction.data,
+    }];
+    IndexedTransaction {
+        signature,
+        slot,
+        block_time: None,
+        votes: parse_votes(&sns_reputation::ID, &instructions, &logs),
+    }
+}
+
+/// Votes on `subject` with the payer of `ctx`
+async fn cast_vote(
+    ctx: &mut ProgramTestContext,
+    subject: &Pubkey,
+    vote_value: VoteValue,
+    slot: u64,
+) -> IndexedTransaction {
+    let program_id = sns_reputation::ID;
+    let voter = ctx.payer.pubkey();
+    let (reputation_state_account, _) = ReputationScore::find_key(&program_id, subject);
+    let (user_vote_state_account, _) = UserVote::find_key(&program_id, &(*subject, voter));
+    let (config_account, _) = Config::find_key(&program_id);
+    let (voter_profile_account, _) = VoterProfile::find_key(&program_id, &voter);
+    let instruction = vote(
+        vote::Accounts {
+            system_program: &system_program::ID,
+            rent_payer: &voter,
+            voter: &voter,
+            reputation_state_account: &reputation_state_account,
+            user_vote_state_account: &user_vote_state_account,
+            voter_profile_account: &voter_profile_account,
+            config_account: &config_account,
+            voter_reputation_account: None,
+            voter_stake_accounts: &[],
+        },
+        vote::Params {
+            user_key: *subject,
+            vote_value,
+        },
+    );
+    execute(ctx, instruction, slot).await
+}
+
+/// Commits to a vote on `subject` with the payer of `ctx` at `slot`, then reveals it once the
+/// commit period is over
+async fn commit_and_reveal_vote(
+    ctx: &mut ProgramTestContext,
+    subject: &Pubkey,
+    vote_value: VoteValue,
+    slot: u64,
+) -> (IndexedTransaction, IndexedTransaction) {
+    let program_id = sns_reputation::ID;
+    let voter = ctx.payer.pubkey();
+    let (reputation_state_account, _) = ReputationScore::find_key(&program_id, subject);
+    let (user_vote_state_account, _) = UserVote::find_key(&program_id, &(*subject, voter));
+    let (config_account, _) = Config::find_key(&program_id);
+    let (voter_profile_account, _) = VoterProfile::find_key(&program_id, &voter);
+    let salt = [7; 32];
+    let commit = commit_vote(
+        commit_vote::Accounts {
+            system_program: &system_program::ID,
+            rent_payer: &voter,
+            voter: &voter,
+            user_vote_state_account: &user_vote_state_account,
+            voter_profile_account: &voter_profile_account,
+            config_account: &config_account,
+        },
+        commit_vote::Params {
+            user_key: *subject,
+            commitment: UserVote::compute_commitment(subject, &voter, vote_value, &salt),
+        },
+    );
+    let reveal = reveal_vote(
+        reveal_vote::Accounts {
+            system_program: &system_program::ID,
+            rent_payer: &voter,
+            voter: &voter,
+            reputation_state_account: &reputation_state_account,
+            user_vote_state_account: &user_vote_state_account,
+            voter_profile_account: &voter_profile_account,
+            voter_reputation_account: None,
+            voter_stake_accounts: &[],
+        },
+        reveal_vote::Params {
+            user_key: *subject,
+            vote_value,
+            salt,
+        },
+    );
+    let commit = execute(ctx, commit, slot).await;
+    let reveal_slot = slot + COMMIT_PERIOD_SLOTS + 1;
+    ctx.warp_to_slot(reveal_slot).unwrap();
+    (commit, execute(ctx, reveal, reveal_slot).await)
+}
+
+#[tokio::test]
+async fn test_replay_votes() {
+    let program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let mut ctx = program_test.start_with_context().await;
+    let mut db = Database::open_in_memory().unwrap();
+    let subject = Pubkey::new_unique();
+
+    let upvote = cast_vote(&mut ctx, &subject, VoteValue::Upvote, 1).await;
+    assert_eq!(upvote.votes.len(), 1);
+    assert_eq!(upvote.votes[0].voter, ctx.payer.pubkey());
+    assert_eq!(upvote.votes[0].value, 1);
+    // Votes have a weight of 1 with the devnet feature
+    assert_eq!(upvote.votes[0].weight, Some(1));
+    db.index_transaction(&upvote).unwrap();
+    assert_eq!(
+        db.derived_score(&subject).unwrap(),
+        DerivedScore {
+            upvote: 1,
+            upvoters: 1,
+            ..DerivedScore::default()
+        }
+    );
+
+    let undo = cast_vote(&mut ctx, &subject, VoteValue::NoVote, 2).await;
+    assert_eq!(undo.votes[0].value, 0);
+    db.index_transaction(&undo).unwrap();
+    assert_eq!(db.derived_score(&subject).unwrap(), DerivedScore::default());
+    assert_eq!(db.history(&subject).unwrap().len(), 2);
+    assert_eq!(db.cursor().unwrap(), Some(undo.signature));
+
+    // Committing to a vote doesn't cast it, revealing it does
+    let (commit, reveal) = commit_and_reveal_vote(&mut ctx, &subject, VoteValue::Downvote, 3).await;
+    assert!(commit.votes.is_empty());
+    assert_eq!(reveal.votes.len(), 1);
+    assert_eq!(reveal.votes[0].value, -1);
+    assert_eq!(reveal.votes[0].weight, Some(1));
+    db.index_transaction(&commit).unwrap();
+    db.index_transaction(&reveal).unwrap();
+    assert_eq!(
+        db.derived_score(&subject).unwrap(),
+        DerivedScore {
+            downvote: 1,
+            downvoters: 1,
+            ..DerivedScore::default()
+        }
+    );
+}
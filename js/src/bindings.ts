@@ -1,6 +1,23 @@
-import { PublicKey, SystemProgram } from "@solana/web3.js";
-import { voteInstruction } from "./raw_instructions";
-import { VoteValue } from "./state";
+import {
+  Connection,
+  Ed25519Program,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import { serialize } from "borsh";
+import {
+  migrateInstruction,
+  relayVoteInstruction,
+  voteInstruction,
+} from "./raw_instructions";
+import {
+  ReputationScoreState,
+  Tag,
+  UserVoteState,
+  VoteValue,
+} from "./state";
 
 /**
  * Mainnet program ID
@@ -18,43 +35,220 @@ export const SNS_REPUTATION_ID_DEVNET = new PublicKey(
 
 interface VotingInstructionParams {
   programId: PublicKey;
+  rentPayer?: PublicKey;
   voter: PublicKey;
   userKey: PublicKey;
   userVotePdaAddress: PublicKey;
   reputationScorePdaAddress: PublicKey;
+  voterProfilePdaAddress: PublicKey;
+  configPdaAddress: PublicKey;
+  voterReputationScorePdaAddress?: PublicKey;
   voteValue: VoteValue;
   voterStakeAddresses: PublicKey[];
 }
 /**
- * Creates voting instruction.
+ * Creates voting instructions. The vote is preceded by the Migrate instructions
+ * of the votee's ReputationScore and the voter's UserVote when they use an
+ * outdated layout, which the program refuses to update.
  *
+ * @param connection - A solana RPC connection
  * @param params - The parameters for the vote function.
  * @param params.programId - The program ID.
- * @param params.voter - Voter, and the actual fee payer of the transaction.
+ * @param params.rentPayer - Pays for the created accounts and is refunded when the vote is undone, defaults to the voter.
+ * @param params.voter - Voter, which can be a PDA signing through CPI.
  * @param params.userKey - The votee account.
  * @param params.userVotePdaAddress - PDA: previous voter's vote state.
- * @param params.reputationScorePdaAddress - PDA: votee reputation score.
+ * @param params.reputationScorePdaAddress - PDA: votee reputation score, or the voter's shard of it. Once the vote is cast, the account recorded in its UserVote is used instead.
+ * @param params.voterProfilePdaAddress - PDA: voter profile.
+ * @param params.configPdaAddress - PDA: program config, holding the cap on new votes per epoch.
+ * @param params.voterReputationScorePdaAddress - PDA: voter reputation score, scales the weight of the vote by the voter's credibility when given.
  * @param params.voteValue - New voter's vote (see VoteValue type).
- * @returns A promise that resolves when the vote is successfully cast.
+ * @returns The instructions to add to the voter's transaction, the vote last.
  */
-export const buildVotingInstruction = ({
-  programId,
-  voter,
-  userKey,
-  userVotePdaAddress,
-  reputationScorePdaAddress,
-  voterStakeAddresses,
-  voteValue,
-}: VotingInstructionParams) => {
-  return new voteInstruction({
+export const buildVotingInstruction = async (
+  connection: Connection,
+  {
+    programId,
+    rentPayer,
+    voter,
+    userKey,
+    userVotePdaAddress,
+    reputationScorePdaAddress,
+    voterProfilePdaAddress,
+    configPdaAddress,
+    voterReputationScorePdaAddress,
+    voterStakeAddresses,
+    voteValue,
+  }: VotingInstructionParams
+): Promise<TransactionInstruction[]> => {
+  // The program only changes a vote in the account it was tallied in
+  const userVoteInfo = await connection.getAccountInfo(userVotePdaAddress);
+  if (userVoteInfo && userVoteInfo.owner.equals(programId)) {
+    const { reputationAccount } = UserVoteState.deserialize(userVoteInfo.data);
+    if (!reputationAccount.equals(PublicKey.default)) {
+      reputationScorePdaAddress = reputationAccount;
+    }
+  }
+  const migrations = await buildMigrateInstructions(connection, {
+    programId,
+    rentPayer: rentPayer ?? voter,
+    stateAccounts: [reputationScorePdaAddress, userVotePdaAddress],
+  });
+  const vote = new voteInstruction({
     userKey: userKey.toBytes(),
     voteValue,
   }).getInstruction(
     programId,
     SystemProgram.programId,
+    rentPayer ?? voter,
     voter,
     reputationScorePdaAddress,
     userVotePdaAddress,
+    voterProfilePdaAddress,
+    configPdaAddress,
+    voterReputationScorePdaAddress,
     voterStakeAddresses
   );
+  return [...migrations, vote];
+};
+
+const RELAYED_VOTE_PREFIX = "sns-reputation relayed vote\n";
+
+interface VoteMessageParams {
+  programId: PublicKey;
+  voter: PublicKey;
+  userKey: PublicKey;
+  voteValue: VoteValue;
+  nonce: bigint;
+  expiry: bigint;
+}
+/**
+ * Serializes the message a voter signs for a relayed vote.
+ *
+ * @param params.nonce - The voter's relay nonce, as stored in its voter profile.
+ * @param params.expiry - Unix timestamp after which the vote can no longer be relayed.
+ * @returns The bytes to sign with the voter's wallet.
+ */
+export const serializeVoteMessage = ({
+  programId,
+  voter,
+  userKey,
+  voteValue,
+  nonce,
+  expiry,
+}: VoteMessageParams): Uint8Array => {
+  const body = serialize(
+    {
+      struct: {
+        programId: { array: { type: "u8", len: 32 } },
+        voter: { array: { type: "u8", len: 32 } },
+        userKey: { array: { type: "u8", len: 32 } },
+        voteValue: "u8",
+        nonce: "u64",
+        expiry: "i64",
+      },
+    },
+    {
+      programId: programId.toBytes(),
+      voter: voter.toBytes(),
+      userKey: userKey.toBytes(),
+      voteValue,
+      nonce,
+      expiry,
+    }
+  );
+  return Buffer.concat([Buffer.from(RELAYED_VOTE_PREFIX), body]);
+};
+
+interface RelayVoteInstructionsParams extends VoteMessageParams {
+  relayer: PublicKey;
+  signature: Uint8Array;
+  userVotePdaAddress: PublicKey;
+  reputationScorePdaAddress: PublicKey;
+  voterProfilePdaAddress: PublicKey;
+  configPdaAddress: PublicKey;
+  voterReputationScorePdaAddress?: PublicKey;
+  voterStakeAddresses: PublicKey[];
+}
+/**
+ * Creates the instructions submitting a vote signed off-chain by the voter: the Ed25519
+ * signature verification followed by the relay instruction. They have to stay in this order.
+ *
+ * @param params.relayer - Submits the vote and pays for the created accounts.
+ * @param params.signature - The voter's signature of `serializeVoteMessage(params)`.
+ * @param params.voterReputationScorePdaAddress - PDA: voter reputation score, scales the weight of the vote by the voter's credibility when given.
+ * @returns The two instructions to add to the relayer's transaction.
+ */
+export const buildRelayVoteInstructions = (
+  params: RelayVoteInstructionsParams
+): TransactionInstruction[] => {
+  const message = serializeVoteMessage(params);
+  const verify = Ed25519Program.createInstructionWithPublicKey({
+    publicKey: params.voter.toBytes(),
+    message,
+    signature: params.signature,
+  });
+  const relay = new relayVoteInstruction({
+    userKey: params.userKey.toBytes(),
+    voteValue: params.voteValue,
+    nonce: params.nonce,
+    expiry: params.expiry,
+  }).getInstruction(
+    params.programId,
+    SystemProgram.programId,
+    SYSVAR_INSTRUCTIONS_PUBKEY,
+    params.relayer,
+    params.voter,
+    params.reputationScorePdaAddress,
+    params.userVotePdaAddress,
+    params.voterProfilePdaAddress,
+    params.configPdaAddress,
+    params.voterReputationScorePdaAddress,
+    params.voterStakeAddresses
+  );
+  return [verify, relay];
+};
+
+/**
+ * Creates the instructions moving the given ReputationScore and UserVote accounts
+ * to the latest layout, which is required before they can be updated. Accounts
+ * that don't exist or already use the latest layout are skipped.
+ *
+ * @param connection - A solana RPC connection
+ * @param params.rentPayer - Tops up the rent of the migrated accounts.
+ * @param params.stateAccounts - The ReputationScore and UserVote PDAs to migrate.
+ * @returns The instructions to prepend to the transaction updating the accounts.
+ */
+export const buildMigrateInstructions = async (
+  connection: Connection,
+  {
+    programId,
+    rentPayer,
+    stateAccounts,
+  }: { programId: PublicKey; rentPayer: PublicKey; stateAccounts: PublicKey[] }
+): Promise<TransactionInstruction[]> => {
+  const infos = await connection.getMultipleAccountsInfo(stateAccounts);
+  const instructions: TransactionInstruction[] = [];
+  infos.forEach((info, i) => {
+    if (!info || !info.owner.equals(programId)) {
+      return;
+    }
+    const tag = info.data.readUInt8(0);
+    const needsMigration =
+      tag === Tag.ReputationScore
+        ? ReputationScoreState.deserialize(info.data).needsMigration()
+        : tag === Tag.UserVote &&
+          UserVoteState.deserialize(info.data).needsMigration();
+    if (needsMigration) {
+      instructions.push(
+        new migrateInstruction().getInstruction(
+          programId,
+          SystemProgram.programId,
+          rentPayer,
+          stateAccounts[i]
+        )
+      );
+    }
+  });
+  return instructions;
 };
@@ -1,4 +1,6 @@
 export * from "./bindings";
 export * from "./raw_instructions";
 export * from "./state";
+export * from "./score";
 export * from "./secondary_bindings";
+export * from "./lookup_table";
@@ -0,0 +1,133 @@
+import {
+  AddressLookupTableAccount,
+  AddressLookupTableProgram,
+  Connection,
+  PublicKey,
+  TransactionInstruction,
+  TransactionMessage,
+  VersionedTransaction,
+} from "@solana/web3.js";
+
+/**
+ * Maximum number of stake accounts the program accepts along a vote
+ */
+export const MAX_STAKE_ACCOUNTS = 32;
+
+/**
+ * Maximum number of addresses added by a single extend instruction, keeping it
+ * within the size of a transaction
+ */
+const MAX_ADDRESSES_PER_EXTEND = 20;
+
+interface StakeLookupTableParams {
+  authority: PublicKey;
+  payer: PublicKey;
+  stakeAccounts: PublicKey[];
+  lookupTable?: PublicKey;
+}
+/**
+ * Creates the instructions creating or extending an address lookup table so that it
+ * holds all the given stake accounts.
+ *
+ * @param connection - A solana RPC connection
+ * @param params.authority - Authority of the lookup table, usually the voter.
+ * @param params.payer - Pays for the lookup table rent.
+ * @param params.stakeAccounts - The voter's stake accounts.
+ * @param params.lookupTable - An existing lookup table to extend, a new one is created when omitted.
+ * @returns The lookup table address and the instructions to send, each in its own transaction.
+ * The lookup table can be used one slot after it was last extended.
+ */
+export const buildStakeLookupTableInstructions = async (
+  connection: Connection,
+  { authority, payer, stakeAccounts, lookupTable }: StakeLookupTableParams
+): Promise<{
+  lookupTable: PublicKey;
+  instructions: TransactionInstruction[];
+}> => {
+  const instructions: TransactionInstruction[] = [];
+  let existing: PublicKey[] = [];
+
+  if (!lookupTable) {
+    const [createIx, address] = AddressLookupTableProgram.createLookupTable({
+      authority,
+      payer,
+      recentSlot: await connection.getSlot("finalized"),
+    });
+    instructions.push(createIx);
+    lookupTable = address;
+  } else {
+    const { value } = await connection.getAddressLookupTable(lookupTable);
+    if (!value) {
+      throw new Error("Lookup table not found");
+    }
+    existing = value.state.addresses;
+  }
+
+  const missing = stakeAccounts.filter(
+    (k) => !existing.some((e) => e.equals(k))
+  );
+  for (let i = 0; i < missing.length; i += MAX_ADDRESSES_PER_EXTEND) {
+    instructions.push(
+      AddressLookupTableProgram.extendLookupTable({
+        lookupTable,
+        authority,
+        payer,
+        addresses: missing.slice(i, i + MAX_ADDRESSES_PER_EXTEND),
+      })
+    );
+  }
+
+  return { lookupTable, instructions };
+};
+
+/**
+ * Compiles instructions into an unsigned v0 transaction resolving accounts through
+ * the given lookup tables.
+ *
+ * @param payerKey - The fee payer.
+ * @param instructions - e.g. the instructions returned by `buildVotingInstruction`.
+ * @param recentBlockhash - A recent blockhash.
+ * @param lookupTables - Lookup tables holding the voter's stake accounts.
+ * @returns The transaction, to be signed by the payer and the voter.
+ */
+export const buildVersionedTransaction = (
+  payerKey: PublicKey,
+  instructions: TransactionInstruction[],
+  recentBlockhash: string,
+  lookupTables: AddressLookupTableAccount[]
+): VersionedTransaction => {
+  const message = new TransactionMessage({
+    payerKey,
+    recentBlockhash,
+    instructions,
+  }).compileToV0Message(lookupTables);
+  return new VersionedTransaction(message);
+};
+
+/**
+ * Fetches lookup tables and compiles instructions into an unsigned v0 transaction.
+ *
+ * @param connection - A solana RPC connection
+ * @param payerKey - The fee payer.
+ * @param instructions - e.g. the instructions returned by `buildVotingInstruction`.
+ * @param lookupTables - Addresses of the lookup tables holding the voter's stake accounts.
+ * @returns The transaction, to be signed by the payer and the voter.
+ */
+export const buildVersionedVoteTransaction = async (
+  connection: Connection,
+  payerKey: PublicKey,
+  instructions: TransactionInstruction[],
+  lookupTables: PublicKey[]
+): Promise<VersionedTransaction> => {
+  const accounts = await Promise.all(
+    lookupTables.map(async (key) => {
+      const { value } = await connection.getAddressLookupTable(key);
+      if (!value) {
+        throw new Error(`Lookup table ${key.toBase58()} not found`);
+      }
+      return value;
+    })
+  );
+  const { blockhash } = await connection.getLatestBlockhash();
+  return buildVersionedTransaction(payerKey, instructions, blockhash, accounts);
+};
@@ -32,9 +32,13 @@ export class voteInstruction {
   getInstruction(
     programId: PublicKey,
     systemProgram: PublicKey,
+    rentPayer: PublicKey,
     voter: PublicKey,
     reputationStateAccount: PublicKey,
     userVoteStateAccount: PublicKey,
+    voterProfileAccount: PublicKey,
+    configAccount: PublicKey,
+    voterReputationAccount: PublicKey | undefined,
     voterStakeAccounts: PublicKey[],
   ): TransactionInstruction {
     const data = Buffer.from(this.serialize());
@@ -44,11 +48,181 @@ export class voteInstruction {
       isSigner: false,
       isWritable: false,
     });
+    keys.push({
+      pubkey: rentPayer,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: voter,
+      isSigner: true,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: reputationStateAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: userVoteStateAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: voterProfileAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: configAccount,
+      isSigner: false,
+      isWritable: false,
+    });
+    if (!!voterReputationAccount) {
+      keys.push({
+        pubkey: voterReputationAccount,
+        isSigner: false,
+        isWritable: false,
+      });
+    }
+    for (let k of voterStakeAccounts) {
+      keys.push({
+        pubkey: k,
+        isSigner: false,
+        isWritable: false,
+      });
+    }
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
+export class commitVoteInstruction {
+  tag: number;
+  userKey: Uint8Array;
+  commitment: Uint8Array;
+  static schema = {
+    struct: {
+      tag: "u8",
+      userKey: { array: { type: "u8", len: 32 } },
+      commitment: { array: { type: "u8", len: 32 } },
+    },
+  };
+  constructor(obj: {
+    userKey: Uint8Array;
+    commitment: Uint8Array;
+  }) {
+    this.tag = 1;
+    this.userKey = obj.userKey;
+    this.commitment = obj.commitment;
+  }
+  serialize(): Uint8Array {
+    return serialize(commitVoteInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    systemProgram: PublicKey,
+    rentPayer: PublicKey,
+    voter: PublicKey,
+    userVoteStateAccount: PublicKey,
+    voterProfileAccount: PublicKey,
+    configAccount: PublicKey,
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: systemProgram,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: rentPayer,
+      isSigner: true,
+      isWritable: true,
+    });
     keys.push({
       pubkey: voter,
       isSigner: true,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: userVoteStateAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: voterProfileAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: configAccount,
+      isSigner: false,
+      isWritable: false,
+    });
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
+export class revealVoteInstruction {
+  tag: number;
+  userKey: Uint8Array;
+  voteValue: number;
+  salt: Uint8Array;
+  static schema = {
+    struct: {
+      tag: "u8",
+      userKey: { array: { type: "u8", len: 32 } },
+      voteValue: "u8",
+      salt: { array: { type: "u8", len: 32 } },
+    },
+  };
+  constructor(obj: {
+    userKey: Uint8Array;
+    voteValue: number;
+    salt: Uint8Array;
+  }) {
+    this.tag = 2;
+    this.userKey = obj.userKey;
+    this.voteValue = obj.voteValue;
+    this.salt = obj.salt;
+  }
+  serialize(): Uint8Array {
+    return serialize(revealVoteInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    systemProgram: PublicKey,
+    rentPayer: PublicKey,
+    voter: PublicKey,
+    reputationStateAccount: PublicKey,
+    userVoteStateAccount: PublicKey,
+    voterProfileAccount: PublicKey,
+    voterReputationAccount: PublicKey | undefined,
+    voterStakeAccounts: PublicKey[],
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: systemProgram,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: rentPayer,
+      isSigner: true,
       isWritable: true,
     });
+    keys.push({
+      pubkey: voter,
+      isSigner: true,
+      isWritable: false,
+    });
     keys.push({
       pubkey: reputationStateAccount,
       isSigner: false,
@@ -59,6 +233,18 @@ export class voteInstruction {
       isSigner: false,
       isWritable: true,
     });
+    keys.push({
+      pubkey: voterProfileAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    if (!!voterReputationAccount) {
+      keys.push({
+        pubkey: voterReputationAccount,
+        isSigner: false,
+        isWritable: false,
+      });
+    }
     for (let k of voterStakeAccounts) {
       keys.push({
         pubkey: k,
@@ -73,3 +259,751 @@ export class voteInstruction {
     });
   }
 }
+export class closeExpiredCommitmentInstruction {
+  tag: number;
+  static schema = {
+    struct: {
+      tag: "u8",
+    },
+  };
+  constructor() {
+    this.tag = 3;
+  }
+  serialize(): Uint8Array {
+    return serialize(closeExpiredCommitmentInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    rentPayer: PublicKey,
+    userVoteStateAccount: PublicKey,
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: rentPayer,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: userVoteStateAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
+export class createDelegationInstruction {
+  tag: number;
+  expiry: bigint;
+  permissions: number;
+  static schema = {
+    struct: {
+      tag: "u8",
+      expiry: "i64",
+      permissions: "u8",
+    },
+  };
+  constructor(obj: {
+    expiry: bigint;
+    permissions: number;
+  }) {
+    this.tag = 4;
+    this.expiry = obj.expiry;
+    this.permissions = obj.permissions;
+  }
+  serialize(): Uint8Array {
+    return serialize(createDelegationInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    systemProgram: PublicKey,
+    staker: PublicKey,
+    delegate: PublicKey,
+    delegationAccount: PublicKey,
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: systemProgram,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: staker,
+      isSigner: true,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: delegate,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: delegationAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
+export class revokeDelegationInstruction {
+  tag: number;
+  static schema = {
+    struct: {
+      tag: "u8",
+    },
+  };
+  constructor() {
+    this.tag = 5;
+  }
+  serialize(): Uint8Array {
+    return serialize(revokeDelegationInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    staker: PublicKey,
+    delegationAccount: PublicKey,
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: staker,
+      isSigner: true,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: delegationAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
+export class delegatedVoteInstruction {
+  tag: number;
+  userKey: Uint8Array;
+  voteValue: number;
+  static schema = {
+    struct: {
+      tag: "u8",
+      userKey: { array: { type: "u8", len: 32 } },
+      voteValue: "u8",
+    },
+  };
+  constructor(obj: {
+    userKey: Uint8Array;
+    voteValue: number;
+  }) {
+    this.tag = 6;
+    this.userKey = obj.userKey;
+    this.voteValue = obj.voteValue;
+  }
+  serialize(): Uint8Array {
+    return serialize(delegatedVoteInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    systemProgram: PublicKey,
+    rentPayer: PublicKey,
+    delegate: PublicKey,
+    staker: PublicKey,
+    delegationAccount: PublicKey,
+    reputationStateAccount: PublicKey,
+    userVoteStateAccount: PublicKey,
+    voterProfileAccount: PublicKey,
+    configAccount: PublicKey,
+    stakerReputationAccount: PublicKey | undefined,
+    stakerStakeAccounts: PublicKey[],
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: systemProgram,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: rentPayer,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: delegate,
+      isSigner: true,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: staker,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: delegationAccount,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: reputationStateAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: userVoteStateAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: voterProfileAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: configAccount,
+      isSigner: false,
+      isWritable: false,
+    });
+    if (!!stakerReputationAccount) {
+      keys.push({
+        pubkey: stakerReputationAccount,
+        isSigner: false,
+        isWritable: false,
+      });
+    }
+    for (let k of stakerStakeAccounts) {
+      keys.push({
+        pubkey: k,
+        isSigner: false,
+        isWritable: false,
+      });
+    }
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
+export class followCuratorInstruction {
+  tag: number;
+  curator: Uint8Array;
+  static schema = {
+    struct: {
+      tag: "u8",
+      curator: { array: { type: "u8", len: 32 } },
+    },
+  };
+  constructor(obj: {
+    curator: Uint8Array;
+  }) {
+    this.tag = 7;
+    this.curator = obj.curator;
+  }
+  serialize(): Uint8Array {
+    return serialize(followCuratorInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    systemProgram: PublicKey,
+    follower: PublicKey,
+    followAccount: PublicKey,
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: systemProgram,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: follower,
+      isSigner: true,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: followAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
+export class unfollowCuratorInstruction {
+  tag: number;
+  static schema = {
+    struct: {
+      tag: "u8",
+    },
+  };
+  constructor() {
+    this.tag = 8;
+  }
+  serialize(): Uint8Array {
+    return serialize(unfollowCuratorInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    follower: PublicKey,
+    followAccount: PublicKey,
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: follower,
+      isSigner: true,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: followAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
+export class propagateCuratorVoteInstruction {
+  tag: number;
+  userKey: Uint8Array;
+  static schema = {
+    struct: {
+      tag: "u8",
+      userKey: { array: { type: "u8", len: 32 } },
+    },
+  };
+  constructor(obj: {
+    userKey: Uint8Array;
+  }) {
+    this.tag = 9;
+    this.userKey = obj.userKey;
+  }
+  serialize(): Uint8Array {
+    return serialize(propagateCuratorVoteInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    systemProgram: PublicKey,
+    rentPayer: PublicKey,
+    follower: PublicKey,
+    followAccount: PublicKey,
+    curatorUserVoteAccount: PublicKey,
+    reputationStateAccount: PublicKey,
+    userVoteStateAccount: PublicKey,
+    voterProfileAccount: PublicKey,
+    configAccount: PublicKey,
+    followerReputationAccount: PublicKey | undefined,
+    followerStakeAccounts: PublicKey[],
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: systemProgram,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: rentPayer,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: follower,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: followAccount,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: curatorUserVoteAccount,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: reputationStateAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: userVoteStateAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: voterProfileAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: configAccount,
+      isSigner: false,
+      isWritable: false,
+    });
+    if (!!followerReputationAccount) {
+      keys.push({
+        pubkey: followerReputationAccount,
+        isSigner: false,
+        isWritable: false,
+      });
+    }
+    for (let k of followerStakeAccounts) {
+      keys.push({
+        pubkey: k,
+        isSigner: false,
+        isWritable: false,
+      });
+    }
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
+export class relayVoteInstruction {
+  tag: number;
+  userKey: Uint8Array;
+  voteValue: number;
+  nonce: bigint;
+  expiry: bigint;
+  static schema = {
+    struct: {
+      tag: "u8",
+      userKey: { array: { type: "u8", len: 32 } },
+      voteValue: "u8",
+      nonce: "u64",
+      expiry: "i64",
+    },
+  };
+  constructor(obj: {
+    userKey: Uint8Array;
+    voteValue: number;
+    nonce: bigint;
+    expiry: bigint;
+  }) {
+    this.tag = 10;
+    this.userKey = obj.userKey;
+    this.voteValue = obj.voteValue;
+    this.nonce = obj.nonce;
+    this.expiry = obj.expiry;
+  }
+  serialize(): Uint8Array {
+    return serialize(relayVoteInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    systemProgram: PublicKey,
+    instructionsSysvar: PublicKey,
+    rentPayer: PublicKey,
+    voter: PublicKey,
+    reputationStateAccount: PublicKey,
+    userVoteStateAccount: PublicKey,
+    voterProfileAccount: PublicKey,
+    configAccount: PublicKey,
+    voterReputationAccount: PublicKey | undefined,
+    voterStakeAccounts: PublicKey[],
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: systemProgram,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: instructionsSysvar,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: rentPayer,
+      isSigner: true,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: voter,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: reputationStateAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: userVoteStateAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: voterProfileAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: configAccount,
+      isSigner: false,
+      isWritable: false,
+    });
+    if (!!voterReputationAccount) {
+      keys.push({
+        pubkey: voterReputationAccount,
+        isSigner: false,
+        isWritable: false,
+      });
+    }
+    for (let k of voterStakeAccounts) {
+      keys.push({
+        pubkey: k,
+        isSigner: false,
+        isWritable: false,
+      });
+    }
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
+export class aggregateInstruction {
+  tag: number;
+  userKey: Uint8Array;
+  static schema = {
+    struct: {
+      tag: "u8",
+      userKey: { array: { type: "u8", len: 32 } },
+    },
+  };
+  constructor(obj: {
+    userKey: Uint8Array;
+  }) {
+    this.tag = 11;
+    this.userKey = obj.userKey;
+  }
+  serialize(): Uint8Array {
+    return serialize(aggregateInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    systemProgram: PublicKey,
+    rentPayer: PublicKey,
+    reputationStateAccount: PublicKey,
+    reputationShardAccounts: PublicKey[],
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: systemProgram,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: rentPayer,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: reputationStateAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    for (let k of reputationShardAccounts) {
+      keys.push({
+        pubkey: k,
+        isSigner: false,
+        isWritable: true,
+      });
+    }
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
+export class migrateInstruction {
+  tag: number;
+  static schema = {
+    struct: {
+      tag: "u8",
+    },
+  };
+  constructor() {
+    this.tag = 12;
+  }
+  serialize(): Uint8Array {
+    return serialize(migrateInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    systemProgram: PublicKey,
+    rentPayer: PublicKey,
+    stateAccount: PublicKey,
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: systemProgram,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: rentPayer,
+      isSigner: true,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: stateAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
+export class snapshotInstruction {
+  tag: number;
+  userKey: Uint8Array;
+  static schema = {
+    struct: {
+      tag: "u8",
+      userKey: { array: { type: "u8", len: 32 } },
+    },
+  };
+  constructor(obj: {
+    userKey: Uint8Array;
+  }) {
+    this.tag = 13;
+    this.userKey = obj.userKey;
+  }
+  serialize(): Uint8Array {
+    return serialize(snapshotInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    systemProgram: PublicKey,
+    rentPayer: PublicKey,
+    reputationStateAccount: PublicKey,
+    scoreHistoryAccount: PublicKey,
+    reputationShardAccounts: PublicKey[],
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: systemProgram,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: rentPayer,
+      isSigner: false,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: reputationStateAccount,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: scoreHistoryAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    for (let k of reputationShardAccounts) {
+      keys.push({
+        pubkey: k,
+        isSigner: false,
+        isWritable: false,
+      });
+    }
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
+export class setConfigInstruction {
+  tag: number;
+  maxNewVotesPerEpoch: bigint;
+  static schema = {
+    struct: {
+      tag: "u8",
+      maxNewVotesPerEpoch: "u64",
+    },
+  };
+  constructor(obj: {
+    maxNewVotesPerEpoch: bigint;
+  }) {
+    this.tag = 14;
+    this.maxNewVotesPerEpoch = obj.maxNewVotesPerEpoch;
+  }
+  serialize(): Uint8Array {
+    return serialize(setConfigInstruction.schema, this);
+  }
+  getInstruction(
+    programId: PublicKey,
+    systemProgram: PublicKey,
+    feePayer: PublicKey,
+    authority: PublicKey,
+    programData: PublicKey,
+    configAccount: PublicKey,
+  ): TransactionInstruction {
+    const data = Buffer.from(this.serialize());
+    let keys: AccountKey[] = [];
+    keys.push({
+      pubkey: systemProgram,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: feePayer,
+      isSigner: true,
+      isWritable: true,
+    });
+    keys.push({
+      pubkey: authority,
+      isSigner: true,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: programData,
+      isSigner: false,
+      isWritable: false,
+    });
+    keys.push({
+      pubkey: configAccount,
+      isSigner: false,
+      isWritable: true,
+    });
+    return new TransactionInstruction({
+      keys,
+      programId,
+      data,
+    });
+  }
+}
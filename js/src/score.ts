@@ -0,0 +1,133 @@
+/**
+ * Normalized scores of a `ReputationScore`, computed in fixed point exactly
+ * like the program does so that on-chain and off-chain results always agree
+ */
+
+/**
+ * Scale of the normalized scores, which range from 0 to 100.00
+ */
+export const SCORE_SCALE = BigInt(10_000);
+
+/**
+ * Scale of the `z` parameter of `wilsonLowerBound`
+ */
+export const Z_SCALE = BigInt(10_000);
+
+/**
+ * Quantile of the standard normal distribution for a 95% confidence level,
+ * scaled by `Z_SCALE`
+ */
+export const WILSON_Z_95 = BigInt(19_600);
+
+/**
+ * Largest `z` accepted by `wilsonLowerBound`, larger values are capped
+ */
+export const WILSON_MAX_Z = BigInt(10) * Z_SCALE;
+
+/**
+ * Scale of the credibility multipliers, a multiplier of `CREDIBILITY_SCALE`
+ * leaving the weight of a vote unchanged
+ */
+export const CREDIBILITY_SCALE = BigInt(10_000);
+
+/**
+ * Largest credibility multiplier, reached by voters whose Wilson score is 100
+ */
+export const MAX_CREDIBILITY_MULTIPLIER =
+  CREDIBILITY_SCALE + SCORE_SCALE / BigInt(2);
+
+/**
+ * Prior of `bayesianAverage`: the score of a user without any vote, between 0
+ * and `SCORE_SCALE`, and the amount of votes it is worth in lamports
+ */
+export interface BayesianPrior {
+  mean: bigint;
+  weight: bigint;
+}
+
+const ZERO = BigInt(0);
+const ONE = BigInt(1);
+const TWO = BigInt(2);
+const FOUR = BigInt(4);
+
+/**
+ * Integer square root, rounded down
+ */
+export const isqrt = (n: bigint): bigint => {
+  if (n < TWO) {
+    return n;
+  }
+  const bits = BigInt(n.toString(2).length);
+  let x = ONE << ((bits + ONE) / TWO);
+  for (;;) {
+    const y = (x + n / x) / TWO;
+    if (y >= x) {
+      return x;
+    }
+    x = y;
+  }
+};
+
+/**
+ * Lower bound of the Wilson score interval of the share of upvoters, between
+ * 0 and `SCORE_SCALE`. Few votes give a low score however positive they are.
+ * `z` is the quantile of the confidence level scaled by `Z_SCALE`
+ */
+export const wilsonLowerBound = (
+  upvoters: bigint,
+  downvoters: bigint,
+  z: bigint = WILSON_Z_95
+): bigint => {
+  const n = upvoters + downvoters;
+  if (n === ZERO) {
+    return ZERO;
+  }
+  const s2 = Z_SCALE * Z_SCALE;
+  if (z > WILSON_MAX_Z) {
+    z = WILSON_MAX_Z;
+  }
+  const z2 = z * z;
+  const product = upvoters * downvoters;
+  const variance = (product / n) * s2 + ((product % n) * s2) / n;
+  const spread = isqrt(variance + z2 / FOUR);
+  let numerator = upvoters * s2 + z2 / TWO - z * spread;
+  if (numerator < ZERO) {
+    numerator = ZERO;
+  }
+  return (numerator * SCORE_SCALE) / (n * s2 + z2);
+};
+
+/**
+ * Share of upvotes in the tallies, between 0 and `SCORE_SCALE`, pulled toward
+ * the mean of the prior by as many lamports of votes as its weight
+ */
+export const bayesianAverage = (
+  upvote: bigint,
+  downvote: bigint,
+  prior: BayesianPrior
+): bigint => {
+  const mean = prior.mean < SCORE_SCALE ? prior.mean : SCORE_SCALE;
+  const denominator = prior.weight + upvote + downvote;
+  if (denominator === ZERO) {
+    return mean;
+  }
+  return (mean * prior.weight + SCORE_SCALE * upvote) / denominator;
+};
+
+/**
+ * Multiplier applied by the program to the weight of the votes of a user with
+ * `upvoters` and `downvoters`, scaled by `CREDIBILITY_SCALE`. It grows with the
+ * Wilson score above 50 and is capped at `MAX_CREDIBILITY_MULTIPLIER`
+ */
+export const credibilityMultiplier = (
+  upvoters: bigint,
+  downvoters: bigint
+): bigint => {
+  const half = SCORE_SCALE / TWO;
+  const score = wilsonLowerBound(upvoters, downvoters, WILSON_Z_95);
+  const excess = score > half ? score - half : ZERO;
+  return (
+    CREDIBILITY_SCALE +
+    (excess * (MAX_CREDIBILITY_MULTIPLIER - CREDIBILITY_SCALE)) / half
+  );
+};
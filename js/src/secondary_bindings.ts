@@ -1,6 +1,15 @@
 import { Connection, PublicKey, StakeProgram } from "@solana/web3.js";
 import { SNS_REPUTATION_ID } from "./bindings";
-import { ReputationScoreState, UserVoteState } from "./state";
+import {
+  ConfigState,
+  NUM_SHARDS,
+  ReputationScoreState,
+  ReputationShardState,
+  ScoreHistoryState,
+  ScoreSnapshot,
+  UserVoteState,
+  VoterProfileState,
+} from "./state";
 import base58 from "bs58";
 import { Buffer } from "buffer";
 
@@ -11,6 +20,51 @@ export const getReputationScoreKey = (
   return ReputationScoreState.findKey(programId, user);
 };
 
+export const getReputationShardKey = (
+  user: PublicKey,
+  shard: number,
+  programId = SNS_REPUTATION_ID
+) => {
+  return ReputationShardState.findKey(programId, user, shard);
+};
+
+/**
+ * Retrieve user upvotes and downvotes, including the votes held by shards that
+ * have not been aggregated yet.
+ * @param connection - A solana RPC connection
+ * @param votee - User voted over by other users
+ * @returns upvote and downvote totals
+ */
+export const getReputationTallies = async (
+  connection: Connection,
+  votee: PublicKey,
+  programId = SNS_REPUTATION_ID
+): Promise<{ upvote: bigint; downvote: bigint }> => {
+  const keys = [(await getReputationScoreKey(votee, programId))[0]];
+  for (let shard = 0; shard < NUM_SHARDS; shard++) {
+    keys.push((await getReputationShardKey(votee, shard, programId))[0]);
+  }
+  const [score, ...shards] = await connection.getMultipleAccountsInfo(keys);
+
+  let upvote = BigInt(0);
+  let downvote = BigInt(0);
+
+  if (score) {
+    const result = ReputationScoreState.deserialize(score.data);
+    upvote += result.upvote;
+    downvote += result.downvote;
+  }
+  for (const shard of shards) {
+    if (shard) {
+      const result = ReputationShardState.deserialize(shard.data);
+      upvote += result.upvote;
+      downvote += result.downvote;
+    }
+  }
+
+  return { upvote, downvote };
+};
+
 /**
  * Retrieve user reputation score, based on number of upvotes and downvotes.
  * @param connection - A solana RPC connection
@@ -25,23 +79,116 @@ export const getReputationScore = async (
   votee: PublicKey,
   programId = SNS_REPUTATION_ID
 ): Promise<bigint> => {
-  const [key] = await getReputationScoreKey(votee, programId);
+  const { upvote, downvote } = await getReputationTallies(
+    connection,
+    votee,
+    programId
+  );
 
-  let upvote = BigInt(0);
-  let downvote = BigInt(0);
+  return upvote - downvote;
+};
 
-  try {
-    const result = await ReputationScoreState.retrieve(connection, key);
+export const getScoreHistoryKey = (
+  user: PublicKey,
+  programId = SNS_REPUTATION_ID
+) => {
+  return ScoreHistoryState.findKey(programId, user);
+};
 
-    upvote = result.upvote;
-    downvote = result.downvote;
-  } catch (err: any) {
+/**
+ * Retrieve the tallies of a user recorded by the Snapshot crank over the last
+ * epochs.
+ *
+ * @param connection - A solana RPC connection
+ * @param votee - User voted over by other users
+ * @returns the snapshots, oldest first, empty if none was ever recorded
+ */
+export const getScoreHistory = async (
+  connection: Connection,
+  votee: PublicKey,
+  programId = SNS_REPUTATION_ID
+): Promise<ScoreSnapshot[]> => {
+  const [key] = await getScoreHistoryKey(votee, programId);
+  const [accountInfo, epochInfo] = await Promise.all([
+    connection.getAccountInfo(key),
+    connection.getEpochInfo(),
+  ]);
+  if (!accountInfo) {
+    return [];
+  }
+  return ScoreHistoryState.deserialize(accountInfo.data).series(
+    BigInt(epochInfo.epoch)
+  );
+};
+
+/**
+ * Average of the score (upvotes minus downvotes) over a history, each snapshot
+ * weighted by the time it stayed current. A user with a long bad history keeps
+ * a low average even after a burst of recent upvotes, unlike one that was only
+ * targeted recently.
+ *
+ * @param series - Snapshots returned by `getScoreHistory`
+ * @param now - Unix timestamp closing the last snapshot, defaults to the current time
+ * @returns the average score, or null for an empty history
+ */
+export const getTimeWeightedScore = (
+  series: ScoreSnapshot[],
+  now = BigInt(Math.floor(Date.now() / 1000))
+): bigint | null => {
+  if (series.length === 0) {
+    return null;
+  }
+  let weightedSum = BigInt(0);
+  for (let i = 0; i < series.length; i++) {
+    const end = i + 1 < series.length ? series[i + 1].timestamp : now;
+    const duration = end - series[i].timestamp;
+    weightedSum += (series[i].upvote - series[i].downvote) * duration;
+  }
+  const totalDuration = now - series[0].timestamp;
+  if (totalDuration <= BigInt(0)) {
+    const last = series[series.length - 1];
+    return last.upvote - last.downvote;
+  }
+  return weightedSum / totalDuration;
+};
+
+export const getConfigKey = (programId = SNS_REPUTATION_ID) => {
+  return ConfigState.findKey(programId);
+};
+
+export const getVoterProfileKey = (
+  voter: PublicKey,
+  programId = SNS_REPUTATION_ID
+) => {
+  return VoterProfileState.findKey(programId, voter);
+};
+
+/**
+ * Retrieve the activity profile of a voter.
+ *
+ * @param connection - A solana RPC connection
+ * @param voter - Voter whose profile is requested
+ * @returns the voter profile, or null if the voter never voted
+ * @example
+ *
+ * const profile = getVoterProfile(connection, voter.publicKey);
+ */
+export const getVoterProfile = async (
+  connection: Connection,
+  voter: PublicKey,
+  programId = SNS_REPUTATION_ID
+): Promise<VoterProfileState | null> => {
+  const [key] = await getVoterProfileKey(voter, programId);
+
+  try {
+    return await VoterProfileState.retrieve(connection, key);
+  } catch (err) {
     if (!(err instanceof Error)) {
       throw err;
     }
   }
 
-  return upvote - downvote;
+  return null;
 };
 
 export const getUserVoteAddress = (
@@ -80,6 +227,37 @@ export const getUserVote = async (
   return null;
 };
 
+/**
+ * Retrieve the UserVote accounts storing `key` at `fieldOffset`, relative to the
+ * end of the header. Accounts using the latest layout store a version byte
+ * after the tag, which the accounts created before versioning don't have.
+ */
+const getUserVotesByKey = async (
+  connection: Connection,
+  programId: PublicKey,
+  key: PublicKey,
+  fieldOffset: number
+): Promise<UserVoteState[]> => {
+  const results = await Promise.all(
+    [8, 8 + 1].map((headerLen) =>
+      connection.getProgramAccounts(programId, {
+        filters: [
+          {
+            memcmp: {
+              offset: headerLen + fieldOffset,
+              bytes: key.toBase58(),
+            },
+          },
+        ],
+      })
+    )
+  );
+
+  return results
+    .flat()
+    .map((item) => UserVoteState.deserialize(item.account.data));
+};
+
 /**
  * Returns all voters that voted over asked votee
  *
@@ -95,20 +273,8 @@ export const getAllVotersForUser = async (
   programId = SNS_REPUTATION_ID
 ): Promise<UserVoteState[]> => {
   try {
-    const filters = [
-      {
-        memcmp: {
-          offset: 8 + 8, // tag + voteValue
-          bytes: votee.toBase58(),
-        },
-      },
-    ];
-
-    const result = await connection.getProgramAccounts(programId, {
-      filters,
-    });
-
-    return result.map((item) => UserVoteState.deserialize(item.account.data));
+    // voteValue
+    return await getUserVotesByKey(connection, programId, votee, 8);
   } catch (err) {
     console.error(err);
     return [];
@@ -121,21 +287,8 @@ export const getAllVoteesForVoter = async (
   programId = SNS_REPUTATION_ID
 ): Promise<UserVoteState[]> => {
   try {
-    const filters = [
-      {
-        memcmp: {
-          // tag + voteValue + votee pubkey
-          offset: 8 + 8 + 32,
-          bytes: voter.toBase58(),
-        },
-      },
-    ];
-
-    const result = await connection.getProgramAccounts(programId, {
-      filters,
-    });
-
-    return result.map((item) => UserVoteState.deserialize(item.account.data));
+    // voteValue + votee pubkey
+    return await getUserVotesByKey(connection, programId, voter, 8 + 32);
   } catch (err) {
     console.error(err);
     return [];
@@ -1,37 +1,129 @@
 import { deserialize } from "borsh";
 import { Connection, PublicKey } from "@solana/web3.js";
 import { Buffer } from "buffer";
+import {
+  BayesianPrior,
+  CREDIBILITY_SCALE,
+  WILSON_Z_95,
+  bayesianAverage,
+  credibilityMultiplier,
+  wilsonLowerBound,
+} from "./score";
 
 export enum Tag {
   Uninitialized = 0,
   ReputationScore = 1,
   UserVote = 2,
+  VoterProfile = 3,
+  Delegation = 4,
+  Follow = 5,
+  ReputationShard = 6,
+  ScoreHistory = 7,
+  Config = 8,
 }
 
 export class ReputationScoreState {
   tag: Tag;
+  version: number;
   nonce: number;
   upvote: bigint;
   downvote: bigint;
+  upvoters: bigint;
+  downvoters: bigint;
+
+  /**
+   * Version of the layout written by the program, accounts using a previous
+   * version have to be migrated before being updated
+   */
+  static VERSION = 2;
+  /**
+   * Size of the accounts created before the layout was versioned
+   */
+  static UNVERSIONED_LEN = 8 + 1 + 8 + 8;
 
   static schema = {
+    struct: {
+      tag: "u64",
+      version: "u8",
+      nonce: "u8",
+      upvote: "u64",
+      downvote: "u64",
+      upvoters: "u64",
+      downvoters: "u64",
+    },
+  };
+
+  /**
+   * Layout of version 1, which didn't count the voters
+   */
+  static v1Schema = {
+    struct: {
+      tag: "u64",
+      version: "u8",
+      nonce: "u8",
+      upvote: "u64",
+      downvote: "u64",
+    },
+  };
+
+  static unversionedSchema = {
     struct: { tag: "u64", nonce: "u8", upvote: "u64", downvote: "u64" },
   };
 
   constructor(obj: {
     tag: Tag;
+    version: number;
     nonce: number;
     upvote: bigint;
     downvote: bigint;
+    upvoters?: bigint;
+    downvoters?: bigint;
   }) {
     this.tag = obj.tag;
+    this.version = obj.version;
     this.nonce = obj.nonce;
     this.upvote = obj.upvote;
     this.downvote = obj.downvote;
+    this.upvoters = obj.upvoters ?? BigInt(0);
+    this.downvoters = obj.downvoters ?? BigInt(0);
+  }
+
+  /**
+   * Lower bound of the Wilson score interval of the share of upvoters, see
+   * `wilsonLowerBound`
+   */
+  wilsonScore(z: bigint = WILSON_Z_95): bigint {
+    return wilsonLowerBound(this.upvoters, this.downvoters, z);
+  }
+
+  /**
+   * Share of upvotes in the tallies pulled toward the prior, see
+   * `bayesianAverage`
+   */
+  bayesianScore(prior: BayesianPrior): bigint {
+    return bayesianAverage(this.upvote, this.downvote, prior);
+  }
+
+  /**
+   * Multiplier applied to the weight of the votes of this user when its
+   * ReputationScore is passed along them, see `credibilityMultiplier`
+   */
+  credibilityMultiplier(): bigint {
+    return credibilityMultiplier(this.upvoters, this.downvoters);
+  }
+
+  needsMigration(): boolean {
+    return this.version !== ReputationScoreState.VERSION;
   }
 
   static deserialize(data: Buffer): ReputationScoreState {
-    return new ReputationScoreState(deserialize(this.schema, data) as any);
+    if (data.length === this.UNVERSIONED_LEN) {
+      const obj = deserialize(this.unversionedSchema, data) as any;
+      return new ReputationScoreState({ ...obj, version: 0 });
+    }
+    // Votes cast before the voters were counted are left out of the counts
+    const schema = data[8] === 1 ? this.v1Schema : this.schema;
+    return new ReputationScoreState(deserialize(schema, data) as any);
   }
 
   static async retrieve(connection: Connection, key: PublicKey) {
@@ -49,47 +141,387 @@ export class ReputationScoreState {
   }
 }
 
+/**
+ * Number of shards a reputation score can be split into
+ */
+export const NUM_SHARDS = 8;
+
+export class ReputationShardState {
+  tag: Tag;
+  nonce: number;
+  userKey: PublicKey;
+  shard: number;
+  upvote: bigint;
+  downvote: bigint;
+  upvoters: bigint;
+  downvoters: bigint;
+
+  static schema = {
+    struct: {
+      tag: "u64",
+      nonce: "u8",
+      userKey: { array: { type: "u8", len: 32 } },
+      shard: "u8",
+      upvote: "i64",
+      downvote: "i64",
+      upvoters: "i64",
+      downvoters: "i64",
+    },
+  };
+
+  constructor(obj: {
+    tag: bigint;
+    nonce: number;
+    userKey: Uint8Array;
+    shard: number;
+    upvote: bigint;
+    downvote: bigint;
+    upvoters: bigint;
+    downvoters: bigint;
+  }) {
+    this.tag = Number(obj.tag) as Tag;
+    this.nonce = obj.nonce;
+    this.userKey = new PublicKey(obj.userKey);
+    this.shard = obj.shard;
+    this.upvote = obj.upvote;
+    this.downvote = obj.downvote;
+    this.upvoters = obj.upvoters;
+    this.downvoters = obj.downvoters;
+  }
+
+  static deserialize(data: Buffer): ReputationShardState {
+    return new ReputationShardState(deserialize(this.schema, data) as any);
+  }
+
+  /**
+   * Shard in which the votes of `voter` land
+   */
+  static shardIndex(voter: PublicKey) {
+    return voter.toBytes()[0] % NUM_SHARDS;
+  }
+
+  static async findKey(
+    programId: PublicKey,
+    userAddress: PublicKey,
+    shard: number
+  ) {
+    return await PublicKey.findProgramAddress(
+      [
+        Buffer.from("reputation_shard"),
+        userAddress.toBytes(),
+        Buffer.from([shard]),
+      ],
+      programId
+    );
+  }
+}
+
 export enum VoteValue {
   NoVote = 0,
   Downvote = 1,
   Upvote = 2,
 }
+/**
+ * Number of epochs kept in a score history
+ */
+export const HISTORY_LEN = 64;
+
+export interface ScoreSnapshot {
+  epoch: bigint;
+  /**
+   * Unix timestamp of the snapshot
+   */
+  timestamp: bigint;
+  upvote: bigint;
+  downvote: bigint;
+}
+
+export class ScoreHistoryState {
+  tag: Tag;
+  nonce: number;
+  userKey: PublicKey;
+  snapshots: ScoreSnapshot[];
+
+  static schema = {
+    struct: {
+      tag: "u64",
+      nonce: "u8",
+      userKey: { array: { type: "u8", len: 32 } },
+      snapshots: {
+        array: {
+          type: {
+            struct: {
+              epoch: "u64",
+              timestamp: "i64",
+              upvote: "u64",
+              downvote: "u64",
+            },
+          },
+          len: HISTORY_LEN,
+        },
+      },
+    },
+  };
+
+  constructor(obj: {
+    tag: bigint;
+    nonce: number;
+    userKey: Uint8Array;
+    snapshots: ScoreSnapshot[];
+  }) {
+    this.tag = Number(obj.tag) as Tag;
+    this.nonce = obj.nonce;
+    this.userKey = new PublicKey(obj.userKey);
+    this.snapshots = obj.snapshots;
+  }
+
+  static deserialize(data: Buffer): ScoreHistoryState {
+    return new ScoreHistoryState(deserialize(this.schema, data) as any);
+  }
+
+  /**
+   * Returns the snapshots of the last `HISTORY_LEN` epochs up to
+   * `currentEpoch`, oldest first
+   */
+  series(currentEpoch: bigint): ScoreSnapshot[] {
+    return this.snapshots
+      .filter(
+        (s) =>
+          s.timestamp !== BigInt(0) &&
+          s.epoch <= currentEpoch &&
+          currentEpoch - s.epoch < BigInt(HISTORY_LEN)
+      )
+      .sort((a, b) => (a.epoch < b.epoch ? -1 : 1));
+  }
+
+  static async findKey(programId: PublicKey, userAddress: PublicKey) {
+    return await PublicKey.findProgramAddress(
+      [Buffer.from("score_history"), userAddress.toBytes()],
+      programId
+    );
+  }
+}
+
 export interface UserVote {
   tag: Tag;
   value: bigint;
   votee: PublicKey;
   voter: PublicKey;
+  commitment: Uint8Array;
+  commitSlot: bigint;
+  followedCurator: PublicKey;
+  rentPayer: PublicKey;
+  nonce: number;
+  credibilityMultiplier: bigint;
+  voteSlot: bigint;
+  reputationAccount: PublicKey;
 }
 
 export class UserVoteState implements UserVote {
   tag: Tag;
+  version: number;
   value: bigint;
   votee: PublicKey;
   voter: PublicKey;
+  commitment: Uint8Array;
+  commitSlot: bigint;
+  followedCurator: PublicKey;
+  rentPayer: PublicKey;
+  nonce: number;
+  /**
+   * Credibility multiplier of the voter applied to the weight of the vote,
+   * scaled by `CREDIBILITY_SCALE`
+   */
+  credibilityMultiplier: bigint;
+  /**
+   * Slot at which the value of the vote was last set, zero for the votes set
+   * before it was recorded
+   */
+  voteSlot: bigint;
+  /**
+   * ReputationScore or ReputationShard in which the value of the vote is
+   * tallied, the default key for pending commitments and for the votes
+   * tallied before it was recorded. Changes to the vote have to go through it
+   */
+  reputationAccount: PublicKey;
+
+  /**
+   * Version of the layout written by the program, accounts using a previous
+   * version have to be migrated before being updated
+   */
+  static VERSION = 4;
+  /**
+   * Size of the accounts created before the layout was versioned, which only
+   * stored the value, votee and voter after the tag
+   */
+  static UNVERSIONED_LEN = 8 + 8 + 32 + 32;
+
+  static unversionedSchema = {
+    struct: {
+      tag: "u64",
+      value: "i64",
+      votee: { array: { type: "u8", len: 32 } },
+      voter: { array: { type: "u8", len: 32 } },
+    },
+  };
+
+  /**
+   * The first versioned layout, which lacks the credibility multiplier
+   */
+  static v1Schema = {
+    struct: {
+      tag: "u64",
+      version: "u8",
+      value: "i64",
+      votee: { array: { type: "u8", len: 32 } },
+      voter: { array: { type: "u8", len: 32 } },
+      commitment: { array: { type: "u8", len: 32 } },
+      commitSlot: "u64",
+      followedCurator: { array: { type: "u8", len: 32 } },
+      rentPayer: { array: { type: "u8", len: 32 } },
+      nonce: "u8",
+    },
+  };
+
+  /**
+   * The second versioned layout, which lacks the slot of the vote
+   */
+  static v2Schema = {
+    struct: {
+      tag: "u64",
+      version: "u8",
+      value: "i64",
+      votee: { array: { type: "u8", len: 32 } },
+      voter: { array: { type: "u8", len: 32 } },
+      commitment: { array: { type: "u8", len: 32 } },
+      commitSlot: "u64",
+      followedCurator: { array: { type: "u8", len: 32 } },
+      rentPayer: { array: { type: "u8", len: 32 } },
+      nonce: "u8",
+      credibilityMultiplier: "u64",
+    },
+  };
+
+  /**
+   * The third versioned layout, which lacks the reputation account
+   */
+  static v3Schema = {
+    struct: {
+      tag: "u64",
+      version: "u8",
+      value: "i64",
+      votee: { array: { type: "u8", len: 32 } },
+      voter: { array: { type: "u8", len: 32 } },
+      commitment: { array: { type: "u8", len: 32 } },
+      commitSlot: "u64",
+      followedCurator: { array: { type: "u8", len: 32 } },
+      rentPayer: { array: { type: "u8", len: 32 } },
+      nonce: "u8",
+      credibilityMultiplier: "u64",
+      voteSlot: "u64",
+    },
+  };
 
   static schema = {
     struct: {
       tag: "u64",
+      version: "u8",
       value: "i64",
       votee: { array: { type: "u8", len: 32 } },
       voter: { array: { type: "u8", len: 32 } },
+      commitment: { array: { type: "u8", len: 32 } },
+      commitSlot: "u64",
+      followedCurator: { array: { type: "u8", len: 32 } },
+      rentPayer: { array: { type: "u8", len: 32 } },
+      nonce: "u8",
+      credibilityMultiplier: "u64",
+      voteSlot: "u64",
+      reputationAccount: { array: { type: "u8", len: 32 } },
     },
   };
 
   constructor(obj: {
     tag: bigint;
+    version: number;
     value: bigint;
     votee: Uint8Array;
     voter: Uint8Array;
+    commitment: Uint8Array;
+    commitSlot: bigint;
+    followedCurator: Uint8Array;
+    rentPayer: Uint8Array;
+    nonce: number;
+    credibilityMultiplier: bigint;
+    voteSlot: bigint;
+    reputationAccount: Uint8Array;
   }) {
     this.tag = Number(obj.tag) as Tag;
+    this.version = obj.version;
     this.value = obj.value;
     this.votee = new PublicKey(obj.votee);
     this.voter = new PublicKey(obj.voter);
+    this.commitment = new Uint8Array(obj.commitment);
+    this.commitSlot = obj.commitSlot;
+    this.followedCurator = new PublicKey(obj.followedCurator);
+    this.rentPayer = new PublicKey(obj.rentPayer);
+    this.nonce = obj.nonce;
+    this.credibilityMultiplier = obj.credibilityMultiplier;
+    this.voteSlot = obj.voteSlot;
+    this.reputationAccount = new PublicKey(obj.reputationAccount);
+  }
+
+  isPendingCommitment(): boolean {
+    return this.commitment.some((b) => b !== 0);
+  }
+
+  needsMigration(): boolean {
+    return this.version !== UserVoteState.VERSION;
   }
 
   static deserialize(data: Buffer): UserVoteState {
-    return new UserVoteState(deserialize(this.schema, data) as any);
+    if (data.length === this.UNVERSIONED_LEN) {
+      // Commit-reveal didn't exist yet, the vote was never pending
+      const obj = deserialize(this.unversionedSchema, data) as any;
+      return new UserVoteState({
+        ...obj,
+        version: 0,
+        commitment: new Uint8Array(32),
+        commitSlot: BigInt(0),
+        followedCurator: new Uint8Array(32),
+        rentPayer: new Uint8Array(32),
+        nonce: 0,
+        credibilityMultiplier: CREDIBILITY_SCALE,
+        voteSlot: BigInt(0),
+        reputationAccount: new Uint8Array(32),
+      });
+    }
+    if (data[8] === this.VERSION) {
+      return new UserVoteState(deserialize(this.schema, data) as any);
+    }
+    if (data[8] === 3) {
+      return new UserVoteState({
+        ...(deserialize(this.v3Schema, data) as any),
+        reputationAccount: new Uint8Array(32),
+      });
+    }
+    if (data[8] === 2) {
+      return new UserVoteState({
+        ...(deserialize(this.v2Schema, data) as any),
+        voteSlot: BigInt(0),
+        reputationAccount: new Uint8Array(32),
+      });
+    }
+    const vote = new UserVoteState({
+      ...(deserialize(this.v1Schema, data) as any),
+      credibilityMultiplier: BigInt(0),
+      voteSlot: BigInt(0),
+      reputationAccount: new Uint8Array(32),
+    });
+    // Votes cast before the multiplier existed were not weighted by it
+    if (!vote.isPendingCommitment()) {
+      vote.credibilityMultiplier = CREDIBILITY_SCALE;
+    }
+    return vote;
   }
 
   static async retrieve(connection: Connection, key: PublicKey) {
@@ -109,3 +541,125 @@ export class UserVoteState implements UserVote {
     );
   }
 }
+
+export class VoterProfileState {
+  tag: Tag;
+  nonce: number;
+  voter: PublicKey;
+  activeVotes: bigint;
+  upvotesCast: bigint;
+  downvotesCast: bigint;
+  firstVoteSlot: bigint;
+  lastVoteSlot: bigint;
+  rateLimitEpoch: bigint;
+  rateLimitNewVotes: bigint;
+  relayNonce: bigint;
+
+  static schema = {
+    struct: {
+      tag: "u64",
+      nonce: "u8",
+      voter: { array: { type: "u8", len: 32 } },
+      activeVotes: "u64",
+      upvotesCast: "u64",
+      downvotesCast: "u64",
+      firstVoteSlot: "u64",
+      lastVoteSlot: "u64",
+      rateLimitEpoch: "u64",
+      rateLimitNewVotes: "u64",
+      relayNonce: "u64",
+    },
+  };
+
+  constructor(obj: {
+    tag: bigint;
+    nonce: number;
+    voter: Uint8Array;
+    activeVotes: bigint;
+    upvotesCast: bigint;
+    downvotesCast: bigint;
+    firstVoteSlot: bigint;
+    lastVoteSlot: bigint;
+    rateLimitEpoch: bigint;
+    rateLimitNewVotes: bigint;
+    relayNonce: bigint;
+  }) {
+    this.tag = Number(obj.tag) as Tag;
+    this.nonce = obj.nonce;
+    this.voter = new PublicKey(obj.voter);
+    this.activeVotes = obj.activeVotes;
+    this.upvotesCast = obj.upvotesCast;
+    this.downvotesCast = obj.downvotesCast;
+    this.firstVoteSlot = obj.firstVoteSlot;
+    this.lastVoteSlot = obj.lastVoteSlot;
+    this.rateLimitEpoch = obj.rateLimitEpoch;
+    this.rateLimitNewVotes = obj.rateLimitNewVotes;
+    this.relayNonce = obj.relayNonce;
+  }
+
+  static deserialize(data: Buffer): VoterProfileState {
+    return new VoterProfileState(deserialize(this.schema, data) as any);
+  }
+
+  static async retrieve(connection: Connection, key: PublicKey) {
+    const accountInfo = await connection.getAccountInfo(key);
+    if (!accountInfo || !accountInfo.data) {
+      throw new Error("State account not found");
+    }
+    return this.deserialize(accountInfo.data);
+  }
+  static async findKey(programId: PublicKey, voter: PublicKey) {
+    return await PublicKey.findProgramAddress(
+      [Buffer.from("voter_profile"), voter.toBytes()],
+      programId
+    );
+  }
+}
+
+export class ConfigState {
+  tag: Tag;
+  nonce: number;
+  maxNewVotesPerEpoch: bigint;
+
+  /**
+   * Number of new votes a voter can cast during a single epoch until the
+   * Config account is created
+   */
+  static DEFAULT_MAX_NEW_VOTES_PER_EPOCH = BigInt(100);
+
+  static schema = {
+    struct: {
+      tag: "u64",
+      nonce: "u8",
+      maxNewVotesPerEpoch: "u64",
+    },
+  };
+
+  constructor(obj: {
+    tag: bigint;
+    nonce: number;
+    maxNewVotesPerEpoch: bigint;
+  }) {
+    this.tag = Number(obj.tag) as Tag;
+    this.nonce = obj.nonce;
+    this.maxNewVotesPerEpoch = obj.maxNewVotesPerEpoch;
+  }
+
+  static deserialize(data: Buffer): ConfigState {
+    return new ConfigState(deserialize(this.schema, data) as any);
+  }
+
+  static async retrieve(connection: Connection, key: PublicKey) {
+    const accountInfo = await connection.getAccountInfo(key);
+    if (!accountInfo || !accountInfo.data) {
+      throw new Error("State account not found");
+    }
+    return this.deserialize(accountInfo.data);
+  }
+  static async findKey(programId: PublicKey) {
+    return await PublicKey.findProgramAddress(
+      [Buffer.from("config")],
+      programId
+    );
+  }
+}
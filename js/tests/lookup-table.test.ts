@@ -0,0 +1,55 @@
+import { expect, test } from "@jest/globals";
+import {
+  AddressLookupTableAccount,
+  Connection,
+  Keypair,
+  PublicKey,
+} from "@solana/web3.js";
+import { buildVotingInstruction, SNS_REPUTATION_ID } from "../src/bindings";
+import { buildVersionedTransaction } from "../src/lookup_table";
+import { VoteValue } from "../src/state";
+
+test("stake accounts are resolved through the lookup table", async () => {
+  const voter = Keypair.generate().publicKey;
+  const stakeAccounts = [...Array(30)].map(() => Keypair.generate().publicKey);
+  const lookupTable = new AddressLookupTableAccount({
+    key: Keypair.generate().publicKey,
+    state: {
+      deactivationSlot: BigInt("18446744073709551615"),
+      lastExtendedSlot: 0,
+      lastExtendedSlotStartIndex: 0,
+      authority: voter,
+      addresses: stakeAccounts,
+    },
+  });
+
+  // None of the accounts exist yet, there is nothing to migrate
+  const connection = {
+    getMultipleAccountsInfo: async (keys: PublicKey[]) => keys.map(() => null),
+  } as unknown as Connection;
+
+  const instructions = await buildVotingInstruction(connection, {
+    programId: SNS_REPUTATION_ID,
+    voter,
+    userKey: Keypair.generate().publicKey,
+    userVotePdaAddress: Keypair.generate().publicKey,
+    reputationScorePdaAddress: Keypair.generate().publicKey,
+    voterProfilePdaAddress: Keypair.generate().publicKey,
+    configPdaAddress: Keypair.generate().publicKey,
+    voteValue: VoteValue.Upvote,
+    voterStakeAddresses: stakeAccounts,
+  });
+  expect(instructions.length).toBe(1);
+
+  const tx = buildVersionedTransaction(
+    voter,
+    instructions,
+    new PublicKey(0).toBase58(),
+    [lookupTable]
+  );
+
+  expect(tx.message.addressTableLookups.length).toBe(1);
+  expect(tx.message.addressTableLookups[0].readonlyIndexes.length).toBe(30);
+  // Serializes within the transaction size limit, unlike a legacy transaction
+  expect(tx.serialize().length).toBeLessThanOrEqual(1232);
+});
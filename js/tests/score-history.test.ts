@@ -0,0 +1,26 @@
+import { expect, test } from "@jest/globals";
+import { getTimeWeightedScore } from "../src/secondary_bindings";
+import { ScoreSnapshot } from "../src/state";
+
+const snapshot = (
+  epoch: number,
+  timestamp: number,
+  upvote: number,
+  downvote: number
+): ScoreSnapshot => ({
+  epoch: BigInt(epoch),
+  timestamp: BigInt(timestamp),
+  upvote: BigInt(upvote),
+  downvote: BigInt(downvote),
+});
+
+test("snapshots are weighted by the time they stayed current", () => {
+  // A long bad history followed by a burst of upvotes
+  const series = [snapshot(10, 0, 0, 100), snapshot(11, 900, 300, 100)];
+
+  expect(getTimeWeightedScore(series, BigInt(1000))).toBe(BigInt(-70));
+});
+
+test("empty histories have no average", () => {
+  expect(getTimeWeightedScore([])).toBeNull();
+});
@@ -0,0 +1,57 @@
+import { expect, test } from "@jest/globals";
+import {
+  CREDIBILITY_SCALE,
+  SCORE_SCALE,
+  WILSON_Z_95,
+  bayesianAverage,
+  credibilityMultiplier,
+  wilsonLowerBound,
+} from "../src/score";
+
+// Values shared with the tests of the program, which must compute the exact same scores
+const MAX_U64 = (BigInt(1) << BigInt(64)) - BigInt(1);
+
+test("wilson lower bound", () => {
+  const cases: [bigint, bigint, bigint][] = [
+    [BigInt(0), BigInt(0), BigInt(0)],
+    [BigInt(0), BigInt(1), BigInt(0)],
+    [BigInt(1), BigInt(0), BigInt(2065)],
+    [BigInt(1), BigInt(1), BigInt(945)],
+    [BigInt(8), BigInt(2), BigInt(4901)],
+    [BigInt(80), BigInt(20), BigInt(7111)],
+    [BigInt(800), BigInt(200), BigInt(7740)],
+    [BigInt(100), BigInt(0), BigInt(9630)],
+    [MAX_U64, MAX_U64, BigInt(4999)],
+  ];
+  for (const [upvoters, downvoters, expected] of cases) {
+    expect(wilsonLowerBound(upvoters, downvoters, WILSON_Z_95)).toBe(expected);
+  }
+  expect(wilsonLowerBound(BigInt(80), BigInt(20), BigInt(0))).toBe(
+    BigInt(8000)
+  );
+});
+
+test("bayesian average", () => {
+  const prior = { mean: BigInt(5000), weight: BigInt(100) };
+  expect(bayesianAverage(BigInt(0), BigInt(0), prior)).toBe(BigInt(5000));
+  expect(bayesianAverage(BigInt(100), BigInt(0), prior)).toBe(BigInt(7500));
+  expect(
+    bayesianAverage(BigInt(300), BigInt(100), {
+      mean: BigInt(5000),
+      weight: BigInt(1000),
+    })
+  ).toBe(BigInt(5714));
+  expect(
+    bayesianAverage(MAX_U64, BigInt(0), { mean: BigInt(0), weight: BigInt(0) })
+  ).toBe(SCORE_SCALE);
+});
+
+test("credibility multiplier", () => {
+  expect(credibilityMultiplier(BigInt(0), BigInt(0))).toBe(CREDIBILITY_SCALE);
+  expect(credibilityMultiplier(BigInt(3), BigInt(0))).toBe(CREDIBILITY_SCALE);
+  expect(credibilityMultiplier(BigInt(20), BigInt(80))).toBe(
+    CREDIBILITY_SCALE
+  );
+  expect(credibilityMultiplier(BigInt(80), BigInt(20))).toBe(BigInt(12_111));
+  expect(credibilityMultiplier(MAX_U64, BigInt(0))).toBe(BigInt(14_999));
+});
@@ -6,9 +6,11 @@ import {
   SNS_REPUTATION_ID_DEVNET,
 } from "../src/bindings";
 import {
+  getConfigKey,
   getReputationScore,
   getUserVoteAddress,
   getReputationScoreKey,
+  getVoterProfileKey,
   getUserVote,
   getAllVotersForUser,
   getAllVoteesForVoter,
@@ -58,16 +60,31 @@ const makeVote = async ({
     SNS_REPUTATION_ID_DEVNET
   );
 
-  const ix = buildVotingInstruction({
+  const [voterProfileAddress] = await getVoterProfileKey(
+    voter.publicKey,
+    SNS_REPUTATION_ID_DEVNET
+  );
+  const [configAddress] = await getConfigKey(SNS_REPUTATION_ID_DEVNET);
+
+  const instructions = await buildVotingInstruction(connection, {
     programId: SNS_REPUTATION_ID_DEVNET,
     voter: voter.publicKey,
     userKey: votee.publicKey,
     userVotePdaAddress: userVoteAddress,
     reputationScorePdaAddress: reputationScoreAddress,
+    voterProfilePdaAddress: voterProfileAddress,
+    configPdaAddress: configAddress,
     voteValue: vote,
+    // Votes don't need stake on devnet
+    voterStakeAddresses: [],
   });
 
-  await signAndSendTransactionInstructions(connection, [voter], voter, [ix]);
+  await signAndSendTransactionInstructions(
+    connection,
+    [voter],
+    voter,
+    instructions
+  );
 
   return { voter };
 };
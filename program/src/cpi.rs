@@ -1,7 +1,14 @@
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, msg, program::invoke_signed,
-    program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, rent::Rent,
-    system_instruction::create_account, sysvar::Sysvar,
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::{create_account, transfer},
+    sysvar::Sysvar,
 };
 
 #[allow(missing_docs)]
@@ -36,6 +43,24 @@ impl Cpi {
         )
     }
 
+    /// Resizes a program account, `fee_payer` topping up its lamports to stay rent exempt
+    pub fn realloc_account<'a>(
+        system_program: &AccountInfo<'a>,
+        fee_payer: &AccountInfo<'a>,
+        account: &AccountInfo<'a>,
+        space: usize,
+    ) -> ProgramResult {
+        let required_lamports = Rent::get()?.minimum_balance(space);
+        let missing_lamports = required_lamports.saturating_sub(account.lamports());
+        if missing_lamports > 0 {
+            invoke(
+                &transfer(fee_payer.key, account.key, missing_lamports),
+                &[system_program.clone(), fee_payer.clone(), account.clone()],
+            )?;
+        }
+        account.realloc(space, false)
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn allocate_and_create_token_account<'a>(
         token_account_owner: &Pubkey,
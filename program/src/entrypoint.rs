@@ -51,6 +51,61 @@ impl PrintProgramError for SnsReputationError {
             SnsReputationError::CannotVoteForYourself => {
                 msg!("Error: You cannot vote for yourself")
             }
+            SnsReputationError::RateLimited => {
+                msg!("Error: Too many new votes have been cast during this epoch")
+            }
+            SnsReputationError::InvalidCommitment => {
+                msg!("Error: The vote commitment is invalid")
+            }
+            SnsReputationError::NoCommitmentExists => msg!("Error: No vote commitment exists"),
+            SnsReputationError::PendingCommitment => {
+                msg!("Error: The vote has been committed and must be revealed first")
+            }
+            SnsReputationError::OutsideRevealWindow => {
+                msg!("Error: The vote can only be revealed during its reveal window")
+            }
+            SnsReputationError::CommitmentNotExpired => {
+                msg!("Error: The vote commitment has not expired yet")
+            }
+            SnsReputationError::InvalidDelegation => msg!("Error: The delegation is invalid"),
+            SnsReputationError::DelegationExpired => msg!("Error: The delegation has expired"),
+            SnsReputationError::DelegationNotAllowed => {
+                msg!("Error: The delegation does not allow this vote")
+            }
+            SnsReputationError::InvalidCurator => msg!("Error: The curator is invalid"),
+            SnsReputationError::FollowerOverride => {
+                msg!("Error: The follower voted directly on this user")
+            }
+            SnsReputationError::InvalidRelaySignature => {
+                msg!("Error: The relayed vote signature is invalid")
+            }
+            SnsReputationError::RelayedVoteExpired => {
+                msg!("Error: The relayed vote has expired")
+            }
+            SnsReputationError::InvalidRelayNonce => {
+                msg!("Error: The relayed vote nonce is invalid")
+            }
+            SnsReputationError::TooManyStakeAccounts => {
+                msg!("Error: Too many stake accounts were passed")
+            }
+            SnsReputationError::InvalidShard => {
+                msg!("Error: The reputation shard is invalid")
+            }
+            SnsReputationError::OutdatedAccountLayout => {
+                msg!("Error: The account uses an outdated layout")
+            }
+            SnsReputationError::Overflow => {
+                msg!("Error: Arithmetic overflow")
+            }
+            SnsReputationError::ScoreUnderflow => {
+                msg!("Error: The vote tallies would become negative")
+            }
+            SnsReputationError::InvalidAuthority => {
+                msg!("Error: The signer is not the upgrade authority of the program")
+            }
+            SnsReputationError::WrongReputationAccount => {
+                msg!("Error: The vote is tallied in another reputation account")
+            }
         }
     }
 }
@@ -24,6 +24,48 @@ pub enum SnsReputationError {
     InvalidStakeAccount,
     #[error("You cannot vote for yourself")]
     CannotVoteForYourself,
+    #[error("Too many new votes have been cast during this epoch")]
+    RateLimited,
+    #[error("The vote commitment is invalid")]
+    InvalidCommitment,
+    #[error("No vote commitment exists")]
+    NoCommitmentExists,
+    #[error("The vote has been committed and must be revealed first")]
+    PendingCommitment,
+    #[error("The vote can only be revealed during its reveal window")]
+    OutsideRevealWindow,
+    #[error("The vote commitment has not expired yet")]
+    CommitmentNotExpired,
+    #[error("The delegation is invalid")]
+    InvalidDelegation,
+    #[error("The delegation has expired")]
+    DelegationExpired,
+    #[error("The delegation does not allow this vote")]
+    DelegationNotAllowed,
+    #[error("The curator is invalid")]
+    InvalidCurator,
+    #[error("The follower voted directly on this user")]
+    FollowerOverride,
+    #[error("The relayed vote signature is invalid")]
+    InvalidRelaySignature,
+    #[error("The relayed vote has expired")]
+    RelayedVoteExpired,
+    #[error("The relayed vote nonce is invalid")]
+    InvalidRelayNonce,
+    #[error("Too many stake accounts were passed")]
+    TooManyStakeAccounts,
+    #[error("The reputation shard is invalid")]
+    InvalidShard,
+    #[error("The account uses an outdated layout")]
+    OutdatedAccountLayout,
+    #[error("Arithmetic overflow")]
+    Overflow,
+    #[error("The vote tallies would become negative")]
+    ScoreUnderflow,
+    #[error("The signer is not the upgrade authority of the program")]
+    InvalidAuthority,
+    #[error("The vote is tallied in another reputation account")]
+    WrongReputationAccount,
 }
 
 impl From<SnsReputationError> for ProgramError {
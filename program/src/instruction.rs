@@ -1,4 +1,8 @@
-pub use crate::processor::vote;
+pub use crate::processor::{
+    aggregate, close_expired_commitment, commit_vote, create_delegation, delegated_vote,
+    follow_curator, migrate, propagate_curator_vote, relay_vote, reveal_vote, revoke_delegation,
+    set_config, snapshot, unfollow_curator, vote,
+};
 use {
     bonfida_utils::InstructionsAccount,
     borsh::{BorshDeserialize, BorshSerialize},
@@ -8,16 +12,301 @@ use {
 #[allow(missing_docs)]
 #[derive(BorshDeserialize, BorshSerialize, FromPrimitive)]
 pub enum ProgramInstruction {
-    /// An example instruction //TODO
+    /// Vote over a votee, change or undo an existing vote
     ///
-    /// | Index | Writable | Signer | Description                   |
-    /// | --------------------------------------------------------- |
-    /// | 0     | ❌        | ❌      | The system program account    |
-    /// | 1     | ❌        | ❌      | The SPL token program account |
-    /// | 2     | ✅        | ✅      | Fee payer account             |
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ❌        | ❌      | The system program account                   |
+    /// | 1     | ✅        | ❌      | The rent payer, signs on account creation     |
+    /// | 2     | ❌        | ✅      | The voter                                    |
+    /// | 3     | ✅        | ❌      | The ReputationScore or ReputationShard PDA    |
+    /// | 4     | ✅        | ❌      | The UserVote PDA                             |
+    /// | 5     | ✅        | ❌      | The VoterProfile PDA                         |
+    /// | 6     | ❌        | ❌      | The Config PDA                               |
+    /// | 7     | ❌        | ❌      | The voter's ReputationScore PDA (optional)    |
+    /// | 7..   | ❌        | ❌      | The voter's stake accounts                   |
     Vote,
+    /// Commit to a hidden vote over a votee
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ❌        | ❌      | The system program account                   |
+    /// | 1     | ✅        | ✅      | The rent payer                               |
+    /// | 2     | ❌        | ✅      | The voter                                    |
+    /// | 3     | ✅        | ❌      | The UserVote PDA                             |
+    /// | 4     | ✅        | ❌      | The VoterProfile PDA                         |
+    /// | 5     | ❌        | ❌      | The Config PDA                               |
+    CommitVote,
+    /// Reveal a committed vote and apply its weight to the votee's score
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ❌        | ❌      | The system program account                   |
+    /// | 1     | ✅        | ✅      | The rent payer                               |
+    /// | 2     | ❌        | ✅      | The voter                                    |
+    /// | 3     | ✅        | ❌      | The ReputationScore PDA, or a shard of it    |
+    /// | 4     | ✅        | ❌      | The UserVote PDA                             |
+    /// | 5     | ✅        | ❌      | The VoterProfile PDA                         |
+    /// | 6     | ❌        | ❌      | The voter's ReputationScore PDA (optional)    |
+    /// | 6..   | ❌        | ❌      | The voter's stake accounts                   |
+    RevealVote,
+    /// Close a committed vote that was not revealed in time
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ✅        | ❌      | The rent payer, receives the rent            |
+    /// | 1     | ✅        | ❌      | The UserVote PDA                             |
+    CloseExpiredCommitment,
+    /// Authorize a delegate to vote on behalf of a staker, or update an existing delegation
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ❌        | ❌      | The system program account                   |
+    /// | 1     | ✅        | ✅      | The staker, fee payer account                |
+    /// | 2     | ❌        | ❌      | The delegate                                 |
+    /// | 3     | ✅        | ❌      | The Delegation PDA                           |
+    CreateDelegation,
+    /// Revoke a delegation
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ✅        | ✅      | The staker, receives the rent                |
+    /// | 1     | ✅        | ❌      | The Delegation PDA                           |
+    RevokeDelegation,
+    /// Vote on behalf of a staker with a delegated key
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ❌        | ❌      | The system program account                   |
+    /// | 1     | ✅        | ❌      | The rent payer, signs on account creation     |
+    /// | 2     | ❌        | ✅      | The delegate                                 |
+    /// | 3     | ❌        | ❌      | The staker                                   |
+    /// | 4     | ❌        | ❌      | The Delegation PDA                           |
+    /// | 5     | ✅        | ❌      | The ReputationScore or ReputationShard PDA    |
+    /// | 6     | ✅        | ❌      | The staker's UserVote PDA                    |
+    /// | 7     | ✅        | ❌      | The staker's VoterProfile PDA                |
+    /// | 8     | ❌        | ❌      | The Config PDA                               |
+    /// | 9     | ❌        | ❌      | The staker's ReputationScore PDA (optional)   |
+    /// | 9..   | ❌        | ❌      | The staker's stake accounts                  |
+    DelegatedVote,
+    /// Follow a curator, or switch to another curator
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ❌        | ❌      | The system program account                   |
+    /// | 1     | ✅        | ✅      | The follower, fee payer account              |
+    /// | 2     | ✅        | ❌      | The Follow PDA                               |
+    FollowCurator,
+    /// Stop following a curator
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ✅        | ✅      | The follower, receives the rent              |
+    /// | 1     | ✅        | ❌      | The Follow PDA                               |
+    UnfollowCurator,
+    /// Propagate a curator's vote to one of its followers (permissionless crank)
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ❌        | ❌      | The system program account                   |
+    /// | 1     | ✅        | ❌      | The rent payer, signs on account creation     |
+    /// | 2     | ❌        | ❌      | The follower                                 |
+    /// | 3     | ❌        | ❌      | The follower's Follow PDA                    |
+    /// | 4     | ❌        | ❌      | The curator's UserVote PDA                   |
+    /// | 5     | ✅        | ❌      | The ReputationScore or ReputationShard PDA    |
+    /// | 6     | ✅        | ❌      | The follower's UserVote PDA                  |
+    /// | 7     | ✅        | ❌      | The follower's VoterProfile PDA              |
+    /// | 8     | ❌        | ❌      | The Config PDA                               |
+    /// | 9     | ❌        | ❌      | The follower's ReputationScore PDA (optional) |
+    /// | 9..   | ❌        | ❌      | The follower's stake accounts                |
+    PropagateCuratorVote,
+    /// Submit a vote signed off-chain by the voter, verified by the preceding Ed25519 instruction
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ❌        | ❌      | The system program account                   |
+    /// | 1     | ❌        | ❌      | The instructions sysvar                      |
+    /// | 2     | ✅        | ✅      | The relayer, rent payer account              |
+    /// | 3     | ❌        | ❌      | The voter                                    |
+    /// | 4     | ✅        | ❌      | The ReputationScore or ReputationShard PDA    |
+    /// | 5     | ✅        | ❌      | The UserVote PDA                             |
+    /// | 6     | ✅        | ❌      | The VoterProfile PDA                         |
+    /// | 7     | ❌        | ❌      | The Config PDA                               |
+    /// | 8     | ❌        | ❌      | The voter's ReputationScore PDA (optional)    |
+    /// | 8..   | ❌        | ❌      | The voter's stake accounts                   |
+    RelayVote,
+    /// Fold the ReputationShard accounts of a user into its ReputationScore (permissionless crank)
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ❌        | ❌      | The system program account                   |
+    /// | 1     | ✅        | ❌      | The rent payer, signs on account creation     |
+    /// | 2     | ✅        | ❌      | The ReputationScore PDA                      |
+    /// | 3..   | ✅        | ❌      | The ReputationShard PDAs                     |
+    Aggregate,
+    /// Move a ReputationScore or UserVote account to the latest layout (permissionless)
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ❌        | ❌      | The system program account                   |
+    /// | 1     | ✅        | ✅      | The rent payer account                       |
+    /// | 2     | ✅        | ❌      | The ReputationScore or UserVote PDA          |
+    Migrate,
+    /// Record the current tallies of a user in its ScoreHistory (permissionless crank)
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ❌        | ❌      | The system program account                   |
+    /// | 1     | ✅        | ❌      | The rent payer, signs on account creation     |
+    /// | 2     | ❌        | ❌      | The ReputationScore PDA                      |
+    /// | 3     | ✅        | ❌      | The ScoreHistory PDA                         |
+    /// | 4..12 | ❌        | ❌      | The ReputationShard PDAs, ordered by index   |
+    Snapshot,
+    /// Set the parameters of the program, signed by its upgrade authority
+    ///
+    /// | Index | Writable | Signer | Description                                  |
+    /// | ------------------------------------------------------------------------ |
+    /// | 0     | ❌        | ❌      | The system program account                   |
+    /// | 1     | ✅        | ✅      | The fee payer account                        |
+    /// | 2     | ❌        | ✅      | The upgrade authority of the program         |
+    /// | 3     | ❌        | ❌      | The ProgramData account of the program       |
+    /// | 4     | ✅        | ❌      | The Config PDA                               |
+    SetConfig,
 }
 #[allow(missing_docs)]
 pub fn vote(accounts: vote::Accounts<Pubkey>, params: vote::Params) -> Instruction {
     accounts.get_instruction(crate::ID, ProgramInstruction::Vote as u8, params)
 }
+#[allow(missing_docs)]
+pub fn commit_vote(
+    accounts: commit_vote::Accounts<Pubkey>,
+    params: commit_vote::Params,
+) -> Instruction {
+    accounts.get_instruction(crate::ID, ProgramInstruction::CommitVote as u8, params)
+}
+#[allow(missing_docs)]
+pub fn reveal_vote(
+    accounts: reveal_vote::Accounts<Pubkey>,
+    params: reveal_vote::Params,
+) -> Instruction {
+    accounts.get_instruction(crate::ID, ProgramInstruction::RevealVote as u8, params)
+}
+#[allow(missing_docs)]
+pub fn close_expired_commitment(
+    accounts: close_expired_commitment::Accounts<Pubkey>,
+    params: close_expired_commitment::Params,
+) -> Instruction {
+    accounts.get_instruction(
+        crate::ID,
+        ProgramInstruction::CloseExpiredCommitment as u8,
+        params,
+    )
+}
+#[allow(missing_docs)]
+pub fn create_delegation(
+    accounts: create_delegation::Accounts<Pubkey>,
+    params: create_delegation::Params,
+) -> Instruction {
+    accounts.get_instruction(
+        crate::ID,
+        ProgramInstruction::CreateDelegation as u8,
+        params,
+    )
+}
+#[allow(missing_docs)]
+pub fn revoke_delegation(
+    accounts: revoke_delegation::Accounts<Pubkey>,
+    params: revoke_delegation::Params,
+) -> Instruction {
+    accounts.get_instruction(
+        crate::ID,
+        ProgramInstruction::RevokeDelegation as u8,
+        params,
+    )
+}
+#[allow(missing_docs)]
+pub fn delegated_vote(
+    accounts: delegated_vote::Accounts<Pubkey>,
+    params: delegated_vote::Params,
+) -> Instruction {
+    accounts.get_instruction(crate::ID, ProgramInstruction::DelegatedVote as u8, params)
+}
+#[allow(missing_docs)]
+pub fn follow_curator(
+    accounts: follow_curator::Accounts<Pubkey>,
+    params: follow_curator::Params,
+) -> Instruction {
+    accounts.get_instruction(crate::ID, ProgramInstruction::FollowCurator as u8, params)
+}
+#[allow(missing_docs)]
+pub fn unfollow_curator(
+    accounts: unfollow_curator::Accounts<Pubkey>,
+    params: unfollow_curator::Params,
+) -> Instruction {
+    accounts.get_instruction(crate::ID, ProgramInstruction::UnfollowCurator as u8, params)
+}
+#[allow(missing_docs)]
+pub fn propagate_curator_vote(
+    accounts: propagate_curator_vote::Accounts<Pubkey>,
+    params: propagate_curator_vote::Params,
+) -> Instruction {
+    accounts.get_instruction(
+        crate::ID,
+        ProgramInstruction::PropagateCuratorVote as u8,
+        params,
+    )
+}
+#[allow(missing_docs)]
+pub fn relay_vote(
+    accounts: relay_vote::Accounts<Pubkey>,
+    params: relay_vote::Params,
+) -> Instruction {
+    accounts.get_instruction(crate::ID, ProgramInstruction::RelayVote as u8, params)
+}
+/// Ed25519 program instruction verifying the signature of a relayed vote message, to be placed
+/// right before the `RelayVote` instruction
+pub fn relay_vote_signature(voter: &Pubkey, signature: &[u8; 64], message: &[u8]) -> Instruction {
+    // Header: signature count, padding and the offsets of the single signature
+    let public_key_offset: u16 = 16;
+    let signature_offset = public_key_offset + 32;
+    let message_offset = signature_offset + 64;
+    let mut data = vec![1, 0];
+    for value in [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(voter.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    Instruction {
+        program_id: solana_program::ed25519_program::ID,
+        accounts: vec![],
+        data,
+    }
+}
+#[allow(missing_docs)]
+pub fn aggregate(accounts: aggregate::Accounts<Pubkey>, params: aggregate::Params) -> Instruction {
+    accounts.get_instruction(crate::ID, ProgramInstruction::Aggregate as u8, params)
+}
+#[allow(missing_docs)]
+pub fn migrate(accounts: migrate::Accounts<Pubkey>, params: migrate::Params) -> Instruction {
+    accounts.get_instruction(crate::ID, ProgramInstruction::Migrate as u8, params)
+}
+#[allow(missing_docs)]
+pub fn snapshot(accounts: snapshot::Accounts<Pubkey>, params: snapshot::Params) -> Instruction {
+    accounts.get_instruction(crate::ID, ProgramInstruction::Snapshot as u8, params)
+}
+#[allow(missing_docs)]
+pub fn set_config(
+    accounts: set_config::Accounts<Pubkey>,
+    params: set_config::Params,
+) -> Instruction {
+    accounts.get_instruction(crate::ID, ProgramInstruction::SetConfig as u8, params)
+}
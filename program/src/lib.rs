@@ -15,4 +15,7 @@ pub mod processor;
 #[allow(missing_docs)]
 pub mod cpi;
 
+#[doc(hidden)]
+pub mod utils;
+
 declare_id_with_central_state!("4X9mF1yUx2ez6ifYCmr2aYJnX5DkKAxbu5QD93s7gooG");
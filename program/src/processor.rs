@@ -9,6 +9,20 @@ use {
 
 use crate::instruction::ProgramInstruction;
 
+pub mod aggregate;
+pub mod close_expired_commitment;
+pub mod commit_vote;
+pub mod create_delegation;
+pub mod delegated_vote;
+pub mod follow_curator;
+pub mod migrate;
+pub mod propagate_curator_vote;
+pub mod relay_vote;
+pub mod reveal_vote;
+pub mod revoke_delegation;
+pub mod set_config;
+pub mod snapshot;
+pub mod unfollow_curator;
 pub mod vote;
 
 pub struct Processor {}
@@ -32,6 +46,90 @@ impl Processor {
                     .map_err(|_| ProgramError::InvalidInstructionData)?;
                 vote::process(program_id, accounts, params)?;
             }
+            ProgramInstruction::CommitVote => {
+                msg!("Instruction: Commit Vote");
+                let params = commit_vote::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                commit_vote::process(program_id, accounts, params)?;
+            }
+            ProgramInstruction::RevealVote => {
+                msg!("Instruction: Reveal Vote");
+                let params = reveal_vote::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                reveal_vote::process(program_id, accounts, params)?;
+            }
+            ProgramInstruction::CloseExpiredCommitment => {
+                msg!("Instruction: Close Expired Commitment");
+                let params = close_expired_commitment::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                close_expired_commitment::process(program_id, accounts, params)?;
+            }
+            ProgramInstruction::CreateDelegation => {
+                msg!("Instruction: Create Delegation");
+                let params = create_delegation::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                create_delegation::process(program_id, accounts, params)?;
+            }
+            ProgramInstruction::RevokeDelegation => {
+                msg!("Instruction: Revoke Delegation");
+                let params = revoke_delegation::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                revoke_delegation::process(program_id, accounts, params)?;
+            }
+            ProgramInstruction::DelegatedVote => {
+                msg!("Instruction: Delegated Vote");
+                let params = delegated_vote::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                delegated_vote::process(program_id, accounts, params)?;
+            }
+            ProgramInstruction::FollowCurator => {
+                msg!("Instruction: Follow Curator");
+                let params = follow_curator::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                follow_curator::process(program_id, accounts, params)?;
+            }
+            ProgramInstruction::UnfollowCurator => {
+                msg!("Instruction: Unfollow Curator");
+                let params = unfollow_curator::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                unfollow_curator::process(program_id, accounts, params)?;
+            }
+            ProgramInstruction::PropagateCuratorVote => {
+                msg!("Instruction: Propagate Curator Vote");
+                let params = propagate_curator_vote::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                propagate_curator_vote::process(program_id, accounts, params)?;
+            }
+            ProgramInstruction::RelayVote => {
+                msg!("Instruction: Relay Vote");
+                let params = relay_vote::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                relay_vote::process(program_id, accounts, params)?;
+            }
+            ProgramInstruction::Aggregate => {
+                msg!("Instruction: Aggregate");
+                let params = aggregate::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                aggregate::process(program_id, accounts, params)?;
+            }
+            ProgramInstruction::Migrate => {
+                msg!("Instruction: Migrate");
+                let params = migrate::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                migrate::process(program_id, accounts, params)?;
+            }
+            ProgramInstruction::Snapshot => {
+                msg!("Instruction: Snapshot");
+                let params = snapshot::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                snapshot::process(program_id, accounts, params)?;
+            }
+            ProgramInstruction::SetConfig => {
+                msg!("Instruction: Set Config");
+                let params = set_config::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                set_config::process(program_id, accounts, params)?;
+            }
         }
 
         Ok(())
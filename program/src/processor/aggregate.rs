@@ -0,0 +1,102 @@
+//! Permissionless instruction for folding the ReputationShard accounts of a user
+//! into its ReputationScore.
+
+use crate::error::SnsReputationError;
+use crate::state::{reputation_shard::ReputationShard, Tag};
+use crate::utils::load_or_create_reputation_score;
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_account_owner},
+        BorshSize, InstructionsAccount,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// The user whose shards are aggregated
+    pub user_key: Pubkey,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    pub system_program: &'a T,
+
+    #[cons(writable)]
+    /// Pays for the ReputationScore if it doesn't exist yet, in which case it has to sign
+    pub rent_payer: &'a T,
+
+    /// PDA for storing ReputationScore data
+    #[cons(writable)]
+    pub reputation_state_account: &'a T,
+
+    /// The ReputationShard PDAs to aggregate
+    #[cons(writable)]
+    pub reputation_shard_accounts: &'a [T],
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            rent_payer: next_account_info(accounts_iter)?,
+            reputation_state_account: next_account_info(accounts_iter)?,
+            reputation_shard_accounts: accounts_iter.as_slice(),
+        };
+
+        // Check keys
+        check_account_key(accounts.system_program, &system_program::ID)?;
+
+        // Check owners
+        for shard_account in accounts.reputation_shard_accounts {
+            check_account_owner(shard_account, program_id)?;
+        }
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    let mut reputation_score = load_or_create_reputation_score(
+        program_id,
+        accounts.system_program,
+        accounts.rent_payer,
+        accounts.reputation_state_account,
+        &params.user_key,
+    )?;
+
+    for shard_account in accounts.reputation_shard_accounts {
+        // Shards are only created by the program, so the stored user key can be trusted
+        let mut shard =
+            ReputationShard::from_buffer(&shard_account.data.borrow(), Tag::ReputationShard)?;
+        if shard.user_key != params.user_key {
+            return Err(SnsReputationError::InvalidShard.into());
+        }
+
+        shard.aggregate_into(&mut reputation_score)?;
+
+        shard
+            .save(&mut shard_account.data.borrow_mut())
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+    }
+
+    reputation_score
+        .save(&mut accounts.reputation_state_account.data.borrow_mut())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! Permissionless instruction for closing a committed vote that was never revealed.
+//!
+//! Once the reveal window of a commitment is over, anyone can close its UserVote
+//! PDA. The rent is returned to the account that paid for it.
+
+use solana_program::{clock::Clock, sysvar::Sysvar};
+
+use crate::error::SnsReputationError;
+use crate::state::{user_vote::UserVote, Tag};
+use crate::utils::close_account;
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_account_owner},
+        BorshSize, InstructionsAccount,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The account that paid for the committed vote, receives the rent back
+    #[cons(writable)]
+    pub rent_payer: &'a T,
+
+    /// PDA that stores the expired committed vote
+    #[cons(writable)]
+    pub user_vote_state_account: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            rent_payer: next_account_info(accounts_iter)?,
+            user_vote_state_account: next_account_info(accounts_iter)?,
+        };
+
+        // Check owners
+        check_account_owner(accounts.user_vote_state_account, program_id)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], _params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    let user_vote = UserVote::from_buffer(
+        &accounts.user_vote_state_account.data.borrow(),
+        Tag::UserVote,
+    )?;
+
+    check_account_key(accounts.rent_payer, user_vote.rent_destination())?;
+
+    if !user_vote.is_pending_commitment() {
+        return Err(SnsReputationError::NoCommitmentExists.into());
+    }
+
+    if !user_vote.is_commitment_expired(Clock::get()?.slot) {
+        return Err(SnsReputationError::CommitmentNotExpired.into());
+    }
+
+    close_account(accounts.user_vote_state_account, accounts.rent_payer);
+
+    Ok(())
+}
@@ -0,0 +1,157 @@
+//! Instruction for committing to a hidden vote over the votee account.
+//!
+//! The UserVote PDA is created with the hash of the vote value and a salt, and
+//! the ReputationScore is left untouched until the vote is revealed with
+//! `RevealVote`.
+
+use solana_program::{clock::Clock, sysvar::Sysvar};
+
+use crate::cpi::Cpi;
+use crate::error::SnsReputationError;
+use crate::state::user_vote::UserVote;
+use crate::utils::{get_max_new_votes_per_epoch, load_or_create_voter_profile};
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_signer},
+        BorshSize, InstructionsAccount,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// votee account pubkey
+    pub user_key: Pubkey,
+    /// Commitment to the vote, as computed by `UserVote::compute_commitment`
+    pub commitment: [u8; 32],
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    pub system_program: &'a T,
+
+    #[cons(writable, signer)]
+    /// Pays for the accounts created by the instruction, can be the voter itself
+    pub rent_payer: &'a T,
+
+    #[cons(signer)]
+    /// The voter, which can be a PDA signing through CPI
+    pub voter: &'a T,
+
+    /// PDA that stores voter's vote, that is derived from votee and vote's keys
+    #[cons(writable)]
+    pub user_vote_state_account: &'a T,
+
+    /// PDA that stores the voter's activity, derived from the voter's key
+    #[cons(writable)]
+    pub voter_profile_account: &'a T,
+
+    /// PDA that stores the parameters of the program, which may not exist yet
+    pub config_account: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        _program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            rent_payer: next_account_info(accounts_iter)?,
+            voter: next_account_info(accounts_iter)?,
+            user_vote_state_account: next_account_info(accounts_iter)?,
+            voter_profile_account: next_account_info(accounts_iter)?,
+            config_account: next_account_info(accounts_iter)?,
+        };
+
+        // Check keys
+        check_account_key(accounts.system_program, &system_program::ID)?;
+
+        // Check signer
+        check_signer(accounts.rent_payer)?;
+        check_signer(accounts.voter)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    if *accounts.voter.key == params.user_key {
+        return Err(SnsReputationError::CannotVoteForYourself.into());
+    }
+
+    if params.commitment == [0; 32] {
+        return Err(SnsReputationError::InvalidCommitment.into());
+    }
+
+    let (user_vote_key, user_vote_nonce) =
+        UserVote::find_key(program_id, &(params.user_key, *accounts.voter.key));
+
+    check_account_key(accounts.user_vote_state_account, &user_vote_key)?;
+
+    // Only new votes can be committed, existing votes are changed with `Vote`
+    if !accounts.user_vote_state_account.data_is_empty() {
+        return Err(SnsReputationError::AlreadyVoted.into());
+    }
+
+    let mut voter_profile = load_or_create_voter_profile(
+        program_id,
+        accounts.system_program,
+        accounts.rent_payer,
+        accounts.voter_profile_account,
+        accounts.voter.key,
+    )?;
+
+    let clock = Clock::get()?;
+
+    // Committed votes count towards the rate limit as they allocate a new UserVote
+    let max_new_votes = get_max_new_votes_per_epoch(program_id, accounts.config_account)?;
+    voter_profile.register_new_vote(clock.epoch, max_new_votes)?;
+
+    let space = UserVote::space();
+
+    Cpi::create_account(
+        program_id,
+        accounts.system_program,
+        accounts.rent_payer,
+        accounts.user_vote_state_account,
+        &[
+            params.user_key.as_ref(),
+            accounts.voter.key.as_ref(),
+            &[user_vote_nonce],
+        ],
+        space,
+    )?;
+
+    let user_vote = UserVote {
+        value: 0,
+        votee: params.user_key,
+        voter: *accounts.voter.key,
+        commitment: params.commitment,
+        commit_slot: clock.slot,
+        rent_payer: *accounts.rent_payer.key,
+        nonce: user_vote_nonce,
+        ..UserVote::default()
+    };
+
+    user_vote
+        .save(&mut accounts.user_vote_state_account.data.borrow_mut())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    voter_profile
+        .save(&mut accounts.voter_profile_account.data.borrow_mut())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! Instruction for authorizing a delegate to vote on behalf of a staker.
+//!
+//! The staker signs once to create the Delegation PDA, after which the delegate
+//! can use `DelegatedVote` with the permissions and until the expiry stored in it.
+//! Calling this instruction on an existing delegation updates it.
+
+use enumflags2::BitFlags;
+
+use crate::cpi::Cpi;
+use crate::error::SnsReputationError;
+use crate::state::{
+    delegation::{Delegation, DelegationPermission},
+    Tag,
+};
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_signer},
+        BorshSize, InstructionsAccount,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// Unix timestamp after which the delegation can no longer be used, 0 if it never expires
+    pub expiry: i64,
+    /// Bit flags of `DelegationPermission`
+    pub permissions: u8,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    pub system_program: &'a T,
+
+    #[cons(writable, signer)]
+    /// The stake authority, fee payer account
+    pub staker: &'a T,
+
+    /// The key allowed to vote on behalf of the staker
+    pub delegate: &'a T,
+
+    /// PDA that stores the delegation, derived from the staker and delegate keys
+    #[cons(writable)]
+    pub delegation_account: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        _program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            staker: next_account_info(accounts_iter)?,
+            delegate: next_account_info(accounts_iter)?,
+            delegation_account: next_account_info(accounts_iter)?,
+        };
+
+        // Check keys
+        check_account_key(accounts.system_program, &system_program::ID)?;
+
+        // Check signer
+        check_signer(accounts.staker)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    let (delegation_key, delegation_nonce) =
+        Delegation::find_key(program_id, accounts.staker.key, accounts.delegate.key);
+
+    check_account_key(accounts.delegation_account, &delegation_key)?;
+
+    if accounts.staker.key == accounts.delegate.key {
+        return Err(SnsReputationError::InvalidDelegation.into());
+    }
+
+    let permissions = BitFlags::<DelegationPermission>::from_bits(params.permissions)
+        .map_err(|_| SnsReputationError::InvalidDelegation)?;
+
+    if permissions.is_empty() {
+        return Err(SnsReputationError::InvalidDelegation.into());
+    }
+
+    if accounts.delegation_account.data_is_empty() {
+        let space = Delegation::default().borsh_len() + std::mem::size_of::<Tag>();
+        Cpi::create_account(
+            program_id,
+            accounts.system_program,
+            accounts.staker,
+            accounts.delegation_account,
+            &[
+                Delegation::SEED,
+                accounts.staker.key.as_ref(),
+                accounts.delegate.key.as_ref(),
+                &[delegation_nonce],
+            ],
+            space,
+        )?;
+    }
+
+    let delegation = Delegation {
+        nonce: delegation_nonce,
+        staker: *accounts.staker.key,
+        delegate: *accounts.delegate.key,
+        expiry: params.expiry,
+        permissions: permissions.bits(),
+    };
+
+    delegation
+        .save(&mut accounts.delegation_account.data.borrow_mut())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+//! Instruction for voting on behalf of a staker with a delegated key.
+//!
+//! The vote is attributed to the staker and weighted by the staker's stake
+//! accounts, exactly as if the staker had used `Vote`.
+
+use solana_program::{clock::Clock, sysvar::Sysvar};
+
+use crate::error::SnsReputationError;
+use crate::processor::vote::{self, cast_vote, VoteContext};
+use crate::state::{delegation::Delegation, user_vote::UserVote, Tag};
+use crate::utils::next_voter_reputation_account;
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_account_owner, check_signer},
+        InstructionsAccount,
+    },
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+    },
+};
+
+pub use vote::Params;
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    pub system_program: &'a T,
+
+    #[cons(writable)]
+    /// Pays for the accounts created by the instruction, in which case it has to sign. When the
+    /// vote is undone, this is the account that paid for the UserVote
+    pub rent_payer: &'a T,
+
+    #[cons(signer)]
+    /// The delegate
+    pub delegate: &'a T,
+
+    /// The stake authority the vote is attributed to
+    pub staker: &'a T,
+
+    /// PDA that stores the delegation, derived from the staker and delegate keys
+    pub delegation_account: &'a T,
+
+    /// PDA for storing ReputationScore data, or the ReputationShard of the staker
+    #[cons(writable)]
+    pub reputation_state_account: &'a T,
+
+    /// PDA that stores the staker's vote, that is derived from votee and staker's keys
+    #[cons(writable)]
+    pub user_vote_state_account: &'a T,
+
+    /// PDA that stores the staker's activity, derived from the staker's key
+    #[cons(writable)]
+    pub voter_profile_account: &'a T,
+
+    /// PDA that stores the parameters of the program, which may not exist yet
+    pub config_account: &'a T,
+
+    /// ReputationScore PDA of the staker, scaling the weight of the vote by the staker's
+    /// credibility when given
+    pub staker_reputation_account: Option<&'a T>,
+
+    /// Stake accounts associated with the staker
+    pub staker_stake_accounts: &'a [T],
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            rent_payer: next_account_info(accounts_iter)?,
+            delegate: next_account_info(accounts_iter)?,
+            staker: next_account_info(accounts_iter)?,
+            delegation_account: next_account_info(accounts_iter)?,
+            reputation_state_account: next_account_info(accounts_iter)?,
+            user_vote_state_account: next_account_info(accounts_iter)?,
+            voter_profile_account: next_account_info(accounts_iter)?,
+            config_account: next_account_info(accounts_iter)?,
+            staker_reputation_account: next_voter_reputation_account(accounts_iter, program_id),
+            staker_stake_accounts: accounts_iter.as_slice(),
+        };
+
+        // Check keys
+        check_account_key(accounts.system_program, &system_program::ID)?;
+
+        // Check owners
+        check_account_owner(accounts.delegation_account, program_id)?;
+
+        // Check signer
+        check_signer(accounts.delegate)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    let delegation =
+        Delegation::from_buffer(&accounts.delegation_account.data.borrow(), Tag::Delegation)?;
+
+    let delegation_key = Delegation::create_key(
+        program_id,
+        accounts.staker.key,
+        accounts.delegate.key,
+        delegation.nonce,
+    )?;
+
+    check_account_key(accounts.delegation_account, &delegation_key)?;
+
+    if delegation.is_expired(Clock::get()?.unix_timestamp) {
+        return Err(SnsReputationError::DelegationExpired.into());
+    }
+
+    // The vote being replaced, if any, is checked against the permissions as well
+    let previous_value = if accounts.user_vote_state_account.data_is_empty() {
+        0
+    } else {
+        check_account_owner(accounts.user_vote_state_account, program_id)?;
+        UserVote::from_buffer(
+            &accounts.user_vote_state_account.data.borrow(),
+            Tag::UserVote,
+        )?
+        .value
+    };
+
+    if !delegation.allows(previous_value, params.vote_value) {
+        return Err(SnsReputationError::DelegationNotAllowed.into());
+    }
+
+    cast_vote(
+        program_id,
+        VoteContext {
+            system_program: accounts.system_program,
+            rent_payer: accounts.rent_payer,
+            voter: accounts.staker,
+            reputation_state_account: accounts.reputation_state_account,
+            user_vote_state_account: accounts.user_vote_state_account,
+            voter_profile_account: accounts.voter_profile_account,
+            config_account: accounts.config_account,
+            voter_reputation_account: accounts.staker_reputation_account,
+            voter_stake_accounts: accounts.staker_stake_accounts,
+            followed_curator: Pubkey::default(),
+        },
+        params,
+    )
+}
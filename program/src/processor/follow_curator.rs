@@ -0,0 +1,105 @@
+//! Instruction for following a curator.
+//!
+//! Once the Follow PDA exists, the curator's votes can be propagated to the
+//! follower with `PropagateCuratorVote`, weighted by the follower's stake.
+//! Calling this instruction on an existing Follow PDA switches to another curator.
+
+use crate::cpi::Cpi;
+use crate::error::SnsReputationError;
+use crate::state::{follow::Follow, Tag};
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_signer},
+        BorshSize, InstructionsAccount,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// The curator to follow
+    pub curator: Pubkey,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    pub system_program: &'a T,
+
+    #[cons(writable, signer)]
+    /// The follower, fee payer account
+    pub follower: &'a T,
+
+    /// PDA that stores the followed curator, derived from the follower's key
+    #[cons(writable)]
+    pub follow_account: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        _program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            follower: next_account_info(accounts_iter)?,
+            follow_account: next_account_info(accounts_iter)?,
+        };
+
+        // Check keys
+        check_account_key(accounts.system_program, &system_program::ID)?;
+
+        // Check signer
+        check_signer(accounts.follower)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    let (follow_key, follow_nonce) = Follow::find_key(program_id, accounts.follower.key);
+
+    check_account_key(accounts.follow_account, &follow_key)?;
+
+    if *accounts.follower.key == params.curator || params.curator == Pubkey::default() {
+        return Err(SnsReputationError::InvalidCurator.into());
+    }
+
+    if accounts.follow_account.data_is_empty() {
+        let space = Follow::default().borsh_len() + std::mem::size_of::<Tag>();
+        Cpi::create_account(
+            program_id,
+            accounts.system_program,
+            accounts.follower,
+            accounts.follow_account,
+            &[
+                Follow::SEED,
+                accounts.follower.key.as_ref(),
+                &[follow_nonce],
+            ],
+            space,
+        )?;
+    }
+
+    let follow = Follow {
+        nonce: follow_nonce,
+        follower: *accounts.follower.key,
+        curator: params.curator,
+    };
+
+    follow
+        .save(&mut accounts.follow_account.data.borrow_mut())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    Ok(())
+}
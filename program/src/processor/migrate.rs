@@ -0,0 +1,115 @@
+//! Permissionless instruction for moving a ReputationScore or UserVote account created with a
+//! previous layout to the latest one.
+
+use crate::cpi::Cpi;
+use crate::error::SnsReputationError;
+use crate::state::{reputation_score::ReputationScore, user_vote::UserVote, Tag};
+use crate::utils::has_tag;
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_account_owner, check_signer},
+        BorshSize, InstructionsAccount,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    pub system_program: &'a T,
+
+    #[cons(writable, signer)]
+    /// Tops up the rent of the account when its size grows
+    pub rent_payer: &'a T,
+
+    /// The ReputationScore or UserVote PDA to migrate
+    #[cons(writable)]
+    pub state_account: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            rent_payer: next_account_info(accounts_iter)?,
+            state_account: next_account_info(accounts_iter)?,
+        };
+
+        // Check keys
+        check_account_key(accounts.system_program, &system_program::ID)?;
+
+        // Check owners
+        check_account_owner(accounts.state_account, program_id)?;
+
+        // Check signer
+        check_signer(accounts.rent_payer)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], _params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    // Accounts owned by the program are only ever written by it, so their content can be trusted
+    if has_tag(accounts.state_account, Tag::ReputationScore) {
+        if ReputationScore::is_latest_layout(&accounts.state_account.data.borrow()) {
+            msg!("The account already uses the latest layout");
+            return Ok(());
+        }
+        let reputation_score = ReputationScore::from_buffer(
+            &accounts.state_account.data.borrow(),
+            Tag::ReputationScore,
+        )?;
+        Cpi::realloc_account(
+            accounts.system_program,
+            accounts.rent_payer,
+            accounts.state_account,
+            ReputationScore::space(),
+        )?;
+        reputation_score
+            .save(&mut accounts.state_account.data.borrow_mut())
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+    } else if has_tag(accounts.state_account, Tag::UserVote) {
+        if UserVote::is_latest_layout(&accounts.state_account.data.borrow()) {
+            msg!("The account already uses the latest layout");
+            return Ok(());
+        }
+        let mut user_vote =
+            UserVote::from_buffer(&accounts.state_account.data.borrow(), Tag::UserVote)?;
+        // The oldest layouts don't store the nonce of the account
+        if user_vote.nonce == 0 {
+            let (key, nonce) = UserVote::find_key(program_id, &(user_vote.votee, user_vote.voter));
+            check_account_key(accounts.state_account, &key)?;
+            user_vote.nonce = nonce;
+        }
+        Cpi::realloc_account(
+            accounts.system_program,
+            accounts.rent_payer,
+            accounts.state_account,
+            UserVote::space(),
+        )?;
+        user_vote
+            .save(&mut accounts.state_account.data.borrow_mut())
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+    } else {
+        return Err(SnsReputationError::DataTypeMismatch.into());
+    }
+
+    Ok(())
+}
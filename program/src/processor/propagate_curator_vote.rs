@@ -0,0 +1,166 @@
+//! Permissionless crank propagating a curator's vote to one of its followers.
+//!
+//! The follower's UserVote over the same votee is created, changed or undone so
+//! that it matches the direction of the curator's vote, weighted by the
+//! follower's own stake. Votes cast directly by the follower override the
+//! curator and are never modified by this instruction.
+
+use crate::error::SnsReputationError;
+use crate::processor::vote::{self, cast_vote, VoteContext};
+use crate::state::{follow::Follow, user_vote::UserVote, Tag, VoteValue};
+use crate::utils::next_voter_reputation_account;
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_account_owner},
+        BorshSize, InstructionsAccount,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// votee account pubkey
+    pub user_key: Pubkey,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    pub system_program: &'a T,
+
+    #[cons(writable)]
+    /// Pays for the accounts created by the instruction, in which case it has to sign. When the
+    /// propagated vote is undone, this is the account that paid for the UserVote
+    pub rent_payer: &'a T,
+
+    /// The follower
+    pub follower: &'a T,
+
+    /// PDA that stores the followed curator, derived from the follower's key
+    pub follow_account: &'a T,
+
+    /// The curator's UserVote PDA over the votee, which may have been closed
+    pub curator_user_vote_account: &'a T,
+
+    /// PDA for storing ReputationScore data, or the ReputationShard of the follower
+    #[cons(writable)]
+    pub reputation_state_account: &'a T,
+
+    /// PDA that stores the follower's vote, that is derived from votee and follower's keys
+    #[cons(writable)]
+    pub user_vote_state_account: &'a T,
+
+    /// PDA that stores the follower's activity, derived from the follower's key
+    #[cons(writable)]
+    pub voter_profile_account: &'a T,
+
+    /// PDA that stores the parameters of the program, which may not exist yet
+    pub config_account: &'a T,
+
+    /// ReputationScore PDA of the follower, scaling the weight of the vote by the follower's
+    /// credibility when given
+    pub follower_reputation_account: Option<&'a T>,
+
+    /// Stake accounts associated with the follower
+    pub follower_stake_accounts: &'a [T],
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            rent_payer: next_account_info(accounts_iter)?,
+            follower: next_account_info(accounts_iter)?,
+            follow_account: next_account_info(accounts_iter)?,
+            curator_user_vote_account: next_account_info(accounts_iter)?,
+            reputation_state_account: next_account_info(accounts_iter)?,
+            user_vote_state_account: next_account_info(accounts_iter)?,
+            voter_profile_account: next_account_info(accounts_iter)?,
+            config_account: next_account_info(accounts_iter)?,
+            follower_reputation_account: next_voter_reputation_account(accounts_iter, program_id),
+            follower_stake_accounts: accounts_iter.as_slice(),
+        };
+
+        // Check keys
+        check_account_key(accounts.system_program, &system_program::ID)?;
+
+        // Check owners
+        check_account_owner(accounts.follow_account, program_id)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    let follow = Follow::from_buffer(&accounts.follow_account.data.borrow(), Tag::Follow)?;
+
+    let follow_key = Follow::create_key(program_id, accounts.follower.key, follow.nonce)?;
+
+    check_account_key(accounts.follow_account, &follow_key)?;
+
+    // A closed curator vote means that the curator undid its vote
+    let curator_vote_value = if accounts.curator_user_vote_account.data_is_empty() {
+        let (curator_user_vote_key, _) =
+            UserVote::find_key(program_id, &(params.user_key, follow.curator));
+
+        check_account_key(accounts.curator_user_vote_account, &curator_user_vote_key)?;
+
+        VoteValue::NoVote
+    } else {
+        check_account_owner(accounts.curator_user_vote_account, program_id)?;
+        let curator_vote = UserVote::from_buffer(
+            &accounts.curator_user_vote_account.data.borrow(),
+            Tag::UserVote,
+        )?;
+
+        let curator_user_vote_key = UserVote::create_key(
+            program_id,
+            &(params.user_key, follow.curator),
+            curator_vote.nonce,
+        )?;
+
+        check_account_key(accounts.curator_user_vote_account, &curator_user_vote_key)?;
+
+        if curator_vote.is_pending_commitment() {
+            return Err(SnsReputationError::PendingCommitment.into());
+        }
+        match curator_vote.value.signum() {
+            1 => VoteValue::Upvote,
+            -1 => VoteValue::Downvote,
+            _ => VoteValue::NoVote,
+        }
+    };
+
+    cast_vote(
+        program_id,
+        VoteContext {
+            system_program: accounts.system_program,
+            rent_payer: accounts.rent_payer,
+            voter: accounts.follower,
+            reputation_state_account: accounts.reputation_state_account,
+            user_vote_state_account: accounts.user_vote_state_account,
+            voter_profile_account: accounts.voter_profile_account,
+            config_account: accounts.config_account,
+            voter_reputation_account: accounts.follower_reputation_account,
+            voter_stake_accounts: accounts.follower_stake_accounts,
+            followed_curator: follow.curator,
+        },
+        vote::Params {
+            user_key: params.user_key,
+            vote_value: curator_vote_value,
+        },
+    )
+}
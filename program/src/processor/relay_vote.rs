@@ -0,0 +1,194 @@
+//! Instruction for submitting a vote signed off-chain by the voter.
+//!
+//! The voter signs a `VoteMessage` with their wallet and a relayer submits it,
+//! preceded by an Ed25519 program instruction verifying the signature. The
+//! relayer pays for the accounts created by the vote, and the nonce stored in
+//! the voter's profile prevents the message from being replayed.
+
+use solana_program::{clock::Clock, sysvar::Sysvar};
+
+use crate::error::SnsReputationError;
+use crate::processor::vote::{self, cast_vote, VoteContext};
+use crate::state::{voter_profile::VoterProfile, Tag, VoteValue};
+use crate::utils::{check_ed25519_signature, next_voter_reputation_account};
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_signer},
+        BorshSize, InstructionsAccount,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program, sysvar,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// votee account pubkey
+    pub user_key: Pubkey,
+    /// voter's vote
+    pub vote_value: VoteValue,
+    /// Relay nonce of the voter, as stored in its VoterProfile
+    pub nonce: u64,
+    /// Unix timestamp after which the signed vote can no longer be relayed
+    pub expiry: i64,
+}
+
+/// The message signed by the voter
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct VoteMessage {
+    /// The program the vote is meant for
+    pub program_id: Pubkey,
+    /// The voter
+    pub voter: Pubkey,
+    /// votee account pubkey
+    pub user_key: Pubkey,
+    /// voter's vote
+    pub vote_value: VoteValue,
+    /// Relay nonce of the voter
+    pub nonce: u64,
+    /// Unix timestamp after which the signed vote can no longer be relayed
+    pub expiry: i64,
+}
+
+impl VoteMessage {
+    /// Prefix of the signed message, preventing it from being mistaken for a transaction
+    pub const PREFIX: &'static [u8] = b"sns-reputation relayed vote\n";
+
+    /// Returns the bytes the voter has to sign
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Self::PREFIX.to_vec();
+        bytes.extend(self.try_to_vec().unwrap());
+        bytes
+    }
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    pub system_program: &'a T,
+
+    /// The instructions sysvar, used to inspect the Ed25519 instruction
+    pub instructions_sysvar: &'a T,
+
+    #[cons(writable, signer)]
+    /// The relayer, pays for the accounts created by the instruction. When the vote is undone,
+    /// this is the account that paid for the UserVote
+    pub rent_payer: &'a T,
+
+    /// The voter who signed the vote message
+    pub voter: &'a T,
+
+    /// PDA for storing ReputationScore data, or the ReputationShard of the voter
+    #[cons(writable)]
+    pub reputation_state_account: &'a T,
+
+    /// PDA that stores voter's vote, that is derived from votee and vote's keys
+    #[cons(writable)]
+    pub user_vote_state_account: &'a T,
+
+    /// PDA that stores the voter's activity and relay nonce, derived from the voter's key
+    #[cons(writable)]
+    pub voter_profile_account: &'a T,
+
+    /// PDA that stores the parameters of the program, which may not exist yet
+    pub config_account: &'a T,
+
+    /// ReputationScore PDA of the voter, scaling the weight of the vote by the voter's
+    /// credibility when given
+    pub voter_reputation_account: Option<&'a T>,
+
+    /// Stake account associated with the voter
+    pub voter_stake_accounts: &'a [T],
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            instructions_sysvar: next_account_info(accounts_iter)?,
+            rent_payer: next_account_info(accounts_iter)?,
+            voter: next_account_info(accounts_iter)?,
+            reputation_state_account: next_account_info(accounts_iter)?,
+            user_vote_state_account: next_account_info(accounts_iter)?,
+            voter_profile_account: next_account_info(accounts_iter)?,
+            config_account: next_account_info(accounts_iter)?,
+            voter_reputation_account: next_voter_reputation_account(accounts_iter, program_id),
+            voter_stake_accounts: accounts_iter.as_slice(),
+        };
+
+        // Check keys
+        check_account_key(accounts.system_program, &system_program::ID)?;
+        check_account_key(accounts.instructions_sysvar, &sysvar::instructions::ID)?;
+
+        // Check signer
+        check_signer(accounts.rent_payer)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    if Clock::get()?.unix_timestamp > params.expiry {
+        return Err(SnsReputationError::RelayedVoteExpired.into());
+    }
+
+    let message = VoteMessage {
+        program_id: *program_id,
+        voter: *accounts.voter.key,
+        user_key: params.user_key,
+        vote_value: params.vote_value,
+        nonce: params.nonce,
+        expiry: params.expiry,
+    };
+
+    check_ed25519_signature(
+        accounts.instructions_sysvar,
+        accounts.voter.key,
+        &message.to_bytes(),
+    )?;
+
+    cast_vote(
+        program_id,
+        VoteContext {
+            system_program: accounts.system_program,
+            rent_payer: accounts.rent_payer,
+            voter: accounts.voter,
+            reputation_state_account: accounts.reputation_state_account,
+            user_vote_state_account: accounts.user_vote_state_account,
+            voter_profile_account: accounts.voter_profile_account,
+            config_account: accounts.config_account,
+            voter_reputation_account: accounts.voter_reputation_account,
+            voter_stake_accounts: accounts.voter_stake_accounts,
+            followed_curator: Pubkey::default(),
+        },
+        vote::Params {
+            user_key: params.user_key,
+            vote_value: params.vote_value,
+        },
+    )?;
+
+    // The profile exists once the vote has been cast, the nonce is consumed afterwards
+    let mut voter_profile = VoterProfile::from_buffer(
+        &accounts.voter_profile_account.data.borrow(),
+        Tag::VoterProfile,
+    )?;
+
+    voter_profile.consume_relay_nonce(params.nonce)?;
+
+    voter_profile
+        .save(&mut accounts.voter_profile_account.data.borrow_mut())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    Ok(())
+}
@@ -0,0 +1,203 @@
+//! Instruction for revealing a vote previously committed with `CommitVote`.
+//!
+//! The vote has to be revealed during its reveal window, at which point its
+//! stake weight is applied to the votee's ReputationScore, or to the shard of it that the
+//! voter writes to.
+
+use solana_program::{clock::Clock, sysvar::Sysvar};
+
+use crate::error::SnsReputationError;
+use crate::processor::vote::{ScoreAccount, VOTE_VALUE_LOG};
+use crate::state::{user_vote::UserVote, voter_profile::VoterProfile, Tag, VoteValue};
+use crate::utils::{
+    apply_credibility_multiplier, get_credibility_multiplier, get_vote_weight,
+    next_voter_reputation_account,
+};
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_account_owner, check_signer},
+        BorshSize, InstructionsAccount,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// votee account pubkey
+    pub user_key: Pubkey,
+    /// voter's committed vote
+    pub vote_value: VoteValue,
+    /// Salt used to compute the commitment
+    pub salt: [u8; 32],
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    pub system_program: &'a T,
+
+    #[cons(writable, signer)]
+    /// Pays for the accounts created by the instruction, can be the voter itself
+    pub rent_payer: &'a T,
+
+    #[cons(signer)]
+    /// The voter, which can be a PDA signing through CPI
+    pub voter: &'a T,
+
+    /// PDA for storing ReputationScore data, or the ReputationShard of the voter
+    #[cons(writable)]
+    pub reputation_state_account: &'a T,
+
+    /// PDA that stores voter's vote, that is derived from votee and vote's keys
+    #[cons(writable)]
+    pub user_vote_state_account: &'a T,
+
+    /// PDA that stores the voter's activity, derived from the voter's key
+    #[cons(writable)]
+    pub voter_profile_account: &'a T,
+
+    /// ReputationScore PDA of the voter, scaling the weight of the vote by the voter's
+    /// credibility when given
+    pub voter_reputation_account: Option<&'a T>,
+
+    /// Stake account associated with the voter
+    pub voter_stake_accounts: &'a [T],
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            rent_payer: next_account_info(accounts_iter)?,
+            voter: next_account_info(accounts_iter)?,
+            reputation_state_account: next_account_info(accounts_iter)?,
+            user_vote_state_account: next_account_info(accounts_iter)?,
+            voter_profile_account: next_account_info(accounts_iter)?,
+            voter_reputation_account: next_voter_reputation_account(accounts_iter, program_id),
+            voter_stake_accounts: accounts_iter.as_slice(),
+        };
+
+        // Check keys
+        check_account_key(accounts.system_program, &system_program::ID)?;
+
+        // Check owners
+        check_account_owner(accounts.user_vote_state_account, program_id)?;
+        check_account_owner(accounts.voter_profile_account, program_id)?;
+
+        // Check signer
+        check_signer(accounts.rent_payer)?;
+        check_signer(accounts.voter)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    let mut user_vote = UserVote::from_buffer(
+        &accounts.user_vote_state_account.data.borrow(),
+        Tag::UserVote,
+    )?;
+
+    // The nonces stored by the program are cheaper to verify than finding the PDAs again
+    let user_vote_key = UserVote::create_key(
+        program_id,
+        &(params.user_key, *accounts.voter.key),
+        user_vote.nonce,
+    )?;
+
+    check_account_key(accounts.user_vote_state_account, &user_vote_key)?;
+
+    let mut voter_profile = VoterProfile::from_buffer(
+        &accounts.voter_profile_account.data.borrow(),
+        Tag::VoterProfile,
+    )?;
+
+    let voter_profile_key =
+        VoterProfile::create_key(program_id, accounts.voter.key, voter_profile.nonce)?;
+
+    check_account_key(accounts.voter_profile_account, &voter_profile_key)?;
+
+    if !user_vote.is_pending_commitment() {
+        return Err(SnsReputationError::NoCommitmentExists.into());
+    }
+
+    let clock = Clock::get()?;
+
+    if !user_vote.is_in_reveal_window(clock.slot) {
+        return Err(SnsReputationError::OutsideRevealWindow.into());
+    }
+
+    let expected_commitment = UserVote::compute_commitment(
+        &params.user_key,
+        accounts.voter.key,
+        params.vote_value,
+        &params.salt,
+    );
+
+    if params.vote_value == VoteValue::NoVote || expected_commitment != user_vote.commitment {
+        return Err(SnsReputationError::InvalidCommitment.into());
+    }
+
+    let vote_weight = get_vote_weight(
+        accounts.voter.key,
+        accounts.voter_stake_accounts,
+        params.vote_value,
+    )?;
+
+    let mut reputation_score = ScoreAccount::load_or_create(
+        program_id,
+        accounts.system_program,
+        accounts.rent_payer,
+        accounts.reputation_state_account,
+        &params.user_key,
+        accounts.voter.key,
+    )?;
+
+    let credibility_multiplier = get_credibility_multiplier(
+        program_id,
+        accounts.voter.key,
+        accounts.voter_reputation_account,
+    )?;
+    let vote_weight = apply_credibility_multiplier(vote_weight, credibility_multiplier)?;
+
+    let new_vote_value = (params.vote_value as i64)
+        .checked_mul(vote_weight)
+        .ok_or(SnsReputationError::Overflow)?;
+
+    reputation_score.add_vote(new_vote_value)?;
+    reputation_score.save()?;
+
+    voter_profile.record_vote(params.vote_value, true, clock.slot);
+
+    user_vote.value = new_vote_value;
+    user_vote.commitment = [0; 32];
+    user_vote.credibility_multiplier = credibility_multiplier;
+    user_vote.vote_slot = clock.slot;
+    user_vote.reputation_account = *accounts.reputation_state_account.key;
+
+    user_vote
+        .save(&mut accounts.user_vote_state_account.data.borrow_mut())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    voter_profile
+        .save(&mut accounts.voter_profile_account.data.borrow_mut())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    msg!("{}{}", VOTE_VALUE_LOG, new_vote_value);
+
+    Ok(())
+}
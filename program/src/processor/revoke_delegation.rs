@@ -0,0 +1,68 @@
+//! Instruction for revoking a delegation created with `CreateDelegation`.
+//!
+//! The Delegation PDA is closed and its rent returned to the staker.
+
+use crate::state::{delegation::Delegation, Tag};
+use crate::utils::close_account;
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_account_owner, check_signer},
+        BorshSize, InstructionsAccount,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    #[cons(writable, signer)]
+    /// The stake authority, receives the rent back
+    pub staker: &'a T,
+
+    /// PDA that stores the delegation
+    #[cons(writable)]
+    pub delegation_account: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            staker: next_account_info(accounts_iter)?,
+            delegation_account: next_account_info(accounts_iter)?,
+        };
+
+        // Check owners
+        check_account_owner(accounts.delegation_account, program_id)?;
+
+        // Check signer
+        check_signer(accounts.staker)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], _params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    let delegation =
+        Delegation::from_buffer(&accounts.delegation_account.data.borrow(), Tag::Delegation)?;
+
+    check_account_key(accounts.staker, &delegation.staker)?;
+
+    close_account(accounts.delegation_account, accounts.staker);
+
+    Ok(())
+}
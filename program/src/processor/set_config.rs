@@ -0,0 +1,128 @@
+//! Instruction for setting the parameters of the program.
+//!
+//! Only the upgrade authority of the program can call it, which is checked against the
+//! ProgramData account of the program. The Config PDA is created on the first call, until
+//! then the default parameters apply.
+
+use crate::cpi::Cpi;
+use crate::error::SnsReputationError;
+use crate::state::{config::Config, Tag};
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_account_owner, check_signer},
+        BorshSize, InstructionsAccount,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        program_utils::limited_deserialize,
+        pubkey::Pubkey,
+        system_program,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// Maximum number of new votes a voter can cast during a single epoch
+    pub max_new_votes_per_epoch: u64,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    pub system_program: &'a T,
+
+    #[cons(writable, signer)]
+    /// The fee payer account
+    pub fee_payer: &'a T,
+
+    #[cons(signer)]
+    /// The upgrade authority of the program
+    pub authority: &'a T,
+
+    /// The ProgramData account of the program, which stores its upgrade authority
+    pub program_data: &'a T,
+
+    /// PDA that stores the parameters of the program
+    #[cons(writable)]
+    pub config_account: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            fee_payer: next_account_info(accounts_iter)?,
+            authority: next_account_info(accounts_iter)?,
+            program_data: next_account_info(accounts_iter)?,
+            config_account: next_account_info(accounts_iter)?,
+        };
+
+        // Check keys
+        check_account_key(accounts.system_program, &system_program::ID)?;
+        let (program_data_key, _) =
+            Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::ID);
+        check_account_key(accounts.program_data, &program_data_key)?;
+
+        // Check owners
+        check_account_owner(accounts.program_data, &bpf_loader_upgradeable::ID)?;
+
+        // Check signer
+        check_signer(accounts.fee_payer)?;
+        check_signer(accounts.authority)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    let upgrade_authority = match limited_deserialize(
+        &accounts.program_data.data.borrow(),
+        UpgradeableLoaderState::size_of_programdata_metadata() as u64,
+    ) {
+        Ok(UpgradeableLoaderState::ProgramData {
+            upgrade_authority_address,
+            ..
+        }) => upgrade_authority_address,
+        _ => return Err(ProgramError::InvalidAccountData),
+    };
+    if upgrade_authority != Some(*accounts.authority.key) {
+        return Err(SnsReputationError::InvalidAuthority.into());
+    }
+
+    let (config_key, config_nonce) = Config::find_key(program_id);
+
+    check_account_key(accounts.config_account, &config_key)?;
+
+    if accounts.config_account.data_is_empty() {
+        let space = Config::default().borsh_len() + std::mem::size_of::<Tag>();
+        Cpi::create_account(
+            program_id,
+            accounts.system_program,
+            accounts.fee_payer,
+            accounts.config_account,
+            &[Config::SEED, &[config_nonce]],
+            space,
+        )?;
+    }
+
+    let config = Config {
+        nonce: config_nonce,
+        max_new_votes_per_epoch: params.max_new_votes_per_epoch,
+    };
+
+    config
+        .save(&mut accounts.config_account.data.borrow_mut())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    Ok(())
+}
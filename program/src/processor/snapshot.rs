@@ -0,0 +1,171 @@
+//! Permissionless instruction for recording the current tallies of a user, shards
+//! included, in its ScoreHistory.
+//!
+//! All `NUM_SHARDS` shards have to be passed, in order, so that a snapshot can't leave out
+//! some of the votes. Shards that don't exist yet hold no votes.
+
+use solana_program::{clock::Clock, sysvar::Sysvar};
+
+use crate::cpi::Cpi;
+use crate::error::SnsReputationError;
+use crate::state::{
+    reputation_score::ReputationScore,
+    reputation_shard::{ReputationShard, NUM_SHARDS},
+    score_history::ScoreHistory,
+    Tag,
+};
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_account_owner, check_signer},
+        BorshSize, InstructionsAccount,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// The user whose tallies are recorded
+    pub user_key: Pubkey,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    pub system_program: &'a T,
+
+    #[cons(writable)]
+    /// Pays for the ScoreHistory if it doesn't exist yet, in which case it has to sign
+    pub rent_payer: &'a T,
+
+    /// PDA for storing ReputationScore data
+    pub reputation_state_account: &'a T,
+
+    /// PDA storing the snapshots of the tallies, derived from the user's key
+    #[cons(writable)]
+    pub score_history_account: &'a T,
+
+    /// The `NUM_SHARDS` ReputationShard PDAs of the user ordered by index, which may not exist
+    /// yet. Their votes are added to the ReputationScore
+    pub reputation_shard_accounts: &'a [T],
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        _program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            rent_payer: next_account_info(accounts_iter)?,
+            reputation_state_account: next_account_info(accounts_iter)?,
+            score_history_account: next_account_info(accounts_iter)?,
+            reputation_shard_accounts: accounts_iter.as_slice(),
+        };
+
+        // Check keys
+        check_account_key(accounts.system_program, &system_program::ID)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    // Users only voted on through shards have no ReputationScore yet
+    let mut tallies = if accounts.reputation_state_account.data_is_empty() {
+        let (key, nonce) = ReputationScore::find_key(program_id, &params.user_key);
+        check_account_key(accounts.reputation_state_account, &key)?;
+        ReputationScore {
+            nonce,
+            ..ReputationScore::default()
+        }
+    } else {
+        check_account_owner(accounts.reputation_state_account, program_id)?;
+        let score = ReputationScore::from_buffer(
+            &accounts.reputation_state_account.data.borrow(),
+            Tag::ReputationScore,
+        )?;
+        let key = ReputationScore::create_key(program_id, &params.user_key, score.nonce)?;
+        check_account_key(accounts.reputation_state_account, &key)?;
+        score
+    };
+
+    if accounts.reputation_shard_accounts.len() != NUM_SHARDS as usize {
+        return Err(SnsReputationError::InvalidShard.into());
+    }
+
+    // The shards are summed before being applied, so that a shard removing votes that were
+    // already aggregated doesn't underflow the tallies depending on the order
+    let mut pending = ReputationShard::default();
+    for (shard, shard_account) in (0..NUM_SHARDS).zip(accounts.reputation_shard_accounts) {
+        if shard_account.data_is_empty() {
+            let (key, _) = ReputationShard::find_key(program_id, &params.user_key, shard);
+            if *shard_account.key != key {
+                return Err(SnsReputationError::InvalidShard.into());
+            }
+            continue;
+        }
+        check_account_owner(shard_account, program_id)?;
+        let reputation_shard =
+            ReputationShard::from_buffer(&shard_account.data.borrow(), Tag::ReputationShard)?;
+        if reputation_shard.shard != shard {
+            return Err(SnsReputationError::InvalidShard.into());
+        }
+        let key = ReputationShard::create_key(
+            program_id,
+            &params.user_key,
+            shard,
+            reputation_shard.nonce,
+        )?;
+        if *shard_account.key != key {
+            return Err(SnsReputationError::InvalidShard.into());
+        }
+        pending.accumulate(&reputation_shard)?;
+    }
+    pending.aggregate_into(&mut tallies)?;
+
+    if accounts.score_history_account.data_is_empty() {
+        let (key, nonce) = ScoreHistory::find_key(program_id, &params.user_key);
+        check_account_key(accounts.score_history_account, &key)?;
+        check_signer(accounts.rent_payer)?;
+        Cpi::create_account(
+            program_id,
+            accounts.system_program,
+            accounts.rent_payer,
+            accounts.score_history_account,
+            &[ScoreHistory::SEED, params.user_key.as_ref(), &[nonce]],
+            ScoreHistory::LEN,
+        )?;
+        ScoreHistory::init(
+            &mut accounts.score_history_account.data.borrow_mut(),
+            nonce,
+            &params.user_key,
+        )?;
+    } else {
+        check_account_owner(accounts.score_history_account, program_id)?;
+    }
+
+    let mut history_data = accounts.score_history_account.data.borrow_mut();
+    let history = ScoreHistory::load_mut(&mut history_data)?;
+    let key = ScoreHistory::create_key(program_id, &params.user_key, history.nonce)?;
+    check_account_key(accounts.score_history_account, &key)?;
+
+    let clock = Clock::get()?;
+    history.record(
+        clock.epoch,
+        clock.unix_timestamp,
+        tallies.upvote,
+        tallies.downvote,
+    );
+
+    Ok(())
+}
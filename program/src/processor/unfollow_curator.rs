@@ -0,0 +1,68 @@
+//! Instruction for no longer following a curator.
+//!
+//! The Follow PDA is closed and its rent returned to the follower. Votes that were
+//! already propagated are kept, and can be changed or undone with `Vote`.
+
+use crate::state::{follow::Follow, Tag};
+use crate::utils::close_account;
+
+use {
+    bonfida_utils::{
+        checks::{check_account_key, check_account_owner, check_signer},
+        BorshSize, InstructionsAccount,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    #[cons(writable, signer)]
+    /// The follower, receives the rent back
+    pub follower: &'a T,
+
+    /// PDA that stores the followed curator
+    #[cons(writable)]
+    pub follow_account: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            follower: next_account_info(accounts_iter)?,
+            follow_account: next_account_info(accounts_iter)?,
+        };
+
+        // Check owners
+        check_account_owner(accounts.follow_account, program_id)?;
+
+        // Check signer
+        check_signer(accounts.follower)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], _params: Params) -> ProgramResult {
+    let accounts = Accounts::parse(accounts, program_id)?;
+
+    let follow = Follow::from_buffer(&accounts.follow_account.data.borrow(), Tag::Follow)?;
+
+    check_account_key(accounts.follower, &follow.follower)?;
+
+    close_account(accounts.follow_account, accounts.follower);
+
+    Ok(())
+}
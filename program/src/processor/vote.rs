@@ -2,30 +2,45 @@
 //! two separate PDAs:
 //! 1. Reputation score PDA - accumulates all voters' votes over the votee account.
 //! 2. User vote PDA – stores voter's vote.
+//!
+//! The voter's activity is tracked in a third PDA, the voter profile.
 
-use bonfida_utils::checks::check_account_owner;
-use solana_program::msg;
-use solana_program::stake::state::StakeState;
-use solana_program::{program::invoke_signed, rent::Rent, sysvar::Sysvar};
+use solana_program::{clock::Clock, sysvar::Sysvar};
 
+use crate::cpi::Cpi;
 use crate::error::SnsReputationError;
-use crate::state::{reputation_score::ReputationScore, user_vote::UserVote, Tag, VoteValue};
+use crate::state::{
+    reputation_score::{ReputationScore, ReputationScorePod},
+    reputation_shard::ReputationShard,
+    user_vote::{UserVote, UserVotePod},
+    Tag, VoteValue,
+};
+use crate::utils::{
+    apply_credibility_multiplier, check_or_create_reputation_score, close_account,
+    get_credibility_multiplier, get_max_new_votes_per_epoch, get_vote_weight, has_tag,
+    load_or_create_reputation_shard, load_or_create_voter_profile, next_voter_reputation_account,
+};
 
 use {
     bonfida_utils::{
-        checks::{check_account_key, check_signer},
+        checks::{check_account_key, check_account_owner, check_signer},
         BorshSize, InstructionsAccount,
     },
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
         account_info::{next_account_info, AccountInfo},
         entrypoint::ProgramResult,
+        msg,
         program_error::ProgramError,
         pubkey::Pubkey,
         system_program,
     },
 };
 
+/// Prefix of the log giving the weighted value of the vote once cast, 0 when it is undone. The
+/// weight isn't part of the instruction, indexers replaying the transactions read it from there
+pub const VOTE_VALUE_LOG: &str = "Vote value: ";
+
 #[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
 pub struct Params {
     /// votee account pubkey
@@ -38,11 +53,17 @@ pub struct Params {
 pub struct Accounts<'a, T> {
     pub system_program: &'a T,
 
-    #[cons(writable, signer)]
-    /// The fee payer account
+    #[cons(writable)]
+    /// Pays for the accounts created by the instruction, in which case it has to sign. Can be the
+    /// voter itself. When the vote is undone, this is the account that paid for the UserVote
+    pub rent_payer: &'a T,
+
+    #[cons(signer)]
+    /// The voter, which can be a PDA signing through CPI
     pub voter: &'a T,
 
-    /// PDA for storing ReputationScore data
+    /// PDA for storing ReputationScore data, or the ReputationShard of the voter. A vote already
+    /// cast has to be changed in the account recorded in its UserVote
     #[cons(writable)]
     pub reputation_state_account: &'a T,
 
@@ -50,6 +71,17 @@ pub struct Accounts<'a, T> {
     #[cons(writable)]
     pub user_vote_state_account: &'a T,
 
+    /// PDA that stores the voter's activity, derived from the voter's key
+    #[cons(writable)]
+    pub voter_profile_account: &'a T,
+
+    /// PDA that stores the parameters of the program, which may not exist yet
+    pub config_account: &'a T,
+
+    /// ReputationScore PDA of the voter, scaling the weight of the vote by the voter's
+    /// credibility when given
+    pub voter_reputation_account: Option<&'a T>,
+
     /// Stake account associated with the voter
     pub voter_stake_accounts: &'a [T],
 }
@@ -57,14 +89,26 @@ pub struct Accounts<'a, T> {
 impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
     pub fn parse(
         accounts: &'a [AccountInfo<'b>],
-        _program_id: &Pubkey,
+        program_id: &Pubkey,
     ) -> Result<Self, ProgramError> {
         let accounts_iter = &mut accounts.iter();
+        let system_program = next_account_info(accounts_iter)?;
+        let rent_payer = next_account_info(accounts_iter)?;
+        let voter = next_account_info(accounts_iter)?;
+        let reputation_state_account = next_account_info(accounts_iter)?;
+        let user_vote_state_account = next_account_info(accounts_iter)?;
+        let voter_profile_account = next_account_info(accounts_iter)?;
+        let config_account = next_account_info(accounts_iter)?;
+        let voter_reputation_account = next_voter_reputation_account(accounts_iter, program_id);
         let accounts = Accounts {
-            system_program: next_account_info(accounts_iter)?,
-            voter: next_account_info(accounts_iter)?,
-            reputation_state_account: next_account_info(accounts_iter)?,
-            user_vote_state_account: next_account_info(accounts_iter)?,
+            system_program,
+            rent_payer,
+            voter,
+            reputation_state_account,
+            user_vote_state_account,
+            voter_profile_account,
+            config_account,
+            voter_reputation_account,
             voter_stake_accounts: accounts_iter.as_slice(),
         };
 
@@ -78,100 +122,174 @@ impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
     }
 }
 
+/// Accounts involved in casting a vote attributed to `voter`
+pub(crate) struct VoteContext<'a, 'b> {
+    pub system_program: &'a AccountInfo<'b>,
+    /// Pays for the accounts created by the vote, or receives the rent back when the vote is undone
+    pub rent_payer: &'a AccountInfo<'b>,
+    /// The voter to whom the vote is attributed
+    pub voter: &'a AccountInfo<'b>,
+    pub reputation_state_account: &'a AccountInfo<'b>,
+    pub user_vote_state_account: &'a AccountInfo<'b>,
+    pub voter_profile_account: &'a AccountInfo<'b>,
+    pub config_account: &'a AccountInfo<'b>,
+    /// ReputationScore of the voter, if its credibility applies to the vote
+    pub voter_reputation_account: Option<&'a AccountInfo<'b>>,
+    pub voter_stake_accounts: &'a [AccountInfo<'b>],
+    /// Curator from which the vote is propagated, the default key for votes cast by the voter
+    pub followed_curator: Pubkey,
+}
+
 pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: Params) -> ProgramResult {
     let accounts = Accounts::parse(accounts, program_id)?;
 
-    let (reputation_score_key, reputation_score_nonce) =
-        ReputationScore::find_key(program_id, &params.user_key);
+    cast_vote(
+        program_id,
+        VoteContext {
+            system_program: accounts.system_program,
+            rent_payer: accounts.rent_payer,
+            voter: accounts.voter,
+            reputation_state_account: accounts.reputation_state_account,
+            user_vote_state_account: accounts.user_vote_state_account,
+            voter_profile_account: accounts.voter_profile_account,
+            config_account: accounts.config_account,
+            voter_reputation_account: accounts.voter_reputation_account,
+            voter_stake_accounts: accounts.voter_stake_accounts,
+            followed_curator: Pubkey::default(),
+        },
+        params,
+    )
+}
 
-    check_account_key(accounts.reputation_state_account, &reputation_score_key)?;
+/// The account in which a vote is tallied. The ReputationScore is updated in place
+pub(crate) enum ScoreAccount<'a, 'b> {
+    Score(&'a AccountInfo<'b>),
+    Shard(&'a AccountInfo<'b>, ReputationShard),
+}
 
-    if *accounts.voter.key == params.user_key {
-        return Err(SnsReputationError::CannotVoteForYourself.into());
-    }
+impl<'a, 'b> ScoreAccount<'a, 'b> {
+    /// Loads the ReputationScore of `user_key`, or the shard of it that `voter` writes to,
+    /// depending on which one `account` is, creating it if needed
+    pub(crate) fn load_or_create(
+        program_id: &Pubkey,
+        system_program: &'a AccountInfo<'b>,
+        rent_payer: &'a AccountInfo<'b>,
+        account: &'a AccountInfo<'b>,
+        user_key: &Pubkey,
+        voter: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        // Votes can land in a shard of the ReputationScore, relieving write-lock contention
+        let canonical = if account.data_is_empty() {
+            Some(ReputationScore::find_key(program_id, user_key))
+        } else {
+            None
+        };
+        let is_shard = match canonical {
+            Some((reputation_score_key, _)) => *account.key != reputation_score_key,
+            None => has_tag(account, Tag::ReputationShard),
+        };
 
-    // Check that voter is authorized to vote
-    #[cfg(not(feature = "devnet"))]
-    let vote_weight = if params.vote_value != VoteValue::NoVote {
-        if accounts.voter_stake_accounts.is_empty() {
-            return Err(SnsReputationError::MissingStakeAccount.into());
-        }
-        let mut total_stake = 0;
-        for voter_stake_account in accounts.voter_stake_accounts.iter() {
-            check_account_owner(voter_stake_account, &solana_program::stake::program::ID)?;
-            let parsed_stake = solana_program::stake::state::StakeState::deserialize(
-                &mut (&voter_stake_account.data.borrow() as &[u8]),
+        if is_shard {
+            let shard = load_or_create_reputation_shard(
+                program_id,
+                system_program,
+                rent_payer,
+                account,
+                user_key,
+                ReputationShard::shard_index(voter),
             )?;
-            total_stake += if let StakeState::Stake(meta, stake) = parsed_stake {
-                if &meta.authorized.staker != accounts.voter.key {
-                    msg!("The staking account should be owned by the voter");
-                    return Err(SnsReputationError::InvalidStakeAccount.into());
-                }
-                let clock = solana_program::sysvar::clock::Clock::get()?;
-                if clock
-                    .epoch
-                    .checked_sub(stake.delegation.activation_epoch)
-                    .unwrap_or_default()
-                    < 2
-                // At least three days lockup
-                {
-                    msg!("Funds have not been staked for long enough.");
-                    return Err(SnsReputationError::InvalidStakeAccount.into());
-                }
-                stake.delegation.stake as i64
-            } else {
-                return Err(SnsReputationError::InvalidStakeAccount.into());
-            }
-        }
-        total_stake
-    } else {
-        0
-    };
-    #[cfg(feature = "devnet")]
-    let vote_weight = 1;
-
-    let mut reputation_score = if accounts.reputation_state_account.data_is_empty() {
-        let space = ReputationScore::default().borsh_len() + std::mem::size_of::<Tag>();
-        let rent = Rent::get()?;
-        let lamports = rent.minimum_balance(space);
-
-        // Allocate account + set nonce
-        invoke_signed(
-            &solana_program::system_instruction::create_account(
-                accounts.voter.key,
-                accounts.reputation_state_account.key,
-                lamports,
-                space as u64,
+            Ok(ScoreAccount::Shard(account, shard))
+        } else {
+            check_or_create_reputation_score(
                 program_id,
-            ),
-            &[
-                accounts.system_program.clone(),
-                accounts.voter.clone(),
-                accounts.reputation_state_account.clone(),
-            ],
-            &[&[params.user_key.as_ref(), &[reputation_score_nonce]]],
-        )?;
+                system_program,
+                rent_payer,
+                account,
+                user_key,
+                canonical,
+            )?;
+            Ok(ScoreAccount::Score(account))
+        }
+    }
 
-        ReputationScore {
-            nonce: reputation_score_nonce,
-            upvote: 0,
-            downvote: 0,
+    pub(crate) fn add_vote(&mut self, value: i64) -> ProgramResult {
+        match self {
+            ScoreAccount::Score(account) => {
+                ReputationScorePod::load_mut(&mut account.data.borrow_mut())?.add_vote(value)
+            }
+            ScoreAccount::Shard(_, shard) => shard.add_vote(value),
         }
-    } else {
-        ReputationScore::from_buffer(
-            &accounts.reputation_state_account.data.borrow(),
-            Tag::ReputationScore,
-        )?
-    };
+    }
 
-    let (user_vote_key, use_key_nonce) =
-        UserVote::find_key(program_id, &(params.user_key, *accounts.voter.key));
+    pub(crate) fn remove_vote(&mut self, value: i64) -> ProgramResult {
+        match self {
+            ScoreAccount::Score(account) => {
+                ReputationScorePod::load_mut(&mut account.data.borrow_mut())?.remove_vote(value)
+            }
+            ScoreAccount::Shard(_, shard) => shard.remove_vote(value),
+        }
+    }
 
-    check_account_key(accounts.user_vote_state_account, &user_vote_key)?;
+    pub(crate) fn save(&self) -> ProgramResult {
+        match self {
+            ScoreAccount::Score(_) => Ok(()),
+            ScoreAccount::Shard(account, shard) => shard
+                .save(&mut account.data.borrow_mut())
+                .map_err(|_| ProgramError::InvalidAccountData),
+        }
+    }
+}
 
-    let new_vote_value = (params.vote_value as i64).checked_mul(vote_weight).unwrap();
+/// Casts, changes or undoes the vote of `accounts.voter`, once the caller has checked
+/// that the vote is authorized
+pub(crate) fn cast_vote(
+    program_id: &Pubkey,
+    accounts: VoteContext,
+    params: Params,
+) -> ProgramResult {
+    if *accounts.voter.key == params.user_key {
+        return Err(SnsReputationError::CannotVoteForYourself.into());
+    }
 
-    let user_vote = if accounts.user_vote_state_account.data_is_empty() {
+    // Check that voter is authorized to vote
+    let vote_weight = get_vote_weight(
+        accounts.voter.key,
+        accounts.voter_stake_accounts,
+        params.vote_value,
+    )?;
+
+    let mut reputation_score = ScoreAccount::load_or_create(
+        program_id,
+        accounts.system_program,
+        accounts.rent_payer,
+        accounts.reputation_state_account,
+        &params.user_key,
+        accounts.voter.key,
+    )?;
+
+    let mut voter_profile = load_or_create_voter_profile(
+        program_id,
+        accounts.system_program,
+        accounts.rent_payer,
+        accounts.voter_profile_account,
+        accounts.voter.key,
+    )?;
+
+    let clock = Clock::get()?;
+    let current_slot = clock.slot;
+
+    let credibility_multiplier = get_credibility_multiplier(
+        program_id,
+        accounts.voter.key,
+        accounts.voter_reputation_account,
+    )?;
+    let vote_weight = apply_credibility_multiplier(vote_weight, credibility_multiplier)?;
+
+    let new_vote_value = (params.vote_value as i64)
+        .checked_mul(vote_weight)
+        .ok_or(SnsReputationError::Overflow)?;
+
+    if accounts.user_vote_state_account.data_is_empty() {
         // If UserVote PDA is empty, means we're dealing with the initial user's vote
         // Create UserVote PDA and update initial ReputationScore value
 
@@ -182,127 +300,143 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: Params) ->
             return Err(SnsReputationError::NoVoteExists.into());
         }
 
-        let space = UserVote::default().borsh_len() + std::mem::size_of::<Tag>();
-        let rent = Rent::get()?;
-        let lamports = rent.minimum_balance(space);
+        // Slow down scripted brigading by capping the number of new votes per epoch
+        let max_new_votes = get_max_new_votes_per_epoch(program_id, accounts.config_account)?;
+        voter_profile.register_new_vote(clock.epoch, max_new_votes)?;
+
+        let (user_vote_key, user_vote_nonce) =
+            UserVote::find_key(program_id, &(params.user_key, *accounts.voter.key));
+
+        check_account_key(accounts.user_vote_state_account, &user_vote_key)?;
+
+        check_signer(accounts.rent_payer)?;
+
+        let space = UserVote::space();
 
         // Allocate account + set nonce
-        invoke_signed(
-            &solana_program::system_instruction::create_account(
-                accounts.voter.key,
-                accounts.user_vote_state_account.key,
-                lamports,
-                space as u64,
-                program_id,
-            ),
-            &[
-                accounts.system_program.clone(),
-                accounts.voter.clone(),
-                accounts.user_vote_state_account.clone(),
-            ],
+        Cpi::create_account(
+            program_id,
+            accounts.system_program,
+            accounts.rent_payer,
+            accounts.user_vote_state_account,
             // Seeds (votee + voter) to derive PDA
-            &[&[
+            &[
                 params.user_key.as_ref(),
                 accounts.voter.key.as_ref(),
-                &[use_key_nonce],
-            ]],
+                &[user_vote_nonce],
+            ],
+            space,
         )?;
 
-        let vote = UserVote {
+        UserVote {
             value: new_vote_value,
             votee: params.user_key,
             voter: *accounts.voter.key,
-        };
-
-        if params.vote_value == VoteValue::Upvote {
-            reputation_score.upvote = reputation_score
-                .upvote
-                .checked_add(new_vote_value.unsigned_abs())
-                .unwrap();
-        } else if params.vote_value == VoteValue::Downvote {
-            reputation_score.downvote = reputation_score
-                .downvote
-                .checked_add(new_vote_value.unsigned_abs())
-                .unwrap();
+            followed_curator: accounts.followed_curator,
+            rent_payer: *accounts.rent_payer.key,
+            nonce: user_vote_nonce,
+            credibility_multiplier,
+            vote_slot: current_slot,
+            reputation_account: *accounts.reputation_state_account.key,
+            ..UserVote::default()
         }
+        .save(&mut accounts.user_vote_state_account.data.borrow_mut())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+        reputation_score.add_vote(new_vote_value)?;
 
-        vote
+        voter_profile.record_vote(params.vote_value, true, current_slot);
     } else {
-        // Otherwise, derive UserVote value and update the ReputationScore
+        // Otherwise, update the UserVote in place and the ReputationScore
         // value correspondingly
 
-        let mut vote = UserVote::from_buffer(
-            &accounts.user_vote_state_account.data.borrow(),
-            Tag::UserVote,
+        check_account_owner(accounts.user_vote_state_account, program_id)?;
+
+        let mut user_vote_data = accounts.user_vote_state_account.data.borrow_mut();
+
+        // Accounts in a previous layout have to be migrated before being updated in place
+        let vote = UserVotePod::load_mut(&mut user_vote_data)?;
+
+        // The nonce stored by the program is cheaper to verify than finding the PDA again
+        let user_vote_key = UserVote::create_key(
+            program_id,
+            &(params.user_key, *accounts.voter.key),
+            vote.nonce,
         )?;
 
+        check_account_key(accounts.user_vote_state_account, &user_vote_key)?;
+
+        // Committed votes have to be revealed or cleaned up before being changed
+        if vote.is_pending_commitment() {
+            return Err(SnsReputationError::PendingCommitment.into());
+        }
+
+        // The vote is changed in the account it was tallied in, so that the value removed is
+        // the one that was added there
+        if vote.reputation_account != Pubkey::default()
+            && vote.reputation_account != *accounts.reputation_state_account.key
+        {
+            return Err(SnsReputationError::WrongReputationAccount.into());
+        }
+
+        // Votes cast by the voter take precedence over the ones propagated from a curator
+        if accounts.followed_curator != Pubkey::default() && !vote.is_followed() {
+            return Err(SnsReputationError::FollowerOverride.into());
+        }
+
+        let previous_value = i64::from(vote.value);
+
         // Return an error if user voted with the same value
-        if (vote.value.signum()) == ((params.vote_value as i64).signum()) {
-            return Err(SnsReputationError::AlreadyVoted.into());
+        if (previous_value.signum()) == ((params.vote_value as i64).signum()) {
+            if vote.followed_curator == accounts.followed_curator {
+                return Err(SnsReputationError::AlreadyVoted.into());
+            }
+            // The voter takes over a propagated vote, or a new curator takes over the
+            // vote propagated from a previous one, without changing its value
+            vote.followed_curator = accounts.followed_curator;
+            msg!("{}{}", VOTE_VALUE_LOG, previous_value);
+            return Ok(());
         }
 
+        reputation_score.remove_vote(previous_value)?;
+
         // If user voted with VoteValue::NoVote, it means that the user wants to undo their previous vote
         if params.vote_value == VoteValue::NoVote {
-            let lamports = **accounts.user_vote_state_account.lamports.borrow_mut();
-            **accounts.user_vote_state_account.lamports.borrow_mut() = 0;
-            **accounts.voter.lamports.borrow_mut() += lamports;
-
-            if vote.value.signum() == (VoteValue::Upvote as i64).signum() {
-                reputation_score.upvote = reputation_score
-                    .upvote
-                    .checked_sub(vote.value.unsigned_abs())
-                    .unwrap();
-            } else if vote.value.signum() == (VoteValue::Downvote as i64).signum() {
-                reputation_score.downvote = reputation_score
-                    .downvote
-                    .checked_sub(vote.value.unsigned_abs())
-                    .unwrap();
-            }
+            // The rent goes back to whoever paid for the UserVote
+            check_account_key(accounts.rent_payer, vote.rent_destination())?;
+            drop(user_vote_data);
+            close_account(accounts.user_vote_state_account, accounts.rent_payer);
+
+            voter_profile.record_undo(current_slot);
 
-            reputation_score
-                .save(&mut accounts.reputation_state_account.data.borrow_mut())
+            reputation_score.save()?;
+
+            voter_profile
+                .save(&mut accounts.voter_profile_account.data.borrow_mut())
                 .map_err(|_| ProgramError::InvalidAccountData)?;
 
+            msg!("{}{}", VOTE_VALUE_LOG, 0);
             return Ok(());
         }
 
         // The user has changed their vote
-        match params.vote_value {
-            VoteValue::Upvote => {
-                reputation_score.upvote = reputation_score
-                    .upvote
-                    .checked_add(new_vote_value.unsigned_abs())
-                    .unwrap();
-                reputation_score.downvote = reputation_score
-                    .downvote
-                    .checked_sub(vote.value.unsigned_abs())
-                    .unwrap();
-            }
-            VoteValue::Downvote => {
-                reputation_score.downvote = reputation_score
-                    .downvote
-                    .checked_add(new_vote_value.unsigned_abs())
-                    .unwrap();
-                reputation_score.upvote = reputation_score
-                    .upvote
-                    .checked_sub(vote.value.unsigned_abs())
-                    .unwrap();
-            }
-            _ => {}
-        }
+        vote.value = new_vote_value.into();
+        vote.followed_curator = accounts.followed_curator;
+        vote.credibility_multiplier = credibility_multiplier.into();
+        vote.vote_slot = current_slot.into();
+        vote.reputation_account = *accounts.reputation_state_account.key;
 
-        vote.value = new_vote_value;
+        reputation_score.add_vote(new_vote_value)?;
 
-        vote
-    };
+        voter_profile.record_vote(params.vote_value, false, current_slot);
+    }
 
-    user_vote
-        .save(&mut accounts.user_vote_state_account.data.borrow_mut())
-        .map_err(|_| ProgramError::InvalidAccountData)?;
+    reputation_score.save()?;
 
-    reputation_score
-        .save(&mut accounts.reputation_state_account.data.borrow_mut())
+    voter_profile
+        .save(&mut accounts.voter_profile_account.data.borrow_mut())
         .map_err(|_| ProgramError::InvalidAccountData)?;
 
+    msg!("{}{}", VOTE_VALUE_LOG, new_vote_value);
     Ok(())
 }
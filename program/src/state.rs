@@ -3,8 +3,15 @@ use {
     borsh::{BorshDeserialize, BorshSerialize},
 };
 
+pub mod config;
+pub mod delegation;
+pub mod follow;
+pub mod pod;
 pub mod reputation_score;
+pub mod reputation_shard;
+pub mod score_history;
 pub mod user_vote;
+pub mod voter_profile;
 
 #[derive(BorshSerialize, BorshDeserialize, BorshSize, PartialEq)]
 #[repr(u64)]
@@ -13,8 +20,17 @@ pub enum Tag {
     Uninitialized,
     ReputationScore,
     UserVote,
+    VoterProfile,
+    Delegation,
+    Follow,
+    ReputationShard,
+    ScoreHistory,
+    Config,
 }
 
+/// Size of the header of versioned accounts: the Tag followed by the version of the layout
+pub const VERSIONED_HEADER_LEN: usize = std::mem::size_of::<Tag>() + 1;
+
 #[derive(BorshSerialize, BorshDeserialize, BorshSize, PartialEq, Debug, Clone, Copy, Default)]
 // Borsh only works with u8 in enums
 #[repr(i64)]
@@ -39,7 +55,7 @@ pub async fn test_stake_parsing() {
     let stake =
         solana_program::stake::state::StakeState::deserialize(&mut (&account.data as &[u8]))
             .unwrap();
-    if let StakeState::Stake(m, s) = stake {
+    if let StakeState::Stake(m, _) = stake {
         assert_eq!(m.authorized.staker, stake_authority);
     } else {
         panic!()
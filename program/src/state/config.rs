@@ -0,0 +1,59 @@
+use bonfida_utils::BorshSize;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{error::SnsReputationError, utils::derive_key};
+
+use super::Tag;
+
+/// Number of new votes a voter can cast during a single epoch until the program is configured
+pub const DEFAULT_MAX_NEW_VOTES_PER_EPOCH: u64 = 100;
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, BorshSize)]
+#[allow(missing_docs)]
+#[repr(C)]
+pub struct Config {
+    /// Nonce
+    pub nonce: u8,
+    /// Maximum number of new votes a voter can cast during a single epoch
+    pub max_new_votes_per_epoch: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            nonce: 0,
+            max_new_votes_per_epoch: DEFAULT_MAX_NEW_VOTES_PER_EPOCH,
+        }
+    }
+}
+
+#[allow(missing_docs)]
+impl Config {
+    pub const SEED: &'static [u8] = b"config";
+
+    pub fn from_buffer(buffer: &[u8], expected_tag: super::Tag) -> Result<Self, ProgramError> {
+        let (tag, mut buffer) = buffer.split_at(8);
+        if *bytemuck::from_bytes::<u64>(tag) != expected_tag as u64 {
+            return Err(SnsReputationError::DataTypeMismatch.into());
+        }
+        Ok(Self::deserialize(&mut buffer)?)
+    }
+
+    pub fn find_key(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED], program_id)
+    }
+
+    /// Derives the key from a known `nonce`, which is much cheaper than `find_key`
+    pub fn create_key(program_id: &Pubkey, nonce: u8) -> Result<Pubkey, ProgramError> {
+        derive_key(&[Self::SEED, &[nonce]], program_id)
+    }
+
+    pub fn save(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        // Skip first 8 bytes and store other data after them
+        self.serialize(&mut (&mut dst[8..]))?;
+        // First Tag data in the first 8 bytes (u64 size) to represent what kind of data stored in next bytes
+        (Tag::Config as u64).serialize(&mut (&mut dst[..]))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use bonfida_utils::BorshSize;
+use borsh::{BorshDeserialize, BorshSerialize};
+use enumflags2::{bitflags, BitFlags};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{error::SnsReputationError, utils::derive_key};
+
+use super::{Tag, VoteValue};
+
+#[bitflags]
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[allow(missing_docs)]
+pub enum DelegationPermission {
+    Upvote = 1 << 0,
+    Downvote = 1 << 1,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, BorshSize, Default)]
+#[allow(missing_docs)]
+#[repr(C)]
+pub struct Delegation {
+    /// Nonce
+    pub nonce: u8,
+    /// Stake authority on whose behalf the delegate votes
+    pub staker: Pubkey,
+    /// Key allowed to vote on behalf of the staker
+    pub delegate: Pubkey,
+    /// Unix timestamp after which the delegation can no longer be used, 0 if it never expires
+    pub expiry: i64,
+    /// Bit flags of `DelegationPermission`
+    pub permissions: u8,
+}
+
+#[allow(missing_docs)]
+impl Delegation {
+    pub const SEED: &'static [u8] = b"delegation";
+
+    pub fn from_buffer(buffer: &[u8], expected_tag: super::Tag) -> Result<Self, ProgramError> {
+        let (tag, mut buffer) = buffer.split_at(8);
+        if *bytemuck::from_bytes::<u64>(tag) != expected_tag as u64 {
+            return Err(SnsReputationError::DataTypeMismatch.into());
+        }
+        Ok(Self::deserialize(&mut buffer)?)
+    }
+
+    pub fn find_key(program_id: &Pubkey, staker: &Pubkey, delegate: &Pubkey) -> (Pubkey, u8) {
+        let seeds: &[&[u8]] = &[Self::SEED, staker.as_ref(), delegate.as_ref()];
+        Pubkey::find_program_address(seeds, program_id)
+    }
+
+    /// Derives the key from a known `nonce`, which is much cheaper than `find_key`
+    pub fn create_key(
+        program_id: &Pubkey,
+        staker: &Pubkey,
+        delegate: &Pubkey,
+        nonce: u8,
+    ) -> Result<Pubkey, ProgramError> {
+        let seeds: &[&[u8]] = &[Self::SEED, staker.as_ref(), delegate.as_ref(), &[nonce]];
+        derive_key(seeds, program_id)
+    }
+
+    pub fn permissions(&self) -> BitFlags<DelegationPermission> {
+        BitFlags::from_bits_truncate(self.permissions)
+    }
+
+    /// Returns true if the delegation has expired at `unix_timestamp`
+    pub fn is_expired(&self, unix_timestamp: i64) -> bool {
+        self.expiry != 0 && unix_timestamp >= self.expiry
+    }
+
+    /// Returns true if the delegate is allowed to replace a vote of `previous_value`, 0 if there
+    /// is none, with a `vote_value` vote. Removing an existing vote, to undo or change it,
+    /// requires the permission matching its sign.
+    pub fn allows(&self, previous_value: i64, vote_value: VoteValue) -> bool {
+        let permissions = self.permissions();
+        [previous_value, vote_value as i64]
+            .iter()
+            .all(|value| match value.signum() {
+                1 => permissions.contains(DelegationPermission::Upvote),
+                -1 => permissions.contains(DelegationPermission::Downvote),
+                _ => true,
+            })
+    }
+
+    pub fn save(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        // Skip first 8 bytes and store other data after them
+        self.serialize(&mut (&mut dst[8..]))?;
+        // First Tag data in the first 8 bytes (u64 size) to represent what kind of data stored in next bytes
+        (Tag::Delegation as u64).serialize(&mut (&mut dst[..]))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_allows() {
+    let delegation = Delegation {
+        permissions: DelegationPermission::Upvote as u8,
+        ..Delegation::default()
+    };
+    assert!(delegation.allows(0, VoteValue::Upvote));
+    assert!(!delegation.allows(0, VoteValue::Downvote));
+    // Only the votes the delegate could have cast can be undone
+    assert!(delegation.allows(3, VoteValue::NoVote));
+    assert!(!delegation.allows(-3, VoteValue::NoVote));
+    // Changing a vote removes it as well
+    assert!(!delegation.allows(-3, VoteValue::Upvote));
+}
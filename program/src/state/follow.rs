@@ -0,0 +1,55 @@
+use bonfida_utils::BorshSize;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{error::SnsReputationError, utils::derive_key};
+
+use super::Tag;
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, BorshSize, Default)]
+#[allow(missing_docs)]
+#[repr(C)]
+pub struct Follow {
+    /// Nonce
+    pub nonce: u8,
+    /// Voter whose stake weight follows the curator
+    pub follower: Pubkey,
+    /// Curator whose votes are propagated to the follower
+    pub curator: Pubkey,
+}
+
+#[allow(missing_docs)]
+impl Follow {
+    pub const SEED: &'static [u8] = b"follow";
+
+    pub fn from_buffer(buffer: &[u8], expected_tag: super::Tag) -> Result<Self, ProgramError> {
+        let (tag, mut buffer) = buffer.split_at(8);
+        if *bytemuck::from_bytes::<u64>(tag) != expected_tag as u64 {
+            return Err(SnsReputationError::DataTypeMismatch.into());
+        }
+        Ok(Self::deserialize(&mut buffer)?)
+    }
+
+    pub fn find_key(program_id: &Pubkey, follower: &Pubkey) -> (Pubkey, u8) {
+        let seeds: &[&[u8]] = &[Self::SEED, follower.as_ref()];
+        Pubkey::find_program_address(seeds, program_id)
+    }
+
+    /// Derives the key from a known `nonce`, which is much cheaper than `find_key`
+    pub fn create_key(
+        program_id: &Pubkey,
+        follower: &Pubkey,
+        nonce: u8,
+    ) -> Result<Pubkey, ProgramError> {
+        let seeds: &[&[u8]] = &[Self::SEED, follower.as_ref(), &[nonce]];
+        derive_key(seeds, program_id)
+    }
+
+    pub fn save(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        // Skip first 8 bytes and store other data after them
+        self.serialize(&mut (&mut dst[8..]))?;
+        // First Tag data in the first 8 bytes (u64 size) to represent what kind of data stored in next bytes
+        (Tag::Follow as u64).serialize(&mut (&mut dst[..]))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+//! Unaligned integers for zero-copy account layouts.
+//!
+//! Borsh packs fields without padding, so the zero-copy views of the accounts
+//! store integers as little-endian byte arrays to keep the exact same layout.
+
+use bytemuck::{Pod, Zeroable};
+
+/// A `u64` with an alignment of 1
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+#[repr(transparent)]
+pub struct PodU64(pub [u8; 8]);
+
+impl From<u64> for PodU64 {
+    fn from(value: u64) -> Self {
+        Self(value.to_le_bytes())
+    }
+}
+
+impl From<PodU64> for u64 {
+    fn from(value: PodU64) -> Self {
+        u64::from_le_bytes(value.0)
+    }
+}
+
+/// An `i64` with an alignment of 1
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+#[repr(transparent)]
+pub struct PodI64(pub [u8; 8]);
+
+impl From<i64> for PodI64 {
+    fn from(value: i64) -> Self {
+        Self(value.to_le_bytes())
+    }
+}
+
+impl From<PodI64> for i64 {
+    fn from(value: PodI64) -> Self {
+        i64::from_le_bytes(value.0)
+    }
+}
@@ -1,10 +1,11 @@
 use bonfida_utils::BorshSize;
 use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
 use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
-use crate::error::SnsReputationError;
+use crate::{error::SnsReputationError, utils::derive_key};
 
-use super::Tag;
+use super::{pod::PodU64, Tag, VERSIONED_HEADER_LEN};
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, BorshSize, Default)]
 #[allow(missing_docs)]
@@ -16,16 +17,95 @@ pub struct ReputationScore {
     pub upvote: u64,
     /// Downvotes amount
     pub downvote: u64,
+    /// Number of votes counted in `upvote`
+    pub upvoters: u64,
+    /// Number of votes counted in `downvote`
+    pub downvoters: u64,
+}
+
+/// Fields of the layouts preceding version 2, which didn't count the voters
+#[derive(BorshDeserialize)]
+struct ReputationScoreV1 {
+    nonce: u8,
+    upvote: u64,
+    downvote: u64,
+}
+
+/// Scale of the normalized scores, which range from 0 to 100.00
+pub const SCORE_SCALE: u64 = 10_000;
+
+/// Scale of the `z` parameter of `ReputationScore::wilson_score`
+pub const Z_SCALE: u64 = 10_000;
+
+/// Quantile of the standard normal distribution for a 95% confidence level, scaled by `Z_SCALE`
+pub const WILSON_Z_95: u64 = 19_600;
+
+/// Largest `z` accepted by `ReputationScore::wilson_score`, larger values are capped
+pub const WILSON_MAX_Z: u64 = 10 * Z_SCALE;
+
+/// Scale of the credibility multipliers, a multiplier of `CREDIBILITY_SCALE` leaving the weight
+/// of a vote unchanged
+pub const CREDIBILITY_SCALE: u64 = 10_000;
+
+/// Largest credibility multiplier, reached by voters whose Wilson score is 100
+pub const MAX_CREDIBILITY_MULTIPLIER: u64 = CREDIBILITY_SCALE + SCORE_SCALE / 2;
+
+/// Prior of `ReputationScore::bayesian_score`: the score of a user without any vote, and the
+/// amount of votes it is worth
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BayesianPrior {
+    /// Score between 0 and `SCORE_SCALE`
+    pub mean: u64,
+    /// Weight of the prior, in the same unit as the tallies
+    pub weight: u64,
 }
 
 #[allow(missing_docs)]
 impl ReputationScore {
+    /// Version of the layout written by the program, stored after the Tag
+    pub const VERSION: u8 = 2;
+    /// Size of the accounts created before the layout was versioned, which have no version byte
+    pub const UNVERSIONED_LEN: usize = 8 + 1 + 8 + 8;
+
+    /// Size of an account using the latest layout
+    pub fn space() -> usize {
+        Self::default().borsh_len() + VERSIONED_HEADER_LEN
+    }
+
+    /// Reads an account in any of the layouts used since the program was deployed
     pub fn from_buffer(buffer: &[u8], expected_tag: super::Tag) -> Result<Self, ProgramError> {
-        let (tag, mut buffer) = buffer.split_at(8);
+        let is_unversioned = buffer.len() == Self::UNVERSIONED_LEN;
+        let (tag, buffer) = buffer.split_at(8);
         if *bytemuck::from_bytes::<u64>(tag) != expected_tag as u64 {
             return Err(SnsReputationError::DataTypeMismatch.into());
         }
-        Ok(Self::deserialize(&mut buffer)?)
+        let (version, mut buffer) = if is_unversioned {
+            (0, buffer)
+        } else {
+            let (version, buffer) = buffer
+                .split_first()
+                .ok_or(ProgramError::InvalidAccountData)?;
+            (*version, buffer)
+        };
+        match version {
+            // Votes cast before the voters were counted are left out of the counts
+            0 | 1 => {
+                let v1 = ReputationScoreV1::deserialize(&mut buffer)?;
+                Ok(Self {
+                    nonce: v1.nonce,
+                    upvote: v1.upvote,
+                    downvote: v1.downvote,
+                    ..Self::default()
+                })
+            }
+            Self::VERSION => Ok(Self::deserialize(&mut buffer)?),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    /// Returns true if the account uses the latest layout
+    pub fn is_latest_layout(buffer: &[u8]) -> bool {
+        buffer.len() == Self::space() && buffer[8] == Self::VERSION
     }
 
     pub fn find_key(program_id: &Pubkey, user_address: &Pubkey) -> (Pubkey, u8) {
@@ -33,12 +113,354 @@ impl ReputationScore {
         Pubkey::find_program_address(seeds, program_id)
     }
 
-    // Stores data
+    /// Derives the key from a known `nonce`, which is much cheaper than `find_key`
+    pub fn create_key(
+        program_id: &Pubkey,
+        user_address: &Pubkey,
+        nonce: u8,
+    ) -> Result<Pubkey, ProgramError> {
+        let seeds: &[&[u8]] = &[user_address.as_ref(), &[nonce]];
+        derive_key(seeds, program_id)
+    }
+
+    /// Returns the tally and the voter count selected by the sign of `value`, if any
+    fn tally_mut(&mut self, value: i64) -> Option<(&mut u64, &mut u64)> {
+        match value.signum() {
+            1 => Some((&mut self.upvote, &mut self.upvoters)),
+            -1 => Some((&mut self.downvote, &mut self.downvoters)),
+            _ => None,
+        }
+    }
+
+    /// Adds a weighted vote to the tallies, the sign of `value` selecting the tally
+    pub fn add_vote(&mut self, value: i64) -> Result<(), ProgramError> {
+        if let Some((tally, voters)) = self.tally_mut(value) {
+            let new_tally = tally
+                .checked_add(value.unsigned_abs())
+                .ok_or(SnsReputationError::Overflow)?;
+            *voters = voters.checked_add(1).ok_or(SnsReputationError::Overflow)?;
+            *tally = new_tally;
+        }
+        Ok(())
+    }
+
+    /// Removes a weighted vote previously added with `add_vote`
+    pub fn remove_vote(&mut self, value: i64) -> Result<(), ProgramError> {
+        if let Some((tally, voters)) = self.tally_mut(value) {
+            *tally = tally
+                .checked_sub(value.unsigned_abs())
+                .ok_or(SnsReputationError::ScoreUnderflow)?;
+            // Votes cast before the layout counted the voters were never added to the counts
+            *voters = voters.saturating_sub(1);
+        }
+        Ok(())
+    }
+
+    /// Lower bound of the Wilson score interval of the share of upvoters, between 0 and
+    /// `SCORE_SCALE`. Unlike the tallies it doesn't depend on the stake of the voters, and few
+    /// votes give a low score however positive they are. `z` is the quantile of the confidence
+    /// level scaled by `Z_SCALE`, `WILSON_Z_95` being the usual choice
+    pub fn wilson_score(&self, z: u64) -> u64 {
+        wilson_lower_bound(self.upvoters, self.downvoters, z)
+    }
+
+    /// Share of upvotes in the tallies, between 0 and `SCORE_SCALE`, pulled toward the mean of
+    /// the prior by as many votes as its weight
+    pub fn bayesian_score(&self, prior: BayesianPrior) -> u64 {
+        bayesian_average(self.upvote, self.downvote, prior)
+    }
+
+    /// Multiplier applied to the weight of the votes of this user, scaled by
+    /// `CREDIBILITY_SCALE`. It grows with the Wilson score above 50 and is capped at
+    /// `MAX_CREDIBILITY_MULTIPLIER`, so that voters upvoting each other can't amplify their
+    /// weight without bound. It never goes below `CREDIBILITY_SCALE`, since voters could
+    /// otherwise escape it by not providing their ReputationScore
+    pub fn credibility_multiplier(&self) -> u64 {
+        let excess = self
+            .wilson_score(WILSON_Z_95)
+            .saturating_sub(SCORE_SCALE / 2);
+        CREDIBILITY_SCALE
+            + excess * (MAX_CREDIBILITY_MULTIPLIER - CREDIBILITY_SCALE) / (SCORE_SCALE / 2)
+    }
+
+    // Stores data, accounts in a previous layout have to be migrated first
     pub fn save(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
-        // Skip first 8 bytes and store other data after them
-        self.serialize(&mut (&mut dst[8..]))?;
+        if dst.len() < Self::space() {
+            return Err(SnsReputationError::OutdatedAccountLayout.into());
+        }
+        // Skip the header and store other data after it
+        self.serialize(&mut (&mut dst[VERSIONED_HEADER_LEN..]))?;
         // First Tag data in the first 8 bytes (u64 size) to represent what kind of data stored in next bytes
         (Tag::ReputationScore as u64).serialize(&mut (&mut dst[..]))?;
+        // Followed by the version of the layout
+        dst[8] = Self::VERSION;
+        Ok(())
+    }
+}
+
+/// Zero-copy view of a ReputationScore account, header included, with the same layout as the
+/// Borsh encoding. Only accounts using the latest layout can be viewed
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[allow(missing_docs)]
+#[repr(C)]
+pub struct ReputationScorePod {
+    pub tag: PodU64,
+    pub version: u8,
+    pub nonce: u8,
+    pub upvote: PodU64,
+    pub downvote: PodU64,
+    pub upvoters: PodU64,
+    pub downvoters: PodU64,
+}
+
+#[allow(missing_docs)]
+impl ReputationScorePod {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    fn check_header(buffer: &[u8]) -> Result<(), ProgramError> {
+        if buffer.get(..8) != Some(&(Tag::ReputationScore as u64).to_le_bytes()[..]) {
+            return Err(SnsReputationError::DataTypeMismatch.into());
+        }
+        if !ReputationScore::is_latest_layout(buffer) {
+            return Err(SnsReputationError::OutdatedAccountLayout.into());
+        }
         Ok(())
     }
+
+    pub fn load(buffer: &[u8]) -> Result<&Self, ProgramError> {
+        Self::check_header(buffer)?;
+        Ok(bytemuck::from_bytes(buffer))
+    }
+
+    pub fn load_mut(buffer: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        Self::check_header(buffer)?;
+        Ok(bytemuck::from_bytes_mut(buffer))
+    }
+
+    /// Adds a weighted vote to the tallies, see `ReputationScore::add_vote`
+    pub fn add_vote(&mut self, value: i64) -> Result<(), ProgramError> {
+        let mut score = ReputationScore::from(&*self);
+        score.add_vote(value)?;
+        self.set_tallies(&score);
+        Ok(())
+    }
+
+    /// Removes a weighted vote, see `ReputationScore::remove_vote`
+    pub fn remove_vote(&mut self, value: i64) -> Result<(), ProgramError> {
+        let mut score = ReputationScore::from(&*self);
+        score.remove_vote(value)?;
+        self.set_tallies(&score);
+        Ok(())
+    }
+
+    fn set_tallies(&mut self, score: &ReputationScore) {
+        self.upvote = score.upvote.into();
+        self.downvote = score.downvote.into();
+        self.upvoters = score.upvoters.into();
+        self.downvoters = score.downvoters.into();
+    }
+}
+
+impl From<&ReputationScorePod> for ReputationScore {
+    fn from(pod: &ReputationScorePod) -> Self {
+        Self {
+            nonce: pod.nonce,
+            upvote: pod.upvote.into(),
+            downvote: pod.downvote.into(),
+            upvoters: pod.upvoters.into(),
+            downvoters: pod.downvoters.into(),
+        }
+    }
+}
+
+/// Integer square root, rounded down
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    // Newton's method from an initial guess above the root decreases monotonically to it
+    let mut x = 1u128 << ((129 - n.leading_zeros()) / 2);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+/// See `ReputationScore::wilson_score`. With `n` votes of which `u` are upvotes, the bound is
+/// `(u + z²/2 - z * sqrt(u * (n - u) / n + z²/4)) / (n + z²)`, every term being scaled by
+/// `Z_SCALE²` to stay in integers. Results are rounded down, clients replicate the computation
+/// exactly
+pub fn wilson_lower_bound(upvoters: u64, downvoters: u64, z: u64) -> u64 {
+    let (u, d) = (upvoters as u128, downvoters as u128);
+    let n = u + d;
+    if n == 0 {
+        return 0;
+    }
+    let (s2, z) = ((Z_SCALE as u128).pow(2), z.min(WILSON_MAX_Z) as u128);
+    let z2 = z * z;
+    // u * d * s2 / n, split so that the product can't overflow
+    let variance = u * d / n * s2 + u * d % n * s2 / n;
+    let spread = isqrt(variance + z2 / 4);
+    let numerator = (u * s2 + z2 / 2).saturating_sub(z * spread);
+    let denominator = n * s2 + z2;
+    (numerator * SCORE_SCALE as u128 / denominator) as u64
+}
+
+/// See `ReputationScore::bayesian_score`, the result is rounded down
+pub fn bayesian_average(upvote: u64, downvote: u64, prior: BayesianPrior) -> u64 {
+    let mean = prior.mean.min(SCORE_SCALE) as u128;
+    let denominator = prior.weight as u128 + upvote as u128 + downvote as u128;
+    if denominator == 0 {
+        return mean as u64;
+    }
+    let numerator = mean * prior.weight as u128 + SCORE_SCALE as u128 * upvote as u128;
+    (numerator / denominator) as u64
+}
+
+#[cfg(test)]
+#[test]
+fn test_pod_layout() {
+    let score = ReputationScore {
+        nonce: 254,
+        upvote: 1 << 40,
+        downvote: 3,
+        upvoters: 2,
+        downvoters: 1,
+    };
+    let mut buffer = vec![0; ReputationScorePod::LEN];
+    score.save(&mut buffer).unwrap();
+    assert_eq!(ReputationScore::space(), ReputationScorePod::LEN);
+
+    let pod = ReputationScorePod::load_mut(&mut buffer).unwrap();
+    assert_eq!(ReputationScore::from(&*pod), score);
+    pod.add_vote(-2).unwrap();
+
+    let score = ReputationScore::from_buffer(&buffer, Tag::ReputationScore).unwrap();
+    assert_eq!((score.downvote, score.downvoters), (5, 2));
+}
+
+#[cfg(test)]
+#[test]
+fn test_previous_layouts() {
+    let legacy = (254u8, 7u64, 3u64);
+    let expected = ReputationScore {
+        nonce: 254,
+        upvote: 7,
+        downvote: 3,
+        ..ReputationScore::default()
+    };
+
+    let mut unversioned = (Tag::ReputationScore as u64).to_le_bytes().to_vec();
+    legacy.serialize(&mut unversioned).unwrap();
+    assert_eq!(unversioned.len(), ReputationScore::UNVERSIONED_LEN);
+
+    let mut v1 = (Tag::ReputationScore as u64).to_le_bytes().to_vec();
+    v1.push(1);
+    legacy.serialize(&mut v1).unwrap();
+
+    for mut buffer in [unversioned, v1] {
+        assert_eq!(
+            ReputationScore::from_buffer(&buffer, Tag::ReputationScore).unwrap(),
+            expected
+        );
+        assert!(!ReputationScore::is_latest_layout(&buffer));
+        assert!(ReputationScorePod::load(&buffer).is_err());
+        assert!(expected.save(&mut buffer).is_err());
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_tally_bounds() {
+    let mut score = ReputationScore {
+        nonce: 0,
+        upvote: u64::MAX,
+        downvote: 1,
+        upvoters: 1,
+        downvoters: 1,
+    };
+    assert_eq!(score.add_vote(1), Err(SnsReputationError::Overflow.into()));
+    assert_eq!(
+        score.remove_vote(-2),
+        Err(SnsReputationError::ScoreUnderflow.into())
+    );
+    // Failed updates leave the tallies untouched
+    assert_eq!((score.upvote, score.downvote), (u64::MAX, 1));
+    assert_eq!((score.upvoters, score.downvoters), (1, 1));
+    score.remove_vote(-1).unwrap();
+    assert_eq!((score.downvote, score.downvoters), (0, 0));
+    // Votes cast before the voters were counted leave the counts at zero
+    score.downvote = 1;
+    score.remove_vote(-1).unwrap();
+    assert_eq!(score.downvoters, 0);
+}
+
+/// Values shared with the tests of the JS bindings, which must compute the exact same scores
+#[cfg(test)]
+#[test]
+fn test_normalized_scores() {
+    let wilson = |upvoters, downvoters| {
+        ReputationScore {
+            upvoters,
+            downvoters,
+            ..ReputationScore::default()
+        }
+        .wilson_score(WILSON_Z_95)
+    };
+    assert_eq!(wilson(0, 0), 0);
+    assert_eq!(wilson(0, 1), 0);
+    assert_eq!(wilson(1, 0), 2065);
+    assert_eq!(wilson(1, 1), 945);
+    assert_eq!(wilson(8, 2), 4901);
+    assert_eq!(wilson(80, 20), 7111);
+    assert_eq!(wilson(800, 200), 7740);
+    assert_eq!(wilson(100, 0), 9630);
+    assert_eq!(wilson(u64::MAX, u64::MAX), 4999);
+    assert_eq!(wilson_lower_bound(80, 20, 0), 8000);
+
+    let bayesian = |upvote, downvote, mean, weight| {
+        ReputationScore {
+            upvote,
+            downvote,
+            ..ReputationScore::default()
+        }
+        .bayesian_score(BayesianPrior { mean, weight })
+    };
+    assert_eq!(bayesian(0, 0, 5000, 100), 5000);
+    assert_eq!(bayesian(0, 0, 0, 0), 0);
+    assert_eq!(bayesian(100, 0, 5000, 100), 7500);
+    assert_eq!(bayesian(300, 100, 5000, 1000), 5714);
+    assert_eq!(bayesian(u64::MAX, 0, 0, 0), SCORE_SCALE);
+}
+
+#[cfg(test)]
+#[test]
+fn test_credibility_multiplier() {
+    let multiplier = |upvoters, downvoters| {
+        ReputationScore {
+            upvoters,
+            downvoters,
+            ..ReputationScore::default()
+        }
+        .credibility_multiplier()
+    };
+    assert_eq!(multiplier(0, 0), CREDIBILITY_SCALE);
+    // Too few votes to be trusted
+    assert_eq!(multiplier(3, 0), CREDIBILITY_SCALE);
+    assert_eq!(multiplier(20, 80), CREDIBILITY_SCALE);
+    assert_eq!(multiplier(80, 20), 12_111);
+    assert_eq!(multiplier(u64::MAX, 0), 14_999);
+    assert!(multiplier(u64::MAX, 0) <= MAX_CREDIBILITY_MULTIPLIER);
+}
+
+#[cfg(test)]
+#[test]
+fn test_isqrt() {
+    for n in (0..1_000_000).chain([u64::MAX as u128, 1 << 92, (1 << 92) - 1]) {
+        let root = isqrt(n);
+        assert!(root * root <= n && (root + 1) * (root + 1) > n);
+    }
 }
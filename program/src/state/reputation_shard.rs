@@ -0,0 +1,201 @@
+use bonfida_utils::BorshSize;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{error::SnsReputationError, utils::derive_key};
+
+use super::{reputation_score::ReputationScore, Tag};
+
+/// Number of shards a ReputationScore can be split into
+pub const NUM_SHARDS: u8 = 8;
+
+/// Holds the votes on a user that have not been aggregated into its ReputationScore yet.
+/// Votes removed from a shard after being aggregated make its tallies negative.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, BorshSize, Default)]
+#[allow(missing_docs)]
+#[repr(C)]
+pub struct ReputationShard {
+    /// Nonce
+    pub nonce: u8,
+    /// The user whose votes are stored
+    pub user_key: Pubkey,
+    /// Index of the shard
+    pub shard: u8,
+    /// Upvotes amount since the last aggregation
+    pub upvote: i64,
+    /// Downvotes amount since the last aggregation
+    pub downvote: i64,
+    /// Change of the number of upvoters since the last aggregation
+    pub upvoters: i64,
+    /// Change of the number of downvoters since the last aggregation
+    pub downvoters: i64,
+}
+
+#[allow(missing_docs)]
+impl ReputationShard {
+    pub const SEED: &'static [u8] = b"reputation_shard";
+
+    pub fn from_buffer(buffer: &[u8], expected_tag: super::Tag) -> Result<Self, ProgramError> {
+        let (tag, mut buffer) = buffer.split_at(8);
+        if *bytemuck::from_bytes::<u64>(tag) != expected_tag as u64 {
+            return Err(SnsReputationError::DataTypeMismatch.into());
+        }
+        Ok(Self::deserialize(&mut buffer)?)
+    }
+
+    /// Shard in which the votes of `voter` land
+    pub fn shard_index(voter: &Pubkey) -> u8 {
+        voter.as_ref()[0] % NUM_SHARDS
+    }
+
+    pub fn find_key(program_id: &Pubkey, user_key: &Pubkey, shard: u8) -> (Pubkey, u8) {
+        let seeds: &[&[u8]] = &[Self::SEED, user_key.as_ref(), &[shard]];
+        Pubkey::find_program_address(seeds, program_id)
+    }
+
+    /// Derives the key from a known `nonce`, which is much cheaper than `find_key`
+    pub fn create_key(
+        program_id: &Pubkey,
+        user_key: &Pubkey,
+        shard: u8,
+        nonce: u8,
+    ) -> Result<Pubkey, ProgramError> {
+        let seeds: &[&[u8]] = &[Self::SEED, user_key.as_ref(), &[shard], &[nonce]];
+        derive_key(seeds, program_id)
+    }
+
+    /// Adds a weighted vote to the tallies, the sign of `value` selecting the tally
+    pub fn add_vote(&mut self, value: i64) -> Result<(), ProgramError> {
+        if value > 0 {
+            self.upvote = checked(self.upvote.checked_add(value))?;
+            self.upvoters = checked(self.upvoters.checked_add(1))?;
+        } else if value < 0 {
+            self.downvote = checked(self.downvote.checked_sub(value))?;
+            self.downvoters = checked(self.downvoters.checked_add(1))?;
+        }
+        Ok(())
+    }
+
+    /// Removes a weighted vote, which may have been added before the last aggregation
+    pub fn remove_vote(&mut self, value: i64) -> Result<(), ProgramError> {
+        if value > 0 {
+            self.upvote = checked(self.upvote.checked_sub(value))?;
+            self.upvoters = checked(self.upvoters.checked_sub(1))?;
+        } else if value < 0 {
+            self.downvote = checked(self.downvote.checked_add(value))?;
+            self.downvoters = checked(self.downvoters.checked_sub(1))?;
+        }
+        Ok(())
+    }
+
+    /// Adds the tallies of `other` to the ones of the shard, so that several shards can be
+    /// applied to a score at once whatever their order
+    pub fn accumulate(&mut self, other: &ReputationShard) -> Result<(), ProgramError> {
+        self.upvote = checked(self.upvote.checked_add(other.upvote))?;
+        self.downvote = checked(self.downvote.checked_add(other.downvote))?;
+        self.upvoters = checked(self.upvoters.checked_add(other.upvoters))?;
+        self.downvoters = checked(self.downvoters.checked_add(other.downvoters))?;
+        Ok(())
+    }
+
+    /// Moves the tallies of the shard into `score`
+    pub fn aggregate_into(&mut self, score: &mut ReputationScore) -> Result<(), ProgramError> {
+        score.upvote = apply_delta(score.upvote, self.upvote)?;
+        score.downvote = apply_delta(score.downvote, self.downvote)?;
+        // Like `ReputationScore::remove_vote`, voters removed without having been counted are
+        // ignored
+        score.upvoters = apply_count_delta(score.upvoters, self.upvoters)?;
+        score.downvoters = apply_count_delta(score.downvoters, self.downvoters)?;
+        self.upvote = 0;
+        self.downvote = 0;
+        self.upvoters = 0;
+        self.downvoters = 0;
+        Ok(())
+    }
+
+    pub fn save(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        // Skip first 8 bytes and store other data after them
+        self.serialize(&mut (&mut dst[8..]))?;
+        // First Tag data in the first 8 bytes (u64 size) to represent what kind of data stored in next bytes
+        (Tag::ReputationShard as u64).serialize(&mut (&mut dst[..]))?;
+        Ok(())
+    }
+}
+
+fn checked(result: Option<i64>) -> Result<i64, ProgramError> {
+    result.ok_or_else(|| SnsReputationError::Overflow.into())
+}
+
+fn apply_delta(total: u64, delta: i64) -> Result<u64, ProgramError> {
+    if delta >= 0 {
+        total
+            .checked_add(delta.unsigned_abs())
+            .ok_or_else(|| SnsReputationError::Overflow.into())
+    } else {
+        total
+            .checked_sub(delta.unsigned_abs())
+            .ok_or_else(|| SnsReputationError::ScoreUnderflow.into())
+    }
+}
+
+fn apply_count_delta(count: u64, delta: i64) -> Result<u64, ProgramError> {
+    if delta >= 0 {
+        apply_delta(count, delta)
+    } else {
+        Ok(count.saturating_sub(delta.unsigned_abs()))
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_aggregate() {
+    let mut score = ReputationScore {
+        nonce: 0,
+        upvote: 10,
+        downvote: 3,
+        upvoters: 2,
+        downvoters: 1,
+    };
+    let mut shard = ReputationShard::default();
+    shard.add_vote(5).unwrap();
+    shard.add_vote(-2).unwrap();
+    shard.aggregate_into(&mut score).unwrap();
+    assert_eq!((score.upvote, score.downvote), (15, 5));
+    assert_eq!((score.upvoters, score.downvoters), (3, 2));
+    assert_eq!(shard, ReputationShard::default());
+
+    // A vote aggregated earlier is removed from the shard
+    shard.remove_vote(5).unwrap();
+    assert_eq!((shard.upvote, shard.upvoters), (-5, -1));
+    shard.aggregate_into(&mut score).unwrap();
+    assert_eq!((score.upvote, score.upvoters), (10, 2));
+
+    // Removing more than was aggregated is reported
+    shard.remove_vote(-20).unwrap();
+    assert_eq!(
+        shard.aggregate_into(&mut score),
+        Err(SnsReputationError::ScoreUnderflow.into())
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_accumulate() {
+    let mut score = ReputationScore::default();
+    let mut removed = ReputationShard::default();
+    removed.remove_vote(-4).unwrap();
+    let mut added = ReputationShard::default();
+    added.add_vote(-4).unwrap();
+
+    // Applied one by one, the shard removing the vote underflows first
+    assert_eq!(
+        removed.aggregate_into(&mut ReputationScore::default()),
+        Err(SnsReputationError::ScoreUnderflow.into())
+    );
+
+    let mut pending = ReputationShard::default();
+    pending.accumulate(&removed).unwrap();
+    pending.accumulate(&added).unwrap();
+    pending.aggregate_into(&mut score).unwrap();
+    assert_eq!(score, ReputationScore::default());
+}
@@ -0,0 +1,157 @@
+use bytemuck::{Pod, Zeroable};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{error::SnsReputationError, utils::derive_key};
+
+use super::{
+    pod::{PodI64, PodU64},
+    Tag,
+};
+
+/// Number of epochs kept in a ScoreHistory
+pub const HISTORY_LEN: usize = 64;
+
+/// Tallies of a user at the time of a snapshot
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+#[allow(missing_docs)]
+#[repr(C)]
+pub struct ScoreSnapshot {
+    pub epoch: PodU64,
+    /// Unix timestamp of the snapshot, zero for entries that were never written
+    pub timestamp: PodI64,
+    pub upvote: PodU64,
+    pub downvote: PodU64,
+}
+
+impl ScoreSnapshot {
+    #[allow(missing_docs)]
+    pub fn is_empty(&self) -> bool {
+        i64::from(self.timestamp) == 0
+    }
+}
+
+/// Ring buffer of the tallies of a user over the last `HISTORY_LEN` epochs, indexed by epoch.
+/// Accessed in place, the account being too large to be copied around cheaply
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[allow(missing_docs)]
+#[repr(C)]
+pub struct ScoreHistory {
+    pub tag: PodU64,
+    /// Nonce
+    pub nonce: u8,
+    /// The user whose tallies are recorded
+    pub user_key: Pubkey,
+    pub snapshots: [ScoreSnapshot; HISTORY_LEN],
+}
+
+#[allow(missing_docs)]
+impl ScoreHistory {
+    pub const SEED: &'static [u8] = b"score_history";
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    pub fn find_key(program_id: &Pubkey, user_key: &Pubkey) -> (Pubkey, u8) {
+        let seeds: &[&[u8]] = &[Self::SEED, user_key.as_ref()];
+        Pubkey::find_program_address(seeds, program_id)
+    }
+
+    /// Derives the key from a known `nonce`, which is much cheaper than `find_key`
+    pub fn create_key(
+        program_id: &Pubkey,
+        user_key: &Pubkey,
+        nonce: u8,
+    ) -> Result<Pubkey, ProgramError> {
+        let seeds: &[&[u8]] = &[Self::SEED, user_key.as_ref(), &[nonce]];
+        derive_key(seeds, program_id)
+    }
+
+    fn check_header(buffer: &[u8]) -> Result<(), ProgramError> {
+        if buffer.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if buffer[..8] != (Tag::ScoreHistory as u64).to_le_bytes() {
+            return Err(SnsReputationError::DataTypeMismatch.into());
+        }
+        Ok(())
+    }
+
+    pub fn load(buffer: &[u8]) -> Result<&Self, ProgramError> {
+        Self::check_header(buffer)?;
+        Ok(bytemuck::from_bytes(buffer))
+    }
+
+    pub fn load_mut(buffer: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        Self::check_header(buffer)?;
+        Ok(bytemuck::from_bytes_mut(buffer))
+    }
+
+    /// Initializes a newly allocated account
+    pub fn init<'a>(
+        buffer: &'a mut [u8],
+        nonce: u8,
+        user_key: &Pubkey,
+    ) -> Result<&'a mut Self, ProgramError> {
+        if buffer.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let history: &mut Self = bytemuck::from_bytes_mut(buffer);
+        history.tag = (Tag::ScoreHistory as u64).into();
+        history.nonce = nonce;
+        history.user_key = *user_key;
+        Ok(history)
+    }
+
+    /// Records the tallies of `epoch`, replacing the ones recorded `HISTORY_LEN` epochs before or
+    /// earlier during the same epoch
+    pub fn record(&mut self, epoch: u64, timestamp: i64, upvote: u64, downvote: u64) {
+        self.snapshots[(epoch % HISTORY_LEN as u64) as usize] = ScoreSnapshot {
+            epoch: epoch.into(),
+            timestamp: timestamp.into(),
+            upvote: upvote.into(),
+            downvote: downvote.into(),
+        };
+    }
+
+    /// Returns the snapshots of the last `HISTORY_LEN` epochs up to `current_epoch`, oldest first
+    pub fn series(&self, current_epoch: u64) -> Vec<ScoreSnapshot> {
+        let mut series = self
+            .snapshots
+            .iter()
+            .filter(|snapshot| {
+                let epoch = u64::from(snapshot.epoch);
+                !snapshot.is_empty()
+                    && epoch <= current_epoch
+                    && current_epoch - epoch < HISTORY_LEN as u64
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        series.sort_by_key(|snapshot| u64::from(snapshot.epoch));
+        series
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_ring_buffer() {
+    let mut buffer = vec![0; ScoreHistory::LEN];
+    let user_key = Pubkey::new_unique();
+    ScoreHistory::init(&mut buffer, 255, &user_key).unwrap();
+    let history = ScoreHistory::load_mut(&mut buffer).unwrap();
+
+    history.record(10, 1_000, 1, 0);
+    history.record(11, 2_000, 2, 0);
+    // A second snapshot in the same epoch replaces the first one
+    history.record(11, 2_500, 3, 1);
+    // Epoch 10 falls out of the window and is overwritten by epoch 74
+    history.record(10 + HISTORY_LEN as u64, 9_000, 4, 1);
+
+    let series = history.series(10 + HISTORY_LEN as u64);
+    let epochs = series
+        .iter()
+        .map(|snapshot| u64::from(snapshot.epoch))
+        .collect::<Vec<_>>();
+    assert_eq!(epochs, vec![11, 10 + HISTORY_LEN as u64]);
+    assert_eq!(u64::from(series[0].upvote), 3);
+
+    // Snapshots older than the window are ignored even if they were not overwritten
+    assert_eq!(history.series(11 + HISTORY_LEN as u64).len(), 1);
+}
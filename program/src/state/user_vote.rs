@@ -1,10 +1,20 @@
 use bonfida_utils::BorshSize;
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use bytemuck::{Pod, Zeroable};
+use solana_program::{hash::hashv, program_error::ProgramError, pubkey::Pubkey};
 
-use crate::error::SnsReputationError;
+use crate::{error::SnsReputationError, utils::derive_key};
 
-use super::Tag;
+use super::{
+    pod::{PodI64, PodU64},
+    reputation_score::CREDIBILITY_SCALE,
+    Tag, VoteValue, VERSIONED_HEADER_LEN,
+};
+
+/// Number of slots (~1 day) during which a committed vote stays hidden
+pub const COMMIT_PERIOD_SLOTS: u64 = 216_000;
+/// Number of slots (~1 day) during which a committed vote can be revealed, once the commit period is over
+pub const REVEAL_PERIOD_SLOTS: u64 = 216_000;
 
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, BorshSize, Default)]
 #[allow(missing_docs)]
@@ -16,16 +26,85 @@ pub struct UserVote {
     pub votee: Pubkey,
     /// Voter address, stored as metadata
     pub voter: Pubkey,
+    /// Hash of the committed vote, zeroed once the vote is revealed or when no commitment was made
+    pub commitment: [u8; 32],
+    /// Slot at which the vote was committed
+    pub commit_slot: u64,
+    /// Curator from which the vote was propagated, the default key for votes cast by the voter
+    pub followed_curator: Pubkey,
+    /// Account that paid the rent of this account, refunded when the vote is closed
+    pub rent_payer: Pubkey,
+    /// Nonce
+    pub nonce: u8,
+    /// Credibility multiplier of the voter applied to the weight of the vote, scaled by
+    /// `CREDIBILITY_SCALE`
+    pub credibility_multiplier: u64,
+    /// Slot at which the value of the vote was last set, zero for the votes set before it was
+    /// recorded
+    pub vote_slot: u64,
+    /// ReputationScore or ReputationShard in which the value of the vote is tallied, the default
+    /// key for pending commitments and for the votes tallied before it was recorded
+    pub reputation_account: Pubkey,
 }
 
 #[allow(missing_docs)]
 impl UserVote {
+    /// Version of the layout written by the program, stored after the Tag
+    pub const VERSION: u8 = 4;
+    /// Size of the accounts created before the layout was versioned, which only stored the
+    /// value, votee and voter after the Tag
+    pub const UNVERSIONED_LEN: usize = 8 + 8 + 32 + 32;
+
+    /// Size of an account using the latest layout
+    pub fn space() -> usize {
+        Self::default().borsh_len() + VERSIONED_HEADER_LEN
+    }
+
+    /// Reads an account in any of the layouts used since the program was deployed. The fields
+    /// missing from unversioned layouts take their default value, the nonce included
     pub fn from_buffer(buffer: &[u8], expected_tag: super::Tag) -> Result<Self, ProgramError> {
-        let (tag, mut buffer) = buffer.split_at(8);
+        let is_unversioned = buffer.len() == Self::UNVERSIONED_LEN;
+        let (tag, buffer) = buffer.split_at(8);
         if *bytemuck::from_bytes::<u64>(tag) != expected_tag as u64 {
             return Err(SnsReputationError::DataTypeMismatch.into());
         }
-        Ok(Self::deserialize(&mut buffer)?)
+        let (version, mut buffer) = if is_unversioned {
+            (0, buffer)
+        } else {
+            let (version, buffer) = buffer
+                .split_first()
+                .ok_or(ProgramError::InvalidAccountData)?;
+            (*version, buffer)
+        };
+        match version {
+            // Commit-reveal didn't exist yet, the vote was never pending
+            0 => Ok(Self {
+                value: i64::deserialize(&mut buffer)?,
+                votee: Pubkey::deserialize(&mut buffer)?,
+                voter: Pubkey::deserialize(&mut buffer)?,
+                credibility_multiplier: CREDIBILITY_SCALE,
+                ..Self::default()
+            }),
+            // The first versioned layout lacks the credibility multiplier, the first two lack the
+            // slot of the vote and all previous layouts lack the reputation account
+            1..=3 => {
+                let mut padded = buffer.to_vec();
+                padded.resize(Self::default().borsh_len(), 0);
+                let mut vote = Self::deserialize(&mut padded.as_slice())?;
+                // Votes cast before the multiplier existed were not weighted by it
+                if version == 1 && !vote.is_pending_commitment() {
+                    vote.credibility_multiplier = CREDIBILITY_SCALE;
+                }
+                Ok(vote)
+            }
+            Self::VERSION => Ok(Self::deserialize(&mut buffer)?),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    /// Returns true if the account uses the latest layout
+    pub fn is_latest_layout(buffer: &[u8]) -> bool {
+        buffer.len() == Self::space() && buffer[8] == Self::VERSION
     }
 
     pub fn find_key(program_id: &Pubkey, addresses: &(Pubkey, Pubkey)) -> (Pubkey, u8) {
@@ -35,11 +114,261 @@ impl UserVote {
         Pubkey::find_program_address(seeds, program_id)
     }
 
+    /// Derives the key from a known `nonce`, which is much cheaper than `find_key`
+    pub fn create_key(
+        program_id: &Pubkey,
+        addresses: &(Pubkey, Pubkey),
+        nonce: u8,
+    ) -> Result<Pubkey, ProgramError> {
+        let (user_address, voter) = addresses;
+
+        let seeds: &[&[u8]] = &[user_address.as_ref(), voter.as_ref(), &[nonce]];
+        derive_key(seeds, program_id)
+    }
+
+    /// Computes the commitment to a `vote_value` vote over `votee` hidden with `salt`
+    pub fn compute_commitment(
+        votee: &Pubkey,
+        voter: &Pubkey,
+        vote_value: VoteValue,
+        salt: &[u8; 32],
+    ) -> [u8; 32] {
+        hashv(&[
+            votee.as_ref(),
+            voter.as_ref(),
+            &(vote_value as i64).to_le_bytes(),
+            salt,
+        ])
+        .to_bytes()
+    }
+
+    /// Returns true if the vote was committed and hasn't been revealed yet
+    pub fn is_pending_commitment(&self) -> bool {
+        self.commitment != [0; 32]
+    }
+
+    /// Returns true if the vote was propagated from a followed curator
+    pub fn is_followed(&self) -> bool {
+        self.followed_curator != Pubkey::default()
+    }
+
+    /// Returns the account to which the rent is returned when the vote is closed
+    pub fn rent_destination(&self) -> &Pubkey {
+        if self.rent_payer == Pubkey::default() {
+            &self.voter
+        } else {
+            &self.rent_payer
+        }
+    }
+
+    /// Returns true if `slot` is in the reveal window of the committed vote
+    pub fn is_in_reveal_window(&self, slot: u64) -> bool {
+        let reveal_start = self.commit_slot.saturating_add(COMMIT_PERIOD_SLOTS);
+        slot >= reveal_start && slot < reveal_start.saturating_add(REVEAL_PERIOD_SLOTS)
+    }
+
+    /// Returns true if the committed vote can no longer be revealed at `slot`
+    pub fn is_commitment_expired(&self, slot: u64) -> bool {
+        slot >= self
+            .commit_slot
+            .saturating_add(COMMIT_PERIOD_SLOTS)
+            .saturating_add(REVEAL_PERIOD_SLOTS)
+    }
+
+    /// Stores data, accounts in a previous layout have to be migrated first
     pub fn save(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
-        // Skip first 8 bytes and store other data after them
-        self.serialize(&mut (&mut dst[8..]))?;
+        if dst.len() < Self::space() {
+            return Err(SnsReputationError::OutdatedAccountLayout.into());
+        }
+        // Skip the header and store other data after it
+        self.serialize(&mut (&mut dst[VERSIONED_HEADER_LEN..]))?;
         // First Tag data in the first 8 bytes (u64 size) to represent what kind of data stored in next bytes
         (Tag::UserVote as u64).serialize(&mut (&mut dst[..]))?;
+        // Followed by the version of the layout
+        dst[8] = Self::VERSION;
         Ok(())
     }
 }
+
+/// Zero-copy view of a UserVote account, header included, with the same layout as the Borsh
+/// encoding. Only accounts using the latest layout can be viewed, the others can be read with
+/// `UserVote::from_buffer`
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[allow(missing_docs)]
+#[repr(C)]
+pub struct UserVotePod {
+    pub tag: PodU64,
+    pub version: u8,
+    pub value: PodI64,
+    pub votee: Pubkey,
+    pub voter: Pubkey,
+    pub commitment: [u8; 32],
+    pub commit_slot: PodU64,
+    pub followed_curator: Pubkey,
+    pub rent_payer: Pubkey,
+    pub nonce: u8,
+    pub credibility_multiplier: PodU64,
+    pub vote_slot: PodU64,
+    pub reputation_account: Pubkey,
+}
+
+#[allow(missing_docs)]
+impl UserVotePod {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    fn check_header(buffer: &[u8]) -> Result<(), ProgramError> {
+        if buffer.get(..8) != Some(&(Tag::UserVote as u64).to_le_bytes()[..]) {
+            return Err(SnsReputationError::DataTypeMismatch.into());
+        }
+        if !UserVote::is_latest_layout(buffer) {
+            return Err(SnsReputationError::OutdatedAccountLayout.into());
+        }
+        Ok(())
+    }
+
+    pub fn load(buffer: &[u8]) -> Result<&Self, ProgramError> {
+        Self::check_header(buffer)?;
+        Ok(bytemuck::from_bytes(buffer))
+    }
+
+    pub fn load_mut(buffer: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        Self::check_header(buffer)?;
+        Ok(bytemuck::from_bytes_mut(buffer))
+    }
+
+    /// See `UserVote::is_pending_commitment`
+    pub fn is_pending_commitment(&self) -> bool {
+        self.commitment != [0; 32]
+    }
+
+    /// See `UserVote::is_followed`
+    pub fn is_followed(&self) -> bool {
+        self.followed_curator != Pubkey::default()
+    }
+
+    /// See `UserVote::rent_destination`
+    pub fn rent_destination(&self) -> &Pubkey {
+        if self.rent_payer == Pubkey::default() {
+            &self.voter
+        } else {
+            &self.rent_payer
+        }
+    }
+}
+
+impl From<&UserVotePod> for UserVote {
+    fn from(pod: &UserVotePod) -> Self {
+        Self {
+            value: pod.value.into(),
+            votee: pod.votee,
+            voter: pod.voter,
+            commitment: pod.commitment,
+            commit_slot: pod.commit_slot.into(),
+            followed_curator: pod.followed_curator,
+            rent_payer: pod.rent_payer,
+            nonce: pod.nonce,
+            credibility_multiplier: pod.credibility_multiplier.into(),
+            vote_slot: pod.vote_slot.into(),
+            reputation_account: pod.reputation_account,
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_pod_layout() {
+    let vote = UserVote {
+        value: -42,
+        votee: Pubkey::new_unique(),
+        voter: Pubkey::new_unique(),
+        commitment: [7; 32],
+        commit_slot: 1234,
+        followed_curator: Pubkey::new_unique(),
+        rent_payer: Pubkey::new_unique(),
+        nonce: 253,
+        credibility_multiplier: 12_000,
+        vote_slot: 5678,
+        reputation_account: Pubkey::new_unique(),
+    };
+    let mut buffer = vec![0; UserVotePod::LEN];
+    vote.save(&mut buffer).unwrap();
+    assert_eq!(UserVote::space(), UserVotePod::LEN);
+    assert_eq!(UserVote::from(UserVotePod::load(&buffer).unwrap()), vote);
+    assert_eq!(UserVote::from_buffer(&buffer, Tag::UserVote).unwrap(), vote);
+}
+
+#[cfg(test)]
+#[test]
+fn test_unversioned_layouts() {
+    let vote = UserVote {
+        value: -42,
+        votee: Pubkey::new_unique(),
+        voter: Pubkey::new_unique(),
+        commitment: [7; 32],
+        commit_slot: 1234,
+        followed_curator: Pubkey::new_unique(),
+        rent_payer: Pubkey::new_unique(),
+        nonce: 253,
+        credibility_multiplier: 0,
+        vote_slot: 0,
+        reputation_account: Pubkey::default(),
+    };
+    let mut buffer = (Tag::UserVote as u64).to_le_bytes().to_vec();
+    vote.serialize(&mut buffer).unwrap();
+
+    // The unversioned layout only stored the value, votee and voter
+    let unversioned = &buffer[..UserVote::UNVERSIONED_LEN];
+    let parsed = UserVote::from_buffer(unversioned, Tag::UserVote).unwrap();
+    assert_eq!(
+        parsed,
+        UserVote {
+            value: vote.value,
+            votee: vote.votee,
+            voter: vote.voter,
+            credibility_multiplier: CREDIBILITY_SCALE,
+            ..UserVote::default()
+        }
+    );
+    assert!(!UserVote::is_latest_layout(unversioned));
+    assert!(UserVotePod::load(unversioned).is_err());
+
+    // The first versioned layout lacks the credibility multiplier. Pending commitments have
+    // no multiplier until they are revealed
+    let mut v1 = (Tag::UserVote as u64).to_le_bytes().to_vec();
+    v1.push(1);
+    v1.extend_from_slice(&buffer[8..buffer.len() - 48]);
+    assert!(!UserVote::is_latest_layout(&v1));
+    assert!(UserVotePod::load(&v1).is_err());
+    assert_eq!(UserVote::from_buffer(&v1, Tag::UserVote).unwrap(), vote);
+
+    // Votes cast before the multiplier existed were not weighted by it
+    v1[9 + 8 + 32 + 32..9 + 8 + 32 + 32 + 32].fill(0);
+    let revealed = UserVote::from_buffer(&v1, Tag::UserVote).unwrap();
+    assert_eq!(revealed.credibility_multiplier, CREDIBILITY_SCALE);
+
+    // The second versioned layout lacks the slot of the vote
+    let v2_vote = UserVote {
+        credibility_multiplier: 12_000,
+        ..vote
+    };
+    let mut v2 = (Tag::UserVote as u64).to_le_bytes().to_vec();
+    v2.push(2);
+    v2_vote.serialize(&mut v2).unwrap();
+    v2.truncate(v2.len() - 40);
+    assert!(!UserVote::is_latest_layout(&v2));
+    assert!(UserVotePod::load(&v2).is_err());
+    assert_eq!(UserVote::from_buffer(&v2, Tag::UserVote).unwrap(), v2_vote);
+
+    // The third versioned layout lacks the reputation account
+    let v3_vote = UserVote {
+        vote_slot: 5678,
+        ..v2_vote
+    };
+    let mut v3 = (Tag::UserVote as u64).to_le_bytes().to_vec();
+    v3.push(3);
+    v3_vote.serialize(&mut v3).unwrap();
+    v3.truncate(v3.len() - 32);
+    assert!(!UserVote::is_latest_layout(&v3));
+    assert!(UserVotePod::load(&v3).is_err());
+    assert_eq!(UserVote::from_buffer(&v3, Tag::UserVote).unwrap(), v3_vote);
+}
@@ -0,0 +1,136 @@
+use bonfida_utils::BorshSize;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{error::SnsReputationError, utils::derive_key};
+
+use super::{Tag, VoteValue};
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, BorshSize, Default)]
+#[allow(missing_docs)]
+#[repr(C)]
+pub struct VoterProfile {
+    /// Nonce
+    pub nonce: u8,
+    /// Voter address, stored as metadata
+    pub voter: Pubkey,
+    /// Number of votes currently held by the voter
+    pub active_votes: u64,
+    /// Number of upvotes cast by the voter over its lifetime
+    pub upvotes_cast: u64,
+    /// Number of downvotes cast by the voter over its lifetime
+    pub downvotes_cast: u64,
+    /// Slot of the voter's first vote
+    pub first_vote_slot: u64,
+    /// Slot of the voter's last vote
+    pub last_vote_slot: u64,
+    /// Epoch in which the new votes below were cast
+    pub rate_limit_epoch: u64,
+    /// Number of new votes cast during `rate_limit_epoch`
+    pub rate_limit_new_votes: u64,
+    /// Nonce expected in the next vote relayed on behalf of the voter
+    pub relay_nonce: u64,
+}
+
+#[allow(missing_docs)]
+impl VoterProfile {
+    pub const SEED: &'static [u8] = b"voter_profile";
+
+    pub fn new(nonce: u8, voter: Pubkey) -> Self {
+        Self {
+            nonce,
+            voter,
+            ..Self::default()
+        }
+    }
+
+    pub fn from_buffer(buffer: &[u8], expected_tag: super::Tag) -> Result<Self, ProgramError> {
+        let (tag, mut buffer) = buffer.split_at(8);
+        if *bytemuck::from_bytes::<u64>(tag) != expected_tag as u64 {
+            return Err(SnsReputationError::DataTypeMismatch.into());
+        }
+        Ok(Self::deserialize(&mut buffer)?)
+    }
+
+    pub fn find_key(program_id: &Pubkey, voter: &Pubkey) -> (Pubkey, u8) {
+        let seeds: &[&[u8]] = &[Self::SEED, voter.as_ref()];
+        Pubkey::find_program_address(seeds, program_id)
+    }
+
+    /// Derives the key from a known `nonce`, which is much cheaper than `find_key`
+    pub fn create_key(
+        program_id: &Pubkey,
+        voter: &Pubkey,
+        nonce: u8,
+    ) -> Result<Pubkey, ProgramError> {
+        let seeds: &[&[u8]] = &[Self::SEED, voter.as_ref(), &[nonce]];
+        derive_key(seeds, program_id)
+    }
+
+    /// Accounts for a new vote cast during `epoch`, failing if the voter already
+    /// cast `max_new_votes` new votes during this epoch
+    pub fn register_new_vote(&mut self, epoch: u64, max_new_votes: u64) -> ProgramResult {
+        if self.rate_limit_epoch != epoch {
+            self.rate_limit_epoch = epoch;
+            self.rate_limit_new_votes = 0;
+        }
+        if self.rate_limit_new_votes >= max_new_votes {
+            return Err(SnsReputationError::RateLimited.into());
+        }
+        self.rate_limit_new_votes += 1;
+        Ok(())
+    }
+
+    /// Consumes `nonce` for a relayed vote, which has to match the next expected nonce
+    pub fn consume_relay_nonce(&mut self, nonce: u64) -> ProgramResult {
+        if nonce != self.relay_nonce {
+            return Err(SnsReputationError::InvalidRelayNonce.into());
+        }
+        self.relay_nonce += 1;
+        Ok(())
+    }
+
+    /// Records a vote cast with `value` at `slot`, `is_new` being true when the vote
+    /// creates a new UserVote account
+    pub fn record_vote(&mut self, value: VoteValue, is_new: bool, slot: u64) {
+        if is_new {
+            self.active_votes = self.active_votes.saturating_add(1);
+        }
+        match value {
+            VoteValue::Upvote => self.upvotes_cast = self.upvotes_cast.saturating_add(1),
+            VoteValue::Downvote => self.downvotes_cast = self.downvotes_cast.saturating_add(1),
+            VoteValue::NoVote => {}
+        }
+        if self.first_vote_slot == 0 {
+            self.first_vote_slot = slot;
+        }
+        self.last_vote_slot = slot;
+    }
+
+    /// Records the removal of one of the voter's votes
+    pub fn record_undo(&mut self, slot: u64) {
+        self.active_votes = self.active_votes.saturating_sub(1);
+        self.last_vote_slot = slot;
+    }
+
+    pub fn save(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        // Skip first 8 bytes and store other data after them
+        self.serialize(&mut (&mut dst[8..]))?;
+        // First Tag data in the first 8 bytes (u64 size) to represent what kind of data stored in next bytes
+        (Tag::VoterProfile as u64).serialize(&mut (&mut dst[..]))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_rate_limit() {
+    let mut profile = VoterProfile::new(0, Pubkey::new_unique());
+    for _ in 0..3 {
+        profile.register_new_vote(10, 3).unwrap();
+    }
+    assert!(profile.register_new_vote(10, 3).is_err());
+    // The counter is reset with the next epoch
+    profile.register_new_vote(11, 3).unwrap();
+    assert_eq!(profile.rate_limit_new_votes, 1);
+}
@@ -0,0 +1,377 @@
+use std::convert::TryFrom;
+
+use bonfida_utils::{
+    checks::{check_account_key, check_account_owner, check_signer},
+    BorshSize,
+};
+use solana_program::{
+    account_info::AccountInfo,
+    ed25519_program,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+#[cfg(not(feature = "devnet"))]
+use {
+    borsh::BorshDeserialize,
+    solana_program::{clock::Clock, stake::state::StakeState, sysvar::Sysvar},
+};
+
+use crate::{
+    cpi::Cpi,
+    error::SnsReputationError,
+    state::{
+        config::{Config, DEFAULT_MAX_NEW_VOTES_PER_EPOCH},
+        reputation_score::{ReputationScore, ReputationScorePod, CREDIBILITY_SCALE},
+        reputation_shard::ReputationShard,
+        voter_profile::VoterProfile,
+        Tag, VoteValue,
+    },
+};
+
+/// Maximum number of stake accounts that can be passed along a vote. Voters with more stake
+/// accounts should pass their largest ones, using an address lookup table
+pub const MAX_STAKE_ACCOUNTS: usize = 32;
+
+fn check_stake_accounts_len(stake_accounts: &[AccountInfo]) -> ProgramResult {
+    if stake_accounts.len() > MAX_STAKE_ACCOUNTS {
+        msg!(
+            "At most {} stake accounts can be passed, got {}",
+            MAX_STAKE_ACCOUNTS,
+            stake_accounts.len()
+        );
+        return Err(SnsReputationError::TooManyStakeAccounts.into());
+    }
+    Ok(())
+}
+
+/// Returns the weight of a `vote_value` vote cast by `voter`, which is the total
+/// amount of lamports `voter` has been staking for at least two epochs in `stake_accounts`
+#[cfg(not(feature = "devnet"))]
+pub fn get_vote_weight(
+    voter: &Pubkey,
+    stake_accounts: &[AccountInfo],
+    vote_value: VoteValue,
+) -> Result<i64, ProgramError> {
+    check_stake_accounts_len(stake_accounts)?;
+    if vote_value == VoteValue::NoVote {
+        return Ok(0);
+    }
+    if stake_accounts.is_empty() {
+        return Err(SnsReputationError::MissingStakeAccount.into());
+    }
+    let clock = Clock::get()?;
+    let mut total_stake: i64 = 0;
+    for voter_stake_account in stake_accounts.iter() {
+        check_account_owner(voter_stake_account, &solana_program::stake::program::ID)?;
+        let parsed_stake =
+            StakeState::deserialize(&mut (&voter_stake_account.data.borrow() as &[u8]))?;
+        let stake = if let StakeState::Stake(meta, stake) = parsed_stake {
+            if &meta.authorized.staker != voter {
+                msg!("The staking account should be owned by the voter");
+                return Err(SnsReputationError::InvalidStakeAccount.into());
+            }
+            if clock
+                .epoch
+                .saturating_sub(stake.delegation.activation_epoch)
+                < 2
+            // At least three days lockup
+            {
+                msg!("Funds have not been staked for long enough.");
+                return Err(SnsReputationError::InvalidStakeAccount.into());
+            }
+            stake.delegation.stake
+        } else {
+            return Err(SnsReputationError::InvalidStakeAccount.into());
+        };
+        total_stake = i64::try_from(stake)
+            .ok()
+            .and_then(|stake| total_stake.checked_add(stake))
+            .ok_or(SnsReputationError::Overflow)?;
+    }
+    Ok(total_stake)
+}
+
+/// On devnet every vote has a weight of 1
+#[cfg(feature = "devnet")]
+pub fn get_vote_weight(
+    _voter: &Pubkey,
+    stake_accounts: &[AccountInfo],
+    _vote_value: VoteValue,
+) -> Result<i64, ProgramError> {
+    check_stake_accounts_len(stake_accounts)?;
+    Ok(1)
+}
+
+/// Returns the credibility multiplier of `voter`, read from its ReputationScore account when it
+/// is given. Voters that don't give it get the neutral multiplier, like voters without any vote
+pub fn get_credibility_multiplier(
+    program_id: &Pubkey,
+    voter: &Pubkey,
+    voter_reputation_account: Option<&AccountInfo>,
+) -> Result<u64, ProgramError> {
+    let voter_reputation_account = match voter_reputation_account {
+        Some(account) => account,
+        None => return Ok(CREDIBILITY_SCALE),
+    };
+    check_account_owner(voter_reputation_account, program_id)?;
+    // Read-only, so accounts in a previous layout don't have to be migrated
+    let score = ReputationScore::from_buffer(
+        &voter_reputation_account.data.borrow(),
+        Tag::ReputationScore,
+    )?;
+    let key = ReputationScore::create_key(program_id, voter, score.nonce)?;
+    check_account_key(voter_reputation_account, &key)?;
+    Ok(score.credibility_multiplier())
+}
+
+/// Returns the next account if the program owns it, which tells the optional ReputationScore of
+/// a voter apart from its stake accounts, owned by the stake program
+pub fn next_voter_reputation_account<'a, 'b>(
+    accounts_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    program_id: &Pubkey,
+) -> Option<&'a AccountInfo<'b>> {
+    match accounts_iter.as_slice().first() {
+        Some(account) if account.owner == program_id => accounts_iter.next(),
+        _ => None,
+    }
+}
+
+/// Scales `vote_weight` by a credibility multiplier, rounding down
+pub fn apply_credibility_multiplier(
+    vote_weight: i64,
+    multiplier: u64,
+) -> Result<i64, ProgramError> {
+    let weight = vote_weight as i128 * multiplier as i128 / CREDIBILITY_SCALE as i128;
+    i64::try_from(weight).map_err(|_| SnsReputationError::Overflow.into())
+}
+
+/// Checks the ReputationScore account of `user_key`, allocating and initializing it first if it
+/// doesn't exist yet, so that it can be updated in place through `ReputationScorePod`.
+///
+/// Existing accounts are verified with the nonce they store, which is much cheaper than
+/// `find_program_address`. Nonces given by clients are never trusted as they could be
+/// non-canonical, so `find_program_address` is still used when allocating the account, unless
+/// the caller already found the canonical key and nonce and passes them as `canonical`.
+pub fn check_or_create_reputation_score<'a>(
+    program_id: &Pubkey,
+    system_program: &AccountInfo<'a>,
+    fee_payer: &AccountInfo<'a>,
+    reputation_state_account: &AccountInfo<'a>,
+    user_key: &Pubkey,
+    canonical: Option<(Pubkey, u8)>,
+) -> ProgramResult {
+    if !reputation_state_account.data_is_empty() {
+        check_account_owner(reputation_state_account, program_id)?;
+        let nonce = ReputationScorePod::load(&reputation_state_account.data.borrow())?.nonce;
+        let key = ReputationScore::create_key(program_id, user_key, nonce)?;
+        check_account_key(reputation_state_account, &key)?;
+        return Ok(());
+    }
+    let (key, nonce) = canonical.unwrap_or_else(|| ReputationScore::find_key(program_id, user_key));
+    check_account_key(reputation_state_account, &key)?;
+    check_signer(fee_payer)?;
+    let space = ReputationScore::space();
+    Cpi::create_account(
+        program_id,
+        system_program,
+        fee_payer,
+        reputation_state_account,
+        &[user_key.as_ref(), &[nonce]],
+        space,
+    )?;
+    ReputationScore {
+        nonce,
+        ..ReputationScore::default()
+    }
+    .save(&mut reputation_state_account.data.borrow_mut())
+}
+
+/// Deserializes the ReputationScore of `user_key`, allocating the account first if it doesn't
+/// exist yet. The account is verified like in `check_or_create_reputation_score`.
+pub fn load_or_create_reputation_score<'a>(
+    program_id: &Pubkey,
+    system_program: &AccountInfo<'a>,
+    fee_payer: &AccountInfo<'a>,
+    reputation_state_account: &AccountInfo<'a>,
+    user_key: &Pubkey,
+) -> Result<ReputationScore, ProgramError> {
+    check_or_create_reputation_score(
+        program_id,
+        system_program,
+        fee_payer,
+        reputation_state_account,
+        user_key,
+        None,
+    )?;
+    ReputationScore::from_buffer(
+        &reputation_state_account.data.borrow(),
+        Tag::ReputationScore,
+    )
+}
+
+/// Deserializes the ReputationShard `shard` of `user_key`, allocating the account first if it
+/// doesn't exist yet. The account is verified like in `load_or_create_reputation_score`.
+pub fn load_or_create_reputation_shard<'a>(
+    program_id: &Pubkey,
+    system_program: &AccountInfo<'a>,
+    fee_payer: &AccountInfo<'a>,
+    reputation_shard_account: &AccountInfo<'a>,
+    user_key: &Pubkey,
+    shard: u8,
+) -> Result<ReputationShard, ProgramError> {
+    if !reputation_shard_account.data_is_empty() {
+        check_account_owner(reputation_shard_account, program_id)?;
+        let reputation_shard = ReputationShard::from_buffer(
+            &reputation_shard_account.data.borrow(),
+            Tag::ReputationShard,
+        )?;
+        let key = ReputationShard::create_key(program_id, user_key, shard, reputation_shard.nonce)?;
+        check_account_key(reputation_shard_account, &key)?;
+        return Ok(reputation_shard);
+    }
+    let (key, nonce) = ReputationShard::find_key(program_id, user_key, shard);
+    check_account_key(reputation_shard_account, &key)?;
+    check_signer(fee_payer)?;
+    let space = ReputationShard::default().borsh_len() + std::mem::size_of::<Tag>();
+    Cpi::create_account(
+        program_id,
+        system_program,
+        fee_payer,
+        reputation_shard_account,
+        &[ReputationShard::SEED, user_key.as_ref(), &[shard], &[nonce]],
+        space,
+    )?;
+    Ok(ReputationShard {
+        nonce,
+        user_key: *user_key,
+        shard,
+        ..ReputationShard::default()
+    })
+}
+
+/// Deserializes the VoterProfile of `voter`, allocating the account first if it doesn't exist yet.
+/// The account is verified like in `load_or_create_reputation_score`.
+pub fn load_or_create_voter_profile<'a>(
+    program_id: &Pubkey,
+    system_program: &AccountInfo<'a>,
+    fee_payer: &AccountInfo<'a>,
+    voter_profile_account: &AccountInfo<'a>,
+    voter: &Pubkey,
+) -> Result<VoterProfile, ProgramError> {
+    if !voter_profile_account.data_is_empty() {
+        check_account_owner(voter_profile_account, program_id)?;
+        let voter_profile =
+            VoterProfile::from_buffer(&voter_profile_account.data.borrow(), Tag::VoterProfile)?;
+        let key = VoterProfile::create_key(program_id, voter, voter_profile.nonce)?;
+        check_account_key(voter_profile_account, &key)?;
+        return Ok(voter_profile);
+    }
+    let (key, nonce) = VoterProfile::find_key(program_id, voter);
+    check_account_key(voter_profile_account, &key)?;
+    check_signer(fee_payer)?;
+    let space = VoterProfile::default().borsh_len() + std::mem::size_of::<Tag>();
+    Cpi::create_account(
+        program_id,
+        system_program,
+        fee_payer,
+        voter_profile_account,
+        &[VoterProfile::SEED, voter.as_ref(), &[nonce]],
+        space,
+    )?;
+    Ok(VoterProfile::new(nonce, *voter))
+}
+
+/// Returns the maximum number of new votes a voter can cast during a single epoch, read from the
+/// Config account. The default applies as long as the Config hasn't been created
+pub fn get_max_new_votes_per_epoch(
+    program_id: &Pubkey,
+    config_account: &AccountInfo,
+) -> Result<u64, ProgramError> {
+    if config_account.data_is_empty() {
+        let (key, _) = Config::find_key(program_id);
+        check_account_key(config_account, &key)?;
+        return Ok(DEFAULT_MAX_NEW_VOTES_PER_EPOCH);
+    }
+    check_account_owner(config_account, program_id)?;
+    let config = Config::from_buffer(&config_account.data.borrow(), Tag::Config)?;
+    let key = Config::create_key(program_id, config.nonce)?;
+    check_account_key(config_account, &key)?;
+    Ok(config.max_new_votes_per_epoch)
+}
+
+/// Derives the address of a PDA from its `seeds`, the last of which is its stored nonce
+pub fn derive_key(seeds: &[&[u8]], program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+    Ok(Pubkey::create_program_address(seeds, program_id)?)
+}
+
+/// Returns true if `account` holds data of type `tag`
+pub fn has_tag(account: &AccountInfo, tag: Tag) -> bool {
+    account.data.borrow().get(..8) == Some(&(tag as u64).to_le_bytes()[..])
+}
+
+/// Closes a program account by transferring all of its lamports to `destination`
+pub fn close_account(account: &AccountInfo, destination: &AccountInfo) {
+    let lamports = **account.lamports.borrow();
+    **account.lamports.borrow_mut() = 0;
+    **destination.lamports.borrow_mut() += lamports;
+}
+
+/// Size of the offsets of a single signature in an Ed25519 program instruction
+const ED25519_SIGNATURE_OFFSETS_LEN: usize = 14;
+/// Start of the signature offsets in an Ed25519 program instruction
+const ED25519_SIGNATURE_OFFSETS_START: usize = 2;
+/// Length of an Ed25519 signature
+const ED25519_SIGNATURE_LEN: usize = 64;
+
+/// Checks that the instruction preceding the current one is a call to the Ed25519 program
+/// verifying the signature of `message` by `signer`. The Ed25519 program fails the whole
+/// transaction if the signature is invalid, so only its inputs have to be checked here.
+pub fn check_ed25519_signature(
+    instructions_sysvar: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> ProgramResult {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    if current_index == 0 {
+        msg!("The Ed25519 instruction must precede the current instruction");
+        return Err(SnsReputationError::InvalidRelaySignature.into());
+    }
+    let ed25519_index = current_index - 1;
+    let instruction = load_instruction_at_checked(ed25519_index as usize, instructions_sysvar)?;
+    if instruction.program_id != ed25519_program::ID {
+        msg!("The instruction preceding the current one is not an Ed25519 instruction");
+        return Err(SnsReputationError::InvalidRelaySignature.into());
+    }
+    let data = instruction.data;
+
+    // A single signature whose data is stored in the Ed25519 instruction itself
+    if data.len() < ED25519_SIGNATURE_OFFSETS_START + ED25519_SIGNATURE_OFFSETS_LEN || data[0] != 1
+    {
+        return Err(SnsReputationError::InvalidRelaySignature.into());
+    }
+    let offsets = &data[ED25519_SIGNATURE_OFFSETS_START
+        ..ED25519_SIGNATURE_OFFSETS_START + ED25519_SIGNATURE_OFFSETS_LEN];
+    let read_u16 = |i: usize| u16::from_le_bytes([offsets[i], offsets[i + 1]]);
+    let signature_offset = read_u16(0) as usize;
+    let public_key_offset = read_u16(4) as usize;
+    let message_offset = read_u16(8) as usize;
+    let message_size = read_u16(10) as usize;
+    for instruction_index in [read_u16(2), read_u16(6), read_u16(12)] {
+        if instruction_index != u16::MAX && instruction_index != ed25519_index {
+            return Err(SnsReputationError::InvalidRelaySignature.into());
+        }
+    }
+
+    let signed_key = data.get(public_key_offset..public_key_offset + 32);
+    let signed_message = data.get(message_offset..message_offset + message_size);
+    let has_signature = data
+        .get(signature_offset..signature_offset + ED25519_SIGNATURE_LEN)
+        .is_some();
+    if signed_key != Some(signer.as_ref()) || signed_message != Some(message) || !has_signature {
+        return Err(SnsReputationError::InvalidRelaySignature.into());
+    }
+    Ok(())
+}
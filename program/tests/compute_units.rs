@@ -0,0 +1,174 @@
+//! Compute unit benchmark of the vote instruction.
+//!
+//! Runs against the BPF build of the program, with the devnet feature so that votes don't need
+//! stake accounts. Changed votes are compared with what finding the PDAs of the existing
+//! accounts again, instead of verifying their stored nonces, would cost:
+//!
+//! ```text
+//! cargo build-bpf --features devnet
+//! cargo test --features test-bpf --test compute_units -- --nocapture
+//! ```
+#![cfg(feature = "test-bpf")]
+
+use sns_reputation::{
+    instruction::vote,
+    state::VoteValue,
+    state::{
+        config::Config, reputation_score::ReputationScore, user_vote::UserVote,
+        voter_profile::VoterProfile,
+    },
+};
+
+use {
+    solana_program::{instruction::Instruction, pubkey::Pubkey},
+    solana_program_test::{ProgramTest, ProgramTestContext},
+    solana_sdk::{signer::Signer, transaction::Transaction},
+};
+
+/// Default compute budget of a transaction
+const TRANSACTION_COMPUTE_BUDGET: u64 = 200_000;
+
+/// Number of votes a transaction should be able to hold
+const VOTES_PER_TRANSACTION: usize = 4;
+
+/// Compute units charged by the runtime for each `create_program_address`, which
+/// `find_program_address` repeats for every bump seed it tries
+const CREATE_PROGRAM_ADDRESS_UNITS: u64 = 1_500;
+
+/// Compute units `find_program_address` takes to find a PDA with the `nonce` bump seed, which is
+/// reached after trying every bump seed above it
+fn find_key_units(nonce: u8) -> u64 {
+    (u64::from(u8::MAX - nonce) + 1) * CREATE_PROGRAM_ADDRESS_UNITS
+}
+
+/// Nonces of the existing ReputationScore, UserVote and VoterProfile verified by a changed vote
+fn verified_nonces(program_id: &Pubkey, voter: Pubkey, votee: Pubkey) -> [u8; 3] {
+    [
+        ReputationScore::find_key(program_id, &votee).1,
+        UserVote::find_key(program_id, &(votee, voter)).1,
+        VoterProfile::find_key(program_id, &voter).1,
+    ]
+}
+
+fn vote_ix(
+    program_id: &Pubkey,
+    voter: Pubkey,
+    votee: Pubkey,
+    vote_value: VoteValue,
+) -> Instruction {
+    let (reputation_state, _) = ReputationScore::find_key(program_id, &votee);
+    let (user_vote_key, _) = UserVote::find_key(program_id, &(votee, voter));
+    let (voter_profile_key, _) = VoterProfile::find_key(program_id, &voter);
+    let (config_key, _) = Config::find_key(program_id);
+    let mut instruction = vote(
+        vote::Accounts {
+            system_program: &Pubkey::default(),
+            rent_payer: &voter,
+            voter: &voter,
+            reputation_state_account: &reputation_state,
+            user_vote_state_account: &user_vote_key,
+            voter_profile_account: &voter_profile_key,
+            config_account: &config_key,
+            voter_reputation_account: None,
+            voter_stake_accounts: &[],
+        },
+        vote::Params {
+            user_key: votee,
+            vote_value,
+        },
+    );
+    instruction.program_id = *program_id;
+    instruction
+}
+
+/// Processes the instructions and returns the compute units they consumed
+async fn units_consumed(
+    ctx: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    slot: u64,
+) -> u64 {
+    ctx.warp_to_slot(slot).unwrap();
+    let recent_blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer],
+        recent_blockhash,
+    );
+    let simulation = ctx
+        .banks_client
+        .simulate_transaction(transaction.clone())
+        .await
+        .unwrap();
+    ctx.banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    simulation.simulation_details.unwrap().units_consumed
+}
+
+#[tokio::test]
+async fn bench_vote_compute_units() {
+    let mut program_test = ProgramTest::new("sns_reputation", sns_reputation::ID, None);
+    program_test.prefer_bpf(true);
+    let mut ctx = program_test.start_with_context().await;
+    let voter = ctx.payer.pubkey();
+    let program_id = sns_reputation::ID;
+    let votees = (0..VOTES_PER_TRANSACTION)
+        .map(|_| Pubkey::new_unique())
+        .collect::<Vec<_>>();
+
+    // New votes find the PDAs they allocate
+    let new_vote = units_consumed(
+        &mut ctx,
+        &[vote_ix(&program_id, voter, votees[0], VoteValue::Upvote)],
+        2,
+    )
+    .await;
+
+    // Existing accounts are verified with their stored nonces
+    let changed_vote = units_consumed(
+        &mut ctx,
+        &[vote_ix(&program_id, voter, votees[0], VoteValue::Downvote)],
+        4,
+    )
+    .await;
+
+    let undone_vote = units_consumed(
+        &mut ctx,
+        &[vote_ix(&program_id, voter, votees[0], VoteValue::NoVote)],
+        6,
+    )
+    .await;
+
+    let instructions = votees
+        .iter()
+        .map(|votee| vote_ix(&program_id, voter, *votee, VoteValue::Upvote))
+        .collect::<Vec<_>>();
+    let new_votes = units_consumed(&mut ctx, &instructions, 8).await;
+
+    let instructions = votees
+        .iter()
+        .map(|votee| vote_ix(&program_id, voter, *votee, VoteValue::Downvote))
+        .collect::<Vec<_>>();
+    let changed_votes = units_consumed(&mut ctx, &instructions, 10).await;
+
+    // Finding the PDAs of the existing accounts again would replace each verification
+    let changed_vote_find_key = verified_nonces(&program_id, voter, votees[0])
+        .iter()
+        .map(|nonce| find_key_units(*nonce) - CREATE_PROGRAM_ADDRESS_UNITS)
+        .sum::<u64>()
+        + changed_vote;
+
+    println!("| Transaction                  | Compute units |");
+    println!("| ---------------------------- | ------------- |");
+    println!("| New vote                     | {new_vote:>13} |");
+    println!("| Changed vote                 | {changed_vote:>13} |");
+    println!("| Changed vote, finding PDAs   | {changed_vote_find_key:>13} |");
+    println!("| Undone vote                  | {undone_vote:>13} |");
+    println!("| {VOTES_PER_TRANSACTION} new votes                  | {new_votes:>13} |");
+    println!("| {VOTES_PER_TRANSACTION} changed votes              | {changed_votes:>13} |");
+
+    assert!(new_votes < TRANSACTION_COMPUTE_BUDGET);
+    assert!(changed_votes < TRANSACTION_COMPUTE_BUDGET);
+}
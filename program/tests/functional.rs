@@ -1,15 +1,42 @@
 use sns_reputation::{
     entrypoint::process_instruction,
     error::SnsReputationError,
-    instruction::vote,
-    state::{reputation_score::ReputationScore, user_vote::UserVote, Tag, VoteValue},
+    instruction::{
+        aggregate, close_expired_commitment, commit_vote, create_delegation, delegated_vote,
+        follow_curator, migrate, propagate_curator_vote, relay_vote, relay_vote_signature,
+        reveal_vote, revoke_delegation, set_config, snapshot, vote,
+    },
+    state::{
+        config::Config,
+        delegation::{Delegation, DelegationPermission},
+        follow::Follow,
+        reputation_score::{ReputationScore, CREDIBILITY_SCALE},
+        reputation_shard::{ReputationShard, NUM_SHARDS},
+        score_history::ScoreHistory,
+        user_vote::{UserVote, COMMIT_PERIOD_SLOTS, REVEAL_PERIOD_SLOTS},
+        voter_profile::VoterProfile,
+        Tag, VoteValue,
+    },
+    utils::MAX_STAKE_ACCOUNTS,
 };
+use std::convert::TryInto;
 use vote::Params;
 
 use {
-    solana_program::{instruction::InstructionError, pubkey::Pubkey},
+    borsh::BorshSerialize,
+    solana_program::{
+        account_info::AccountInfo,
+        bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        entrypoint::ProgramResult,
+        instruction::{AccountMeta, Instruction, InstructionError},
+        program::invoke_signed,
+        pubkey::Pubkey,
+        rent::Rent,
+    },
     solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext},
     solana_sdk::{
+        account::Account,
+        signature::Keypair,
         signer::Signer,
         transaction::{Transaction, TransactionError},
     },
@@ -17,6 +44,10 @@ use {
 
 pub mod common;
 
+fn config_key() -> Pubkey {
+    Config::find_key(&sns_reputation::ID).0
+}
+
 async fn process_vote(
     prg_test_ctx: &mut ProgramTestContext,
     payer: Pubkey,
@@ -31,12 +62,18 @@ async fn process_vote(
     *current_slot += 2;
     prg_test_ctx.warp_to_slot(*current_slot as u64).unwrap();
 
+    let (voter_profile_account, _) = VoterProfile::find_key(&sns_reputation::ID, &payer);
+
     let instruction = vote(
         vote::Accounts {
             system_program: &Pubkey::default(),
+            rent_payer: &payer,
             voter: &payer,
             reputation_state_account: &reputation_state_account,
             user_vote_state_account: &user_vote_state_account,
+            voter_profile_account: &voter_profile_account,
+            config_account: &config_key(),
+            voter_reputation_account: None,
             voter_stake_accounts: &[],
         },
         Params {
@@ -88,6 +125,20 @@ async fn fetch_user_vote(prg_test_ctx: &mut ProgramTestContext, user_vote_key: P
     UserVote::from_buffer(&user_vote_account.data, Tag::UserVote).unwrap()
 }
 
+async fn fetch_voter_profile(
+    prg_test_ctx: &mut ProgramTestContext,
+    voter_profile_key: Pubkey,
+) -> VoterProfile {
+    let voter_profile_account = prg_test_ctx
+        .banks_client
+        .get_account(voter_profile_key)
+        .await
+        .unwrap()
+        .unwrap();
+
+    VoterProfile::from_buffer(&voter_profile_account.data, Tag::VoterProfile).unwrap()
+}
+
 #[tokio::test]
 async fn test_voting() {
     let program_test = ProgramTest::new(
@@ -104,7 +155,9 @@ async fn test_voting() {
     let payer_pubkey = prg_test_ctx.payer.pubkey();
     let (reputation_state, reputation_state_nonce) =
         ReputationScore::find_key(&sns_reputation::ID, &votee);
-    let (user_vote_key, _) = UserVote::find_key(&sns_reputation::ID, &(votee, payer_pubkey));
+    let (user_vote_key, user_vote_nonce) =
+        UserVote::find_key(&sns_reputation::ID, &(votee, payer_pubkey));
+    let (voter_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &payer_pubkey);
     let mut current_slot: u8 = 1;
 
     // ============================================
@@ -131,6 +184,8 @@ async fn test_voting() {
             nonce: reputation_state_nonce,
             upvote: 1,
             downvote: 0,
+            upvoters: 1,
+            downvoters: 0,
         }
     );
 
@@ -141,9 +196,19 @@ async fn test_voting() {
             value: VoteValue::Upvote as i64,
             voter: payer_pubkey,
             votee,
+            rent_payer: payer_pubkey,
+            nonce: user_vote_nonce,
+            credibility_multiplier: CREDIBILITY_SCALE,
+            vote_slot: current_slot as u64,
+            reputation_account: reputation_state,
+            ..UserVote::default()
         }
     );
 
+    let parsed_voter_profile = fetch_voter_profile(&mut prg_test_ctx, voter_profile_key).await;
+    assert_eq!(parsed_voter_profile.active_votes, 1);
+    assert_eq!(parsed_voter_profile.upvotes_cast, 1);
+
     // ============================================
     // Now try to vote same value and check that program returned an error
 
@@ -198,7 +263,9 @@ async fn test_voting() {
         ReputationScore {
             nonce: reputation_state_nonce,
             upvote: 0,
-            downvote: 1
+            downvote: 1,
+            upvoters: 0,
+            downvoters: 1,
         }
     );
 
@@ -208,7 +275,13 @@ async fn test_voting() {
         UserVote {
             value: opposite_vote as i64,
             voter: payer_pubkey,
-            votee
+            votee,
+            rent_payer: payer_pubkey,
+            nonce: user_vote_nonce,
+            credibility_multiplier: CREDIBILITY_SCALE,
+            vote_slot: current_slot as u64,
+            reputation_account: reputation_state,
+            ..UserVote::default()
         },
         "❌ New opposite vote is incorrect!"
     );
@@ -242,7 +315,9 @@ async fn test_voting() {
         ReputationScore {
             nonce: reputation_state_nonce,
             upvote: 0,
-            downvote: 0
+            downvote: 0,
+            upvoters: 0,
+            downvoters: 0,
         }
     );
 
@@ -270,6 +345,15 @@ async fn test_voting() {
         "❌ Rent was not returned to the user!"
     );
 
+    // Check that the voter profile kept track of the whole history
+    let parsed_voter_profile = fetch_voter_profile(&mut prg_test_ctx, voter_profile_key).await;
+
+    assert_eq!(parsed_voter_profile.voter, payer_pubkey);
+    assert_eq!(parsed_voter_profile.active_votes, 0);
+    assert_eq!(parsed_voter_profile.upvotes_cast, 1);
+    assert_eq!(parsed_voter_profile.downvotes_cast, 1);
+    assert!(parsed_voter_profile.first_vote_slot < parsed_voter_profile.last_vote_slot);
+
     // ============================================
     // Test that user cannot vote with undo when there's nothing to undo
 
@@ -298,3 +382,1730 @@ async fn test_voting() {
         panic!();
     };
 }
+
+async fn send_instruction(
+    prg_test_ctx: &mut ProgramTestContext,
+    instruction: Instruction,
+    slot: u64,
+) -> Result<(), BanksClientError> {
+    send_instruction_with_signers(prg_test_ctx, instruction, slot, &[]).await
+}
+
+async fn send_instruction_with_signers(
+    prg_test_ctx: &mut ProgramTestContext,
+    instruction: Instruction,
+    slot: u64,
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    send_instructions(prg_test_ctx, &[instruction], slot, signers).await
+}
+
+async fn send_instructions(
+    prg_test_ctx: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    slot: u64,
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    prg_test_ctx.warp_to_slot(slot).unwrap();
+    let recent_blockhash = prg_test_ctx
+        .banks_client
+        .get_latest_blockhash()
+        .await
+        .unwrap();
+    let mut all_signers = vec![&prg_test_ctx.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&prg_test_ctx.payer.pubkey()),
+        &all_signers,
+        recent_blockhash,
+    );
+    prg_test_ctx
+        .banks_client
+        .process_transaction(transaction)
+        .await
+}
+
+fn assert_program_error(tx_result: Result<(), BanksClientError>, expected: SnsReputationError) {
+    if let Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(n),
+    ))) = tx_result
+    {
+        assert_eq!(n, expected as u32, "❌ Error is not correct!")
+    } else {
+        panic!("❌ Expected a program error, got {:?}", tx_result);
+    };
+}
+
+#[tokio::test]
+async fn test_commit_reveal() {
+    let program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let mut prg_test_ctx = program_test.start_with_context().await;
+
+    let votee = Pubkey::new_unique();
+    let expired_votee = Pubkey::new_unique();
+    let payer_pubkey = prg_test_ctx.payer.pubkey();
+    let (reputation_state, _) = ReputationScore::find_key(&sns_reputation::ID, &votee);
+    let (user_vote_key, _) = UserVote::find_key(&sns_reputation::ID, &(votee, payer_pubkey));
+    let (expired_user_vote_key, _) =
+        UserVote::find_key(&sns_reputation::ID, &(expired_votee, payer_pubkey));
+    let (voter_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &payer_pubkey);
+    let salt = [42; 32];
+
+    let commit_ix = |votee: Pubkey, user_vote_state_account: Pubkey| {
+        commit_vote(
+            commit_vote::Accounts {
+                system_program: &Pubkey::default(),
+                rent_payer: &payer_pubkey,
+                voter: &payer_pubkey,
+                user_vote_state_account: &user_vote_state_account,
+                voter_profile_account: &voter_profile_key,
+                config_account: &config_key(),
+            },
+            commit_vote::Params {
+                user_key: votee,
+                commitment: UserVote::compute_commitment(
+                    &votee,
+                    &payer_pubkey,
+                    VoteValue::Upvote,
+                    &salt,
+                ),
+            },
+        )
+    };
+    let reveal_ix = |salt: [u8; 32]| {
+        reveal_vote(
+            reveal_vote::Accounts {
+                system_program: &Pubkey::default(),
+                rent_payer: &payer_pubkey,
+                voter: &payer_pubkey,
+                reputation_state_account: &reputation_state,
+                user_vote_state_account: &user_vote_key,
+                voter_profile_account: &voter_profile_key,
+                voter_reputation_account: None,
+                voter_stake_accounts: &[],
+            },
+            reveal_vote::Params {
+                user_key: votee,
+                vote_value: VoteValue::Upvote,
+                salt,
+            },
+        )
+    };
+
+    // ============================================
+    // Commit to two votes, the score is not affected
+
+    let commit_slot = 10;
+    send_instruction(
+        &mut prg_test_ctx,
+        commit_ix(votee, user_vote_key),
+        commit_slot,
+    )
+    .await
+    .unwrap();
+    send_instruction(
+        &mut prg_test_ctx,
+        commit_ix(expired_votee, expired_user_vote_key),
+        commit_slot + 2,
+    )
+    .await
+    .unwrap();
+
+    let reputation_account = prg_test_ctx
+        .banks_client
+        .get_account(reputation_state)
+        .await
+        .unwrap();
+    assert!(reputation_account.is_none());
+
+    let parsed_user_vote = fetch_user_vote(&mut prg_test_ctx, user_vote_key).await;
+    assert_eq!(parsed_user_vote.value, 0);
+    assert!(parsed_user_vote.is_pending_commitment());
+
+    // ============================================
+    // The vote can neither be revealed before the end of the commit period nor changed
+
+    let tx_result = send_instruction(&mut prg_test_ctx, reveal_ix(salt), commit_slot + 4).await;
+    assert_program_error(tx_result, SnsReputationError::OutsideRevealWindow);
+
+    let instruction = vote(
+        vote::Accounts {
+            system_program: &Pubkey::default(),
+            rent_payer: &payer_pubkey,
+            voter: &payer_pubkey,
+            reputation_state_account: &reputation_state,
+            user_vote_state_account: &user_vote_key,
+            voter_profile_account: &voter_profile_key,
+            config_account: &config_key(),
+            voter_reputation_account: None,
+            voter_stake_accounts: &[],
+        },
+        vote::Params {
+            user_key: votee,
+            vote_value: VoteValue::Downvote,
+        },
+    );
+    let tx_result = send_instruction(&mut prg_test_ctx, instruction, commit_slot + 6).await;
+    assert_program_error(tx_result, SnsReputationError::PendingCommitment);
+
+    // ============================================
+    // Reveal the vote during the reveal window
+
+    let reveal_slot = commit_slot + COMMIT_PERIOD_SLOTS + 2;
+    let tx_result = send_instruction(&mut prg_test_ctx, reveal_ix([0; 32]), reveal_slot).await;
+    assert_program_error(tx_result, SnsReputationError::InvalidCommitment);
+
+    send_instruction(&mut prg_test_ctx, reveal_ix(salt), reveal_slot + 2)
+        .await
+        .unwrap();
+
+    let parsed_reputation_score = fetch_reputation_score(&mut prg_test_ctx, reputation_state).await;
+    assert_eq!(parsed_reputation_score.upvote, 1);
+    assert_eq!(parsed_reputation_score.downvote, 0);
+
+    let parsed_user_vote = fetch_user_vote(&mut prg_test_ctx, user_vote_key).await;
+    assert_eq!(parsed_user_vote.value, VoteValue::Upvote as i64);
+    assert!(!parsed_user_vote.is_pending_commitment());
+
+    let parsed_voter_profile = fetch_voter_profile(&mut prg_test_ctx, voter_profile_key).await;
+    assert_eq!(parsed_voter_profile.active_votes, 1);
+    assert_eq!(parsed_voter_profile.upvotes_cast, 1);
+
+    // ============================================
+    // Anyone can clean up the commitment that was never revealed, once it expired
+
+    let close_ix = close_expired_commitment(
+        close_expired_commitment::Accounts {
+            rent_payer: &payer_pubkey,
+            user_vote_state_account: &expired_user_vote_key,
+        },
+        close_expired_commitment::Params {},
+    );
+    let tx_result = send_instruction(&mut prg_test_ctx, close_ix.clone(), reveal_slot + 4).await;
+    assert_program_error(tx_result, SnsReputationError::CommitmentNotExpired);
+
+    let expiry_slot = commit_slot + COMMIT_PERIOD_SLOTS + REVEAL_PERIOD_SLOTS + 4;
+    send_instruction(&mut prg_test_ctx, close_ix, expiry_slot)
+        .await
+        .unwrap();
+
+    let expired_user_vote_account = prg_test_ctx
+        .banks_client
+        .get_account(expired_user_vote_key)
+        .await
+        .unwrap();
+    assert!(expired_user_vote_account.is_none());
+
+    // ============================================
+    // A reveal can land in the shard of the voter, like a direct vote
+
+    let sharded_votee = Pubkey::new_unique();
+    let (sharded_user_vote_key, _) =
+        UserVote::find_key(&sns_reputation::ID, &(sharded_votee, payer_pubkey));
+    let (shard_key, _) = ReputationShard::find_key(
+        &sns_reputation::ID,
+        &sharded_votee,
+        ReputationShard::shard_index(&payer_pubkey),
+    );
+    let sharded_commit_slot = expiry_slot + 2;
+    send_instruction(
+        &mut prg_test_ctx,
+        commit_ix(sharded_votee, sharded_user_vote_key),
+        sharded_commit_slot,
+    )
+    .await
+    .unwrap();
+
+    let sharded_reveal_ix = reveal_vote(
+        reveal_vote::Accounts {
+            system_program: &Pubkey::default(),
+            rent_payer: &payer_pubkey,
+            voter: &payer_pubkey,
+            reputation_state_account: &shard_key,
+            user_vote_state_account: &sharded_user_vote_key,
+            voter_profile_account: &voter_profile_key,
+            voter_reputation_account: None,
+            voter_stake_accounts: &[],
+        },
+        reveal_vote::Params {
+            user_key: sharded_votee,
+            vote_value: VoteValue::Upvote,
+            salt,
+        },
+    );
+    send_instruction(
+        &mut prg_test_ctx,
+        sharded_reveal_ix,
+        sharded_commit_slot + COMMIT_PERIOD_SLOTS + 2,
+    )
+    .await
+    .unwrap();
+
+    let shard_account = prg_test_ctx
+        .banks_client
+        .get_account(shard_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let parsed_shard =
+        ReputationShard::from_buffer(&shard_account.data, Tag::ReputationShard).unwrap();
+    assert_eq!((parsed_shard.upvote, parsed_shard.downvote), (1, 0));
+    let (sharded_votee_score, _) = ReputationScore::find_key(&sns_reputation::ID, &sharded_votee);
+    let sharded_votee_score_account = prg_test_ctx
+        .banks_client
+        .get_account(sharded_votee_score)
+        .await
+        .unwrap();
+    assert!(sharded_votee_score_account.is_none());
+    let parsed_user_vote = fetch_user_vote(&mut prg_test_ctx, sharded_user_vote_key).await;
+    assert_eq!(parsed_user_vote.reputation_account, shard_key);
+}
+
+#[tokio::test]
+async fn test_delegated_voting() {
+    let mut program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let delegate = Keypair::new();
+    program_test.add_account(
+        delegate.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let mut prg_test_ctx = program_test.start_with_context().await;
+
+    let votee = Pubkey::new_unique();
+    let staker = prg_test_ctx.payer.pubkey();
+    let (delegation_key, _) =
+        Delegation::find_key(&sns_reputation::ID, &staker, &delegate.pubkey());
+    let (reputation_state, _) = ReputationScore::find_key(&sns_reputation::ID, &votee);
+    let (user_vote_key, _) = UserVote::find_key(&sns_reputation::ID, &(votee, staker));
+    let (voter_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &staker);
+
+    let delegated_vote_ix = |vote_value: VoteValue| {
+        delegated_vote(
+            delegated_vote::Accounts {
+                system_program: &Pubkey::default(),
+                rent_payer: &delegate.pubkey(),
+                delegate: &delegate.pubkey(),
+                staker: &staker,
+                delegation_account: &delegation_key,
+                reputation_state_account: &reputation_state,
+                user_vote_state_account: &user_vote_key,
+                voter_profile_account: &voter_profile_key,
+                config_account: &config_key(),
+                staker_reputation_account: None,
+                staker_stake_accounts: &[],
+            },
+            delegated_vote::Params {
+                user_key: votee,
+                vote_value,
+            },
+        )
+    };
+
+    // ============================================
+    // The staker allows the delegate to upvote only
+
+    let instruction = create_delegation(
+        create_delegation::Accounts {
+            system_program: &Pubkey::default(),
+            staker: &staker,
+            delegate: &delegate.pubkey(),
+            delegation_account: &delegation_key,
+        },
+        create_delegation::Params {
+            expiry: 0,
+            permissions: DelegationPermission::Upvote as u8,
+        },
+    );
+    send_instruction(&mut prg_test_ctx, instruction, 2)
+        .await
+        .unwrap();
+
+    let tx_result = send_instruction_with_signers(
+        &mut prg_test_ctx,
+        delegated_vote_ix(VoteValue::Downvote),
+        4,
+        &[&delegate],
+    )
+    .await;
+    assert_program_error(tx_result, SnsReputationError::DelegationNotAllowed);
+
+    send_instruction_with_signers(
+        &mut prg_test_ctx,
+        delegated_vote_ix(VoteValue::Upvote),
+        6,
+        &[&delegate],
+    )
+    .await
+    .unwrap();
+
+    // The vote is attributed to the staker
+    let parsed_user_vote = fetch_user_vote(&mut prg_test_ctx, user_vote_key).await;
+    assert_eq!(parsed_user_vote.voter, staker);
+    assert_eq!(parsed_user_vote.value, VoteValue::Upvote as i64);
+
+    let parsed_reputation_score = fetch_reputation_score(&mut prg_test_ctx, reputation_state).await;
+    assert_eq!(parsed_reputation_score.upvote, 1);
+
+    // ============================================
+    // The delegate can't remove a downvote cast by the staker, to undo or change it
+
+    let other_votee = Pubkey::new_unique();
+    let (other_reputation_state, _) = ReputationScore::find_key(&sns_reputation::ID, &other_votee);
+    let (other_user_vote_key, _) = UserVote::find_key(&sns_reputation::ID, &(other_votee, staker));
+    let mut current_slot = 6;
+    process_vote(
+        &mut prg_test_ctx,
+        staker,
+        other_reputation_state,
+        other_user_vote_key,
+        VoteValue::Downvote,
+        other_votee,
+        &mut current_slot,
+    )
+    .await
+    .unwrap();
+
+    for (vote_value, slot) in [(VoteValue::NoVote, 10), (VoteValue::Upvote, 12)] {
+        let instruction = delegated_vote(
+            delegated_vote::Accounts {
+                system_program: &Pubkey::default(),
+                rent_payer: &delegate.pubkey(),
+                delegate: &delegate.pubkey(),
+                staker: &staker,
+                delegation_account: &delegation_key,
+                reputation_state_account: &other_reputation_state,
+                user_vote_state_account: &other_user_vote_key,
+                voter_profile_account: &voter_profile_key,
+                config_account: &config_key(),
+                staker_reputation_account: None,
+                staker_stake_accounts: &[],
+            },
+            delegated_vote::Params {
+                user_key: other_votee,
+                vote_value,
+            },
+        );
+        let tx_result =
+            send_instruction_with_signers(&mut prg_test_ctx, instruction, slot, &[&delegate]).await;
+        assert_program_error(tx_result, SnsReputationError::DelegationNotAllowed);
+    }
+
+    // ============================================
+    // Once revoked, the delegation cannot be used anymore
+
+    let instruction = revoke_delegation(
+        revoke_delegation::Accounts {
+            staker: &staker,
+            delegation_account: &delegation_key,
+        },
+        revoke_delegation::Params {},
+    );
+    send_instruction(&mut prg_test_ctx, instruction, 14)
+        .await
+        .unwrap();
+
+    let tx_result = send_instruction_with_signers(
+        &mut prg_test_ctx,
+        delegated_vote_ix(VoteValue::NoVote),
+        16,
+        &[&delegate],
+    )
+    .await;
+    assert!(tx_result.is_err());
+}
+
+#[tokio::test]
+async fn test_curator_following() {
+    let mut program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let curator = Keypair::new();
+    program_test.add_account(
+        curator.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let mut prg_test_ctx = program_test.start_with_context().await;
+
+    let votee = Pubkey::new_unique();
+    let follower = prg_test_ctx.payer.pubkey();
+    let (follow_key, _) = Follow::find_key(&sns_reputation::ID, &follower);
+    let (reputation_state, _) = ReputationScore::find_key(&sns_reputation::ID, &votee);
+    let (curator_user_vote_key, _) =
+        UserVote::find_key(&sns_reputation::ID, &(votee, curator.pubkey()));
+    let (curator_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &curator.pubkey());
+    let (follower_user_vote_key, _) = UserVote::find_key(&sns_reputation::ID, &(votee, follower));
+    let (follower_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &follower);
+
+    let vote_ix = |voter: Pubkey, user_vote: Pubkey, profile: Pubkey, vote_value: VoteValue| {
+        vote(
+            vote::Accounts {
+                system_program: &Pubkey::default(),
+                rent_payer: &voter,
+                voter: &voter,
+                reputation_state_account: &reputation_state,
+                user_vote_state_account: &user_vote,
+                voter_profile_account: &profile,
+                config_account: &config_key(),
+                voter_reputation_account: None,
+                voter_stake_accounts: &[],
+            },
+            vote::Params {
+                user_key: votee,
+                vote_value,
+            },
+        )
+    };
+    let propagate_ix = || {
+        propagate_curator_vote(
+            propagate_curator_vote::Accounts {
+                system_program: &Pubkey::default(),
+                rent_payer: &follower,
+                follower: &follower,
+                follow_account: &follow_key,
+                curator_user_vote_account: &curator_user_vote_key,
+                reputation_state_account: &reputation_state,
+                user_vote_state_account: &follower_user_vote_key,
+                voter_profile_account: &follower_profile_key,
+                config_account: &config_key(),
+                follower_reputation_account: None,
+                follower_stake_accounts: &[],
+            },
+            propagate_curator_vote::Params { user_key: votee },
+        )
+    };
+
+    // ============================================
+    // The curator upvotes and the follower follows the curator
+
+    send_instruction_with_signers(
+        &mut prg_test_ctx,
+        vote_ix(
+            curator.pubkey(),
+            curator_user_vote_key,
+            curator_profile_key,
+            VoteValue::Upvote,
+        ),
+        2,
+        &[&curator],
+    )
+    .await
+    .unwrap();
+
+    let instruction = follow_curator(
+        follow_curator::Accounts {
+            system_program: &Pubkey::default(),
+            follower: &follower,
+            follow_account: &follow_key,
+        },
+        follow_curator::Params {
+            curator: curator.pubkey(),
+        },
+    );
+    send_instruction(&mut prg_test_ctx, instruction, 4)
+        .await
+        .unwrap();
+
+    // ============================================
+    // The curator's vote is propagated to the follower
+
+    send_instruction(&mut prg_test_ctx, propagate_ix(), 6)
+        .await
+        .unwrap();
+
+    let parsed_user_vote = fetch_user_vote(&mut prg_test_ctx, follower_user_vote_key).await;
+    assert_eq!(parsed_user_vote.value, VoteValue::Upvote as i64);
+    assert_eq!(parsed_user_vote.followed_curator, curator.pubkey());
+
+    let parsed_reputation_score = fetch_reputation_score(&mut prg_test_ctx, reputation_state).await;
+    assert_eq!(parsed_reputation_score.upvote, 2);
+
+    // ============================================
+    // The curator changes its mind, and so does the follower
+
+    send_instruction_with_signers(
+        &mut prg_test_ctx,
+        vote_ix(
+            curator.pubkey(),
+            curator_user_vote_key,
+            curator_profile_key,
+            VoteValue::Downvote,
+        ),
+        8,
+        &[&curator],
+    )
+    .await
+    .unwrap();
+    send_instruction(&mut prg_test_ctx, propagate_ix(), 10)
+        .await
+        .unwrap();
+
+    let parsed_reputation_score = fetch_reputation_score(&mut prg_test_ctx, reputation_state).await;
+    assert_eq!(parsed_reputation_score.upvote, 0);
+    assert_eq!(parsed_reputation_score.downvote, 2);
+
+    // ============================================
+    // The follower overrides the curator on this votee
+
+    send_instruction(
+        &mut prg_test_ctx,
+        vote_ix(
+            follower,
+            follower_user_vote_key,
+            follower_profile_key,
+            VoteValue::Upvote,
+        ),
+        12,
+    )
+    .await
+    .unwrap();
+
+    let parsed_user_vote = fetch_user_vote(&mut prg_test_ctx, follower_user_vote_key).await;
+    assert_eq!(parsed_user_vote.value, VoteValue::Upvote as i64);
+    assert!(!parsed_user_vote.is_followed());
+
+    let tx_result = send_instruction(&mut prg_test_ctx, propagate_ix(), 14).await;
+    assert_program_error(tx_result, SnsReputationError::FollowerOverride);
+}
+
+#[tokio::test]
+async fn test_sponsored_voting() {
+    let program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let mut prg_test_ctx = program_test.start_with_context().await;
+
+    // The voter has no lamports, all accounts are funded by a sponsor
+    let voter = Keypair::new();
+    let votee = Pubkey::new_unique();
+    let rent_payer = prg_test_ctx.payer.pubkey();
+    let (reputation_state, _) = ReputationScore::find_key(&sns_reputation::ID, &votee);
+    let (user_vote_key, _) = UserVote::find_key(&sns_reputation::ID, &(votee, voter.pubkey()));
+    let (voter_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &voter.pubkey());
+
+    let instruction = vote(
+        vote::Accounts {
+            system_program: &Pubkey::default(),
+            rent_payer: &rent_payer,
+            voter: &voter.pubkey(),
+            reputation_state_account: &reputation_state,
+            user_vote_state_account: &user_vote_key,
+            voter_profile_account: &voter_profile_key,
+            config_account: &config_key(),
+            voter_reputation_account: None,
+            voter_stake_accounts: &[],
+        },
+        vote::Params {
+            user_key: votee,
+            vote_value: VoteValue::Downvote,
+        },
+    );
+    send_instruction_with_signers(&mut prg_test_ctx, instruction, 2, &[&voter])
+        .await
+        .unwrap();
+
+    let parsed_user_vote = fetch_user_vote(&mut prg_test_ctx, user_vote_key).await;
+    assert_eq!(parsed_user_vote.voter, voter.pubkey());
+    assert_eq!(parsed_user_vote.rent_payer, rent_payer);
+
+    let parsed_reputation_score = fetch_reputation_score(&mut prg_test_ctx, reputation_state).await;
+    assert_eq!(parsed_reputation_score.downvote, 1);
+
+    // ============================================
+    // Undoing the vote refunds the sponsor, not the voter
+
+    let undo_ix = |rent_payer: Pubkey| {
+        vote(
+            vote::Accounts {
+                system_program: &Pubkey::default(),
+                rent_payer: &rent_payer,
+                voter: &voter.pubkey(),
+                reputation_state_account: &reputation_state,
+                user_vote_state_account: &user_vote_key,
+                voter_profile_account: &voter_profile_key,
+                config_account: &config_key(),
+                voter_reputation_account: None,
+                voter_stake_accounts: &[],
+            },
+            vote::Params {
+                user_key: votee,
+                vote_value: VoteValue::NoVote,
+            },
+        )
+    };
+
+    let tx_result =
+        send_instruction_with_signers(&mut prg_test_ctx, undo_ix(voter.pubkey()), 4, &[&voter])
+            .await;
+    assert!(tx_result.is_err());
+
+    send_instruction_with_signers(&mut prg_test_ctx, undo_ix(rent_payer), 6, &[&voter])
+        .await
+        .unwrap();
+
+    let voter_account = prg_test_ctx
+        .banks_client
+        .get_account(voter.pubkey())
+        .await
+        .unwrap();
+    assert!(voter_account.is_none());
+}
+
+#[tokio::test]
+async fn test_relayed_voting() {
+    let program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let mut prg_test_ctx = program_test.start_with_context().await;
+
+    // The voter only signs messages, the relayer submits and pays for everything
+    let voter = Keypair::new();
+    let votee = Pubkey::new_unique();
+    let relayer = prg_test_ctx.payer.pubkey();
+    let (reputation_state, _) = ReputationScore::find_key(&sns_reputation::ID, &votee);
+    let (user_vote_key, _) = UserVote::find_key(&sns_reputation::ID, &(votee, voter.pubkey()));
+    let (voter_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &voter.pubkey());
+
+    let relay_ixs = |signer: &Keypair, vote_value: VoteValue, nonce: u64, expiry: i64| {
+        let message = relay_vote::VoteMessage {
+            program_id: sns_reputation::ID,
+            voter: voter.pubkey(),
+            user_key: votee,
+            vote_value,
+            nonce,
+            expiry,
+        }
+        .to_bytes();
+        let signature = signer.sign_message(&message);
+        vec![
+            relay_vote_signature(
+                &signer.pubkey(),
+                signature.as_ref().try_into().unwrap(),
+                &message,
+            ),
+            relay_vote(
+                relay_vote::Accounts {
+                    system_program: &Pubkey::default(),
+                    instructions_sysvar: &solana_program::sysvar::instructions::ID,
+                    rent_payer: &relayer,
+                    voter: &voter.pubkey(),
+                    reputation_state_account: &reputation_state,
+                    user_vote_state_account: &user_vote_key,
+                    voter_profile_account: &voter_profile_key,
+                    config_account: &config_key(),
+                    voter_reputation_account: None,
+                    voter_stake_accounts: &[],
+                },
+                relay_vote::Params {
+                    user_key: votee,
+                    vote_value,
+                    nonce,
+                    expiry,
+                },
+            ),
+        ]
+    };
+
+    // A message signed by someone else is rejected
+    let tx_result = send_instructions(
+        &mut prg_test_ctx,
+        &relay_ixs(&Keypair::new(), VoteValue::Upvote, 0, i64::MAX),
+        2,
+        &[],
+    )
+    .await;
+    assert!(tx_result.is_err());
+
+    // An expired message is rejected
+    let tx_result = send_instructions(
+        &mut prg_test_ctx,
+        &relay_ixs(&voter, VoteValue::Upvote, 0, 0),
+        4,
+        &[],
+    )
+    .await;
+    assert!(tx_result.is_err());
+
+    send_instructions(
+        &mut prg_test_ctx,
+        &relay_ixs(&voter, VoteValue::Upvote, 0, i64::MAX),
+        6,
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let parsed_user_vote = fetch_user_vote(&mut prg_test_ctx, user_vote_key).await;
+    assert_eq!(parsed_user_vote.voter, voter.pubkey());
+    assert_eq!(parsed_user_vote.rent_payer, relayer);
+
+    let parsed_voter_profile = fetch_voter_profile(&mut prg_test_ctx, voter_profile_key).await;
+    assert_eq!(parsed_voter_profile.relay_nonce, 1);
+
+    // ============================================
+    // The same message cannot be replayed
+
+    let tx_result = send_instructions(
+        &mut prg_test_ctx,
+        &relay_ixs(&voter, VoteValue::Downvote, 0, i64::MAX),
+        8,
+        &[],
+    )
+    .await;
+    assert!(tx_result.is_err());
+
+    send_instructions(
+        &mut prg_test_ctx,
+        &relay_ixs(&voter, VoteValue::Downvote, 1, i64::MAX),
+        10,
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let parsed_reputation_score = fetch_reputation_score(&mut prg_test_ctx, reputation_state).await;
+    assert_eq!(parsed_reputation_score.upvote, 0);
+    assert_eq!(parsed_reputation_score.downvote, 1);
+}
+
+#[tokio::test]
+async fn test_too_many_stake_accounts() {
+    let program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let mut prg_test_ctx = program_test.start_with_context().await;
+
+    let votee = Pubkey::new_unique();
+    let voter = prg_test_ctx.payer.pubkey();
+    let (reputation_state, _) = ReputationScore::find_key(&sns_reputation::ID, &votee);
+    let (user_vote_key, _) = UserVote::find_key(&sns_reputation::ID, &(votee, voter));
+    let (voter_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &voter);
+
+    // The same account is passed several times to keep the transaction small
+    let stake_account = Pubkey::new_unique();
+    let vote_ix = |stake_accounts_count: usize| {
+        vote(
+            vote::Accounts {
+                system_program: &Pubkey::default(),
+                rent_payer: &voter,
+                voter: &voter,
+                reputation_state_account: &reputation_state,
+                user_vote_state_account: &user_vote_key,
+                voter_profile_account: &voter_profile_key,
+                config_account: &config_key(),
+                voter_reputation_account: None,
+                voter_stake_accounts: &vec![stake_account; stake_accounts_count],
+            },
+            Params {
+                user_key: votee,
+                vote_value: VoteValue::Upvote,
+            },
+        )
+    };
+
+    let tx_result = send_instruction(&mut prg_test_ctx, vote_ix(MAX_STAKE_ACCOUNTS + 1), 2).await;
+    assert_program_error(tx_result, SnsReputationError::TooManyStakeAccounts);
+
+    send_instruction(&mut prg_test_ctx, vote_ix(MAX_STAKE_ACCOUNTS), 4)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_sharded_voting() {
+    let program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let mut prg_test_ctx = program_test.start_with_context().await;
+
+    let votee = Pubkey::new_unique();
+    let voter = prg_test_ctx.payer.pubkey();
+    let shard = ReputationShard::shard_index(&voter);
+    let (shard_key, _) = ReputationShard::find_key(&sns_reputation::ID, &votee, shard);
+    let (reputation_state, _) = ReputationScore::find_key(&sns_reputation::ID, &votee);
+    let (user_vote_key, _) = UserVote::find_key(&sns_reputation::ID, &(votee, voter));
+    let (voter_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &voter);
+
+    let vote_ix = |reputation_state_account: Pubkey, vote_value: VoteValue| {
+        vote(
+            vote::Accounts {
+                system_program: &Pubkey::default(),
+                rent_payer: &voter,
+                voter: &voter,
+                reputation_state_account: &reputation_state_account,
+                user_vote_state_account: &user_vote_key,
+                voter_profile_account: &voter_profile_key,
+                config_account: &config_key(),
+                voter_reputation_account: None,
+                voter_stake_accounts: &[],
+            },
+            Params {
+                user_key: votee,
+                vote_value,
+            },
+        )
+    };
+
+    // Only the voter's own shard is accepted
+    let (other_shard_key, _) =
+        ReputationShard::find_key(&sns_reputation::ID, &votee, (shard + 1) % NUM_SHARDS);
+    let tx_result = send_instruction(
+        &mut prg_test_ctx,
+        vote_ix(other_shard_key, VoteValue::Upvote),
+        2,
+    )
+    .await;
+    assert!(tx_result.is_err());
+
+    send_instruction(&mut prg_test_ctx, vote_ix(shard_key, VoteValue::Upvote), 4)
+        .await
+        .unwrap();
+
+    let shard_account = prg_test_ctx
+        .banks_client
+        .get_account(shard_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let parsed_shard =
+        ReputationShard::from_buffer(&shard_account.data, Tag::ReputationShard).unwrap();
+    assert_eq!(parsed_shard.upvote, 1);
+    assert_eq!(parsed_shard.user_key, votee);
+
+    // ============================================
+    // Aggregate the shard into the ReputationScore
+
+    let aggregate_ix = aggregate(
+        aggregate::Accounts {
+            system_program: &Pubkey::default(),
+            rent_payer: &voter,
+            reputation_state_account: &reputation_state,
+            reputation_shard_accounts: &[shard_key],
+        },
+        aggregate::Params { user_key: votee },
+    );
+    send_instruction(&mut prg_test_ctx, aggregate_ix, 6)
+        .await
+        .unwrap();
+
+    let parsed_reputation_score = fetch_reputation_score(&mut prg_test_ctx, reputation_state).await;
+    assert_eq!(parsed_reputation_score.upvote, 1);
+
+    // Changing the vote after the aggregation leaves negative tallies in the shard
+    send_instruction(
+        &mut prg_test_ctx,
+        vote_ix(shard_key, VoteValue::Downvote),
+        8,
+    )
+    .await
+    .unwrap();
+
+    let shard_account = prg_test_ctx
+        .banks_client
+        .get_account(shard_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let parsed_shard =
+        ReputationShard::from_buffer(&shard_account.data, Tag::ReputationShard).unwrap();
+    assert_eq!((parsed_shard.upvote, parsed_shard.downvote), (-1, 1));
+
+    // Shards of other users are rejected
+    let other_votee = Pubkey::new_unique();
+    let (other_votee_score, _) = ReputationScore::find_key(&sns_reputation::ID, &other_votee);
+    let aggregate_ix = aggregate(
+        aggregate::Accounts {
+            system_program: &Pubkey::default(),
+            rent_payer: &voter,
+            reputation_state_account: &other_votee_score,
+            reputation_shard_accounts: &[shard_key],
+        },
+        aggregate::Params {
+            user_key: other_votee,
+        },
+    );
+    let tx_result = send_instruction(&mut prg_test_ctx, aggregate_ix, 10).await;
+    assert_program_error(tx_result, SnsReputationError::InvalidShard);
+
+    // ============================================
+    // The vote is undone in the shard it was tallied in, not in the ReputationScore
+
+    let parsed_user_vote = fetch_user_vote(&mut prg_test_ctx, user_vote_key).await;
+    assert_eq!(parsed_user_vote.reputation_account, shard_key);
+
+    let tx_result = send_instruction(
+        &mut prg_test_ctx,
+        vote_ix(reputation_state, VoteValue::NoVote),
+        12,
+    )
+    .await;
+    assert_program_error(tx_result, SnsReputationError::WrongReputationAccount);
+
+    send_instruction(&mut prg_test_ctx, vote_ix(shard_key, VoteValue::NoVote), 14)
+        .await
+        .unwrap();
+
+    let aggregate_ix = aggregate(
+        aggregate::Accounts {
+            system_program: &Pubkey::default(),
+            rent_payer: &voter,
+            reputation_state_account: &reputation_state,
+            reputation_shard_accounts: &[shard_key],
+        },
+        aggregate::Params { user_key: votee },
+    );
+    send_instruction(&mut prg_test_ctx, aggregate_ix, 16)
+        .await
+        .unwrap();
+
+    let parsed_reputation_score = fetch_reputation_score(&mut prg_test_ctx, reputation_state).await;
+    assert_eq!(
+        (
+            parsed_reputation_score.upvote,
+            parsed_reputation_score.downvote,
+            parsed_reputation_score.upvoters,
+            parsed_reputation_score.downvoters
+        ),
+        (0, 0, 0, 0)
+    );
+}
+
+#[tokio::test]
+async fn test_migration() {
+    let mut program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let voter = Keypair::new();
+    let votee = Pubkey::new_unique();
+    let (reputation_state, reputation_nonce) =
+        ReputationScore::find_key(&sns_reputation::ID, &votee);
+    let (user_vote_key, user_vote_nonce) =
+        UserVote::find_key(&sns_reputation::ID, &(votee, voter.pubkey()));
+    let (voter_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &voter.pubkey());
+
+    program_test.add_account(
+        voter.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+
+    // Accounts created by the first version of the program
+    let mut reputation_data = (Tag::ReputationScore as u64).to_le_bytes().to_vec();
+    (reputation_nonce, 1u64, 0u64)
+        .serialize(&mut reputation_data)
+        .unwrap();
+    let mut user_vote_data = (Tag::UserVote as u64).to_le_bytes().to_vec();
+    (1i64, votee, voter.pubkey())
+        .serialize(&mut user_vote_data)
+        .unwrap();
+    for (key, data) in [
+        (reputation_state, reputation_data),
+        (user_vote_key, user_vote_data),
+    ] {
+        program_test.add_account(
+            key,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: sns_reputation::ID,
+                ..Account::default()
+            },
+        );
+    }
+    let mut prg_test_ctx = program_test.start_with_context().await;
+    let payer_pubkey = prg_test_ctx.payer.pubkey();
+
+    let vote_ix = vote(
+        vote::Accounts {
+            system_program: &Pubkey::default(),
+            rent_payer: &voter.pubkey(),
+            voter: &voter.pubkey(),
+            reputation_state_account: &reputation_state,
+            user_vote_state_account: &user_vote_key,
+            voter_profile_account: &voter_profile_key,
+            config_account: &config_key(),
+            voter_reputation_account: None,
+            voter_stake_accounts: &[],
+        },
+        Params {
+            user_key: votee,
+            vote_value: VoteValue::Downvote,
+        },
+    );
+    let migrate_ix = |state_account: &Pubkey| {
+        migrate(
+            migrate::Accounts {
+                system_program: &Pubkey::default(),
+                rent_payer: &payer_pubkey,
+                state_account,
+            },
+            migrate::Params {},
+        )
+    };
+
+    // Previous layouts are readable but have to be migrated before being updated
+    let parsed_user_vote = fetch_user_vote(&mut prg_test_ctx, user_vote_key).await;
+    assert_eq!(parsed_user_vote.value, 1);
+    let tx_result =
+        send_instruction_with_signers(&mut prg_test_ctx, vote_ix.clone(), 2, &[&voter]).await;
+    assert_program_error(tx_result, SnsReputationError::OutdatedAccountLayout);
+
+    send_instructions(
+        &mut prg_test_ctx,
+        &[migrate_ix(&reputation_state), migrate_ix(&user_vote_key)],
+        4,
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let reputation_account = prg_test_ctx
+        .banks_client
+        .get_account(reputation_state)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(ReputationScore::is_latest_layout(&reputation_account.data));
+    assert!(Rent::default().is_exempt(reputation_account.lamports, ReputationScore::space()));
+    let parsed_reputation_score = fetch_reputation_score(&mut prg_test_ctx, reputation_state).await;
+    assert_eq!(parsed_reputation_score.upvote, 1);
+
+    let user_vote_account = prg_test_ctx
+        .banks_client
+        .get_account(user_vote_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(UserVote::is_latest_layout(&user_vote_account.data));
+    let parsed_user_vote = fetch_user_vote(&mut prg_test_ctx, user_vote_key).await;
+    assert_eq!(
+        parsed_user_vote,
+        UserVote {
+            value: 1,
+            votee,
+            voter: voter.pubkey(),
+            nonce: user_vote_nonce,
+            credibility_multiplier: CREDIBILITY_SCALE,
+            ..UserVote::default()
+        }
+    );
+
+    // Migrating an account using the latest layout is a no-op
+    send_instruction(&mut prg_test_ctx, migrate_ix(&user_vote_key), 6)
+        .await
+        .unwrap();
+
+    send_instruction_with_signers(&mut prg_test_ctx, vote_ix, 8, &[&voter])
+        .await
+        .unwrap();
+    let parsed_reputation_score = fetch_reputation_score(&mut prg_test_ctx, reputation_state).await;
+    assert_eq!(
+        (
+            parsed_reputation_score.upvote,
+            parsed_reputation_score.downvote
+        ),
+        (0, 1)
+    );
+    // The upvote cast before the voters were counted was never part of the counts
+    assert_eq!(
+        (
+            parsed_reputation_score.upvoters,
+            parsed_reputation_score.downvoters
+        ),
+        (0, 1)
+    );
+}
+
+#[tokio::test]
+async fn test_snapshots() {
+    let program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let mut prg_test_ctx = program_test.start_with_context().await;
+    let epoch_schedule = prg_test_ctx.genesis_config().epoch_schedule;
+
+    let votee = Pubkey::new_unique();
+    let voter = prg_test_ctx.payer.pubkey();
+    let (reputation_state, _) = ReputationScore::find_key(&sns_reputation::ID, &votee);
+    let (score_history_key, _) = ScoreHistory::find_key(&sns_reputation::ID, &votee);
+    let (user_vote_key, _) = UserVote::find_key(&sns_reputation::ID, &(votee, voter));
+    let (voter_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &voter);
+
+    let vote_ix = |votee: Pubkey, reputation_state_account: Pubkey, vote_value: VoteValue| {
+        let (user_vote_key, _) = UserVote::find_key(&sns_reputation::ID, &(votee, voter));
+        vote(
+            vote::Accounts {
+                system_program: &Pubkey::default(),
+                rent_payer: &voter,
+                voter: &voter,
+                reputation_state_account: &reputation_state_account,
+                user_vote_state_account: &user_vote_key,
+                voter_profile_account: &voter_profile_key,
+                config_account: &config_key(),
+                voter_reputation_account: None,
+                voter_stake_accounts: &[],
+            },
+            Params {
+                user_key: votee,
+                vote_value,
+            },
+        )
+    };
+    // Snapshots need every shard of the user, created or not
+    let all_shards = |votee: Pubkey| {
+        (0..NUM_SHARDS)
+            .map(|shard| ReputationShard::find_key(&sns_reputation::ID, &votee, shard).0)
+            .collect::<Vec<_>>()
+    };
+    let snapshot_ix = |votee: Pubkey, shards: &[Pubkey]| {
+        snapshot(
+            snapshot::Accounts {
+                system_program: &Pubkey::default(),
+                rent_payer: &voter,
+                reputation_state_account: &ReputationScore::find_key(&sns_reputation::ID, &votee).0,
+                score_history_account: &ScoreHistory::find_key(&sns_reputation::ID, &votee).0,
+                reputation_shard_accounts: shards,
+            },
+            snapshot::Params { user_key: votee },
+        )
+    };
+
+    send_instruction(
+        &mut prg_test_ctx,
+        vote_ix(votee, reputation_state, VoteValue::Upvote),
+        2,
+    )
+    .await
+    .unwrap();
+    send_instruction(&mut prg_test_ctx, snapshot_ix(votee, &all_shards(votee)), 4)
+        .await
+        .unwrap();
+
+    // The vote is changed during the next epoch
+    let first_epoch = epoch_schedule.get_epoch(4);
+    let next_epoch_slot = epoch_schedule.get_first_slot_in_epoch(first_epoch + 1);
+    send_instruction(
+        &mut prg_test_ctx,
+        vote_ix(votee, reputation_state, VoteValue::Downvote),
+        next_epoch_slot + 2,
+    )
+    .await
+    .unwrap();
+    send_instruction(
+        &mut prg_test_ctx,
+        snapshot_ix(votee, &all_shards(votee)),
+        next_epoch_slot + 4,
+    )
+    .await
+    .unwrap();
+
+    let history_account = prg_test_ctx
+        .banks_client
+        .get_account(score_history_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let history = ScoreHistory::load(&history_account.data).unwrap();
+    assert_eq!(history.user_key, votee);
+    let series = history
+        .series(first_epoch + 1)
+        .iter()
+        .map(|s| {
+            (
+                u64::from(s.epoch),
+                u64::from(s.upvote),
+                u64::from(s.downvote),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(series, vec![(first_epoch, 1, 0), (first_epoch + 1, 0, 1)]);
+    assert!(
+        fetch_user_vote(&mut prg_test_ctx, user_vote_key)
+            .await
+            .value
+            < 0
+    );
+
+    // ============================================
+    // Unaggregated shards are counted, but only once and never partially
+
+    let sharded_votee = Pubkey::new_unique();
+    let shard = ReputationShard::shard_index(&voter);
+    let (shard_key, _) = ReputationShard::find_key(&sns_reputation::ID, &sharded_votee, shard);
+    send_instruction(
+        &mut prg_test_ctx,
+        vote_ix(sharded_votee, shard_key, VoteValue::Upvote),
+        next_epoch_slot + 6,
+    )
+    .await
+    .unwrap();
+
+    // A partial set of shards could leave out some of the votes
+    let tx_result = send_instruction(
+        &mut prg_test_ctx,
+        snapshot_ix(sharded_votee, &[shard_key]),
+        next_epoch_slot + 8,
+    )
+    .await;
+    assert_program_error(tx_result, SnsReputationError::InvalidShard);
+
+    let mut duplicated_shards = all_shards(sharded_votee);
+    duplicated_shards[((shard + 1) % NUM_SHARDS) as usize] = shard_key;
+    let tx_result = send_instruction(
+        &mut prg_test_ctx,
+        snapshot_ix(sharded_votee, &duplicated_shards),
+        next_epoch_slot + 10,
+    )
+    .await;
+    assert_program_error(tx_result, SnsReputationError::InvalidShard);
+
+    send_instruction(
+        &mut prg_test_ctx,
+        snapshot_ix(sharded_votee, &all_shards(sharded_votee)),
+        next_epoch_slot + 12,
+    )
+    .await
+    .unwrap();
+    let history_account = prg_test_ctx
+        .banks_client
+        .get_account(ScoreHistory::find_key(&sns_reputation::ID, &sharded_votee).0)
+        .await
+        .unwrap()
+        .unwrap();
+    let series = ScoreHistory::load(&history_account.data)
+        .unwrap()
+        .series(first_epoch + 1);
+    assert_eq!(series.len(), 1);
+    assert_eq!(u64::from(series[0].upvote), 1);
+}
+
+#[tokio::test]
+async fn test_credibility_weighting() {
+    let mut program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let voter = Keypair::new();
+    let votee = Pubkey::new_unique();
+    let other_user = Pubkey::new_unique();
+    let (reputation_state, _) = ReputationScore::find_key(&sns_reputation::ID, &votee);
+    let (user_vote_key, _) = UserVote::find_key(&sns_reputation::ID, &(votee, voter.pubkey()));
+    let (voter_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &voter.pubkey());
+    let (voter_reputation_key, voter_reputation_nonce) =
+        ReputationScore::find_key(&sns_reputation::ID, &voter.pubkey());
+    let (other_reputation_key, other_reputation_nonce) =
+        ReputationScore::find_key(&sns_reputation::ID, &other_user);
+
+    program_test.add_account(
+        voter.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    // The voter and another user are both well regarded
+    for (key, nonce) in [
+        (voter_reputation_key, voter_reputation_nonce),
+        (other_reputation_key, other_reputation_nonce),
+    ] {
+        let mut data = vec![0; ReputationScore::space()];
+        ReputationScore {
+            nonce,
+            upvote: 80,
+            downvote: 20,
+            upvoters: 80,
+            downvoters: 20,
+        }
+        .save(&mut data)
+        .unwrap();
+        program_test.add_account(
+            key,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: sns_reputation::ID,
+                ..Account::default()
+            },
+        );
+    }
+    let mut prg_test_ctx = program_test.start_with_context().await;
+
+    let vote_ix = |voter_reputation_account: Option<&Pubkey>, vote_value: VoteValue| {
+        vote(
+            vote::Accounts {
+                system_program: &Pubkey::default(),
+                rent_payer: &voter.pubkey(),
+                voter: &voter.pubkey(),
+                reputation_state_account: &reputation_state,
+                user_vote_state_account: &user_vote_key,
+                voter_profile_account: &voter_profile_key,
+                config_account: &config_key(),
+                voter_reputation_account,
+                voter_stake_accounts: &[],
+            },
+            Params {
+                user_key: votee,
+                vote_value,
+            },
+        )
+    };
+
+    // The ReputationScore has to be the voter's own
+    let tx_result = send_instruction_with_signers(
+        &mut prg_test_ctx,
+        vote_ix(Some(&other_reputation_key), VoteValue::Upvote),
+        2,
+        &[&voter],
+    )
+    .await;
+    assert!(tx_result.is_err());
+
+    // Votes cast without the ReputationScore are not weighted by the voter's credibility
+    send_instruction_with_signers(
+        &mut prg_test_ctx,
+        vote_ix(None, VoteValue::Upvote),
+        4,
+        &[&voter],
+    )
+    .await
+    .unwrap();
+    let user_vote = fetch_user_vote(&mut prg_test_ctx, user_vote_key).await;
+    assert_eq!(user_vote.credibility_multiplier, CREDIBILITY_SCALE);
+
+    // The multiplier used is recorded, the weight of 1 of devnet votes being rounded down
+    send_instruction_with_signers(
+        &mut prg_test_ctx,
+        vote_ix(Some(&voter_reputation_key), VoteValue::Downvote),
+        6,
+        &[&voter],
+    )
+    .await
+    .unwrap();
+    let user_vote = fetch_user_vote(&mut prg_test_ctx, user_vote_key).await;
+    assert_eq!(user_vote.credibility_multiplier, 12_111);
+    assert_eq!(user_vote.value, -1);
+    let parsed_reputation_score = fetch_reputation_score(&mut prg_test_ctx, reputation_state).await;
+    assert_eq!(
+        (
+            parsed_reputation_score.upvote,
+            parsed_reputation_score.downvote
+        ),
+        (0, 1)
+    );
+
+    // Revealed votes are weighted by the credibility of the voter as well
+    let revealed_votee = Pubkey::new_unique();
+    let (revealed_user_vote_key, _) =
+        UserVote::find_key(&sns_reputation::ID, &(revealed_votee, voter.pubkey()));
+    let salt = [3; 32];
+    let commit_ix = commit_vote(
+        commit_vote::Accounts {
+            system_program: &Pubkey::default(),
+            rent_payer: &voter.pubkey(),
+            voter: &voter.pubkey(),
+            user_vote_state_account: &revealed_user_vote_key,
+            voter_profile_account: &voter_profile_key,
+            config_account: &config_key(),
+        },
+        commit_vote::Params {
+            user_key: revealed_votee,
+            commitment: UserVote::compute_commitment(
+                &revealed_votee,
+                &voter.pubkey(),
+                VoteValue::Upvote,
+                &salt,
+            ),
+        },
+    );
+    send_instruction_with_signers(&mut prg_test_ctx, commit_ix, 8, &[&voter])
+        .await
+        .unwrap();
+    let reveal_ix = reveal_vote(
+        reveal_vote::Accounts {
+            system_program: &Pubkey::default(),
+            rent_payer: &voter.pubkey(),
+            voter: &voter.pubkey(),
+            reputation_state_account: &ReputationScore::find_key(
+                &sns_reputation::ID,
+                &revealed_votee,
+            )
+            .0,
+            user_vote_state_account: &revealed_user_vote_key,
+            voter_profile_account: &voter_profile_key,
+            voter_reputation_account: Some(&voter_reputation_key),
+            voter_stake_accounts: &[],
+        },
+        reveal_vote::Params {
+            user_key: revealed_votee,
+            vote_value: VoteValue::Upvote,
+            salt,
+        },
+    );
+    send_instruction_with_signers(
+        &mut prg_test_ctx,
+        reveal_ix,
+        8 + COMMIT_PERIOD_SLOTS + 2,
+        &[&voter],
+    )
+    .await
+    .unwrap();
+    let user_vote = fetch_user_vote(&mut prg_test_ctx, revealed_user_vote_key).await;
+    assert_eq!(user_vote.credibility_multiplier, 12_111);
+    assert_eq!(user_vote.value, 1);
+}
+
+#[tokio::test]
+async fn test_rate_limit() {
+    let mut program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    // The program is configured by its upgrade authority, stored in its ProgramData account
+    let upgrade_authority = Keypair::new();
+    let (program_data_key, _) =
+        Pubkey::find_program_address(&[sns_reputation::ID.as_ref()], &bpf_loader_upgradeable::ID);
+    program_test.add_account(
+        program_data_key,
+        Account {
+            lamports: 1_000_000_000,
+            data: bincode::serialize(&UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(upgrade_authority.pubkey()),
+            })
+            .unwrap(),
+            owner: bpf_loader_upgradeable::ID,
+            ..Account::default()
+        },
+    );
+    let mut prg_test_ctx = program_test.start_with_context().await;
+    let payer = prg_test_ctx.payer.pubkey();
+
+    let set_config_ix = |authority: Pubkey| {
+        set_config(
+            set_config::Accounts {
+                system_program: &Pubkey::default(),
+                fee_payer: &payer,
+                authority: &authority,
+                program_data: &program_data_key,
+                config_account: &config_key(),
+            },
+            set_config::Params {
+                max_new_votes_per_epoch: 2,
+            },
+        )
+    };
+
+    let impostor = Keypair::new();
+    let tx_result = send_instruction_with_signers(
+        &mut prg_test_ctx,
+        set_config_ix(impostor.pubkey()),
+        2,
+        &[&impostor],
+    )
+    .await;
+    assert_program_error(tx_result, SnsReputationError::InvalidAuthority);
+
+    send_instruction_with_signers(
+        &mut prg_test_ctx,
+        set_config_ix(upgrade_authority.pubkey()),
+        4,
+        &[&upgrade_authority],
+    )
+    .await
+    .unwrap();
+
+    // ============================================
+    // The third new vote of the epoch is rejected
+
+    let votees = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let vote_on = |votee: &Pubkey| {
+        (
+            ReputationScore::find_key(&sns_reputation::ID, votee).0,
+            UserVote::find_key(&sns_reputation::ID, &(*votee, payer)).0,
+        )
+    };
+    let mut current_slot = 4;
+    for votee in &votees[..2] {
+        let (reputation_state, user_vote_key) = vote_on(votee);
+        process_vote(
+            &mut prg_test_ctx,
+            payer,
+            reputation_state,
+            user_vote_key,
+            VoteValue::Upvote,
+            *votee,
+            &mut current_slot,
+        )
+        .await
+        .unwrap();
+    }
+
+    let (reputation_state, user_vote_key) = vote_on(&votees[2]);
+    let tx_result = process_vote(
+        &mut prg_test_ctx,
+        payer,
+        reputation_state,
+        user_vote_key,
+        VoteValue::Upvote,
+        votees[2],
+        &mut current_slot,
+    )
+    .await;
+    assert_program_error(tx_result, SnsReputationError::RateLimited);
+
+    // Existing votes can still be changed
+    let (reputation_state, user_vote_key) = vote_on(&votees[0]);
+    process_vote(
+        &mut prg_test_ctx,
+        payer,
+        reputation_state,
+        user_vote_key,
+        VoteValue::Downvote,
+        votees[0],
+        &mut current_slot,
+    )
+    .await
+    .unwrap();
+
+    let (voter_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &payer);
+    let voter_profile = fetch_voter_profile(&mut prg_test_ctx, voter_profile_key).await;
+    assert_eq!(voter_profile.rate_limit_new_votes, 2);
+}
+
+/// Seed of the PDA voting through `vault_process_instruction`
+const VAULT_SEED: &[u8] = b"vault";
+
+/// Program signing the instruction it is given for its vault PDA, as a multisig or a DAO
+/// treasury would. The first account is the program to invoke
+fn vault_process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let (vault, nonce) = Pubkey::find_program_address(&[VAULT_SEED], program_id);
+    let instruction = Instruction {
+        program_id: *accounts[0].key,
+        accounts: accounts[1..]
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer || *account.key == vault,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: instruction_data.to_vec(),
+    };
+    invoke_signed(&instruction, accounts, &[&[VAULT_SEED, &[nonce]]])
+}
+
+#[tokio::test]
+async fn test_pda_voting() {
+    let mut program_test = ProgramTest::new(
+        "sns_reputation",
+        sns_reputation::ID,
+        processor!(process_instruction),
+    );
+    let vault_program_id = Pubkey::new_unique();
+    program_test.add_program(
+        "vault",
+        vault_program_id,
+        processor!(vault_process_instruction),
+    );
+    let mut prg_test_ctx = program_test.start_with_context().await;
+
+    // The vault holds no lamports, the rent is paid by a regular account
+    let (vault, _) = Pubkey::find_program_address(&[VAULT_SEED], &vault_program_id);
+    let votee = Pubkey::new_unique();
+    let rent_payer = prg_test_ctx.payer.pubkey();
+    let (reputation_state, _) = ReputationScore::find_key(&sns_reputation::ID, &votee);
+    let (user_vote_key, _) = UserVote::find_key(&sns_reputation::ID, &(votee, vault));
+    let (voter_profile_key, _) = VoterProfile::find_key(&sns_reputation::ID, &vault);
+
+    let mut instruction = vote(
+        vote::Accounts {
+            system_program: &Pubkey::default(),
+            rent_payer: &rent_payer,
+            voter: &vault,
+            reputation_state_account: &reputation_state,
+            user_vote_state_account: &user_vote_key,
+            voter_profile_account: &voter_profile_key,
+            config_account: &config_key(),
+            voter_reputation_account: None,
+            voter_stake_accounts: &[],
+        },
+        vote::Params {
+            user_key: votee,
+            vote_value: VoteValue::Upvote,
+        },
+    );
+    // Only the vault program can sign for the vault
+    for account in instruction.accounts.iter_mut() {
+        if account.pubkey == vault {
+            account.is_signer = false;
+        }
+    }
+
+    let tx_result = send_instruction(&mut prg_test_ctx, instruction.clone(), 2).await;
+    assert!(matches!(
+        tx_result,
+        Err(BanksClientError::TransactionError(
+            TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+        ))
+    ));
+
+    let mut accounts = vec![AccountMeta::new_readonly(sns_reputation::ID, false)];
+    accounts.extend(instruction.accounts);
+    let cpi_instruction = Instruction {
+        program_id: vault_program_id,
+        accounts,
+        data: instruction.data,
+    };
+    send_instruction(&mut prg_test_ctx, cpi_instruction, 4)
+        .await
+        .unwrap();
+
+    let parsed_user_vote = fetch_user_vote(&mut prg_test_ctx, user_vote_key).await;
+    assert_eq!(parsed_user_vote.voter, vault);
+    assert_eq!(parsed_user_vote.rent_payer, rent_payer);
+    assert_eq!(parsed_user_vote.value, VoteValue::Upvote as i64);
+
+    let parsed_reputation_score = fetch_reputation_score(&mut prg_test_ctx, reputation_state).await;
+    assert_eq!(parsed_reputation_score.upvote, 1);
+}
@@ -1,4 +1,4 @@
-from typing import List
+from typing import List, Optional
 import borsh_construct as borsh
 from solana.transaction import TransactionInstruction, AccountMeta
 from solana.publickey import PublicKey
@@ -22,9 +22,13 @@ class VoteInstruction:
 		vote_value: int,
 programId: PublicKey,
 system_program: PublicKey,
+rent_payer: PublicKey,
 voter: PublicKey,
 reputation_state_account: PublicKey,
 user_vote_state_account: PublicKey,
+voter_profile_account: PublicKey,
+config_account: PublicKey,
+voter_reputation_account: Optional[PublicKey],
 voter_stake_accounts: List[PublicKey],
 ) -> TransactionInstruction:
 		data = self.serialize(
@@ -34,13 +38,574 @@ voter_stake_accounts: List[PublicKey],
 		keys: List[AccountMeta] = []
 		keys.append(AccountMeta(system_program,
 			False, False))
+		keys.append(AccountMeta(rent_payer,
+			False, True))
+		keys.append(AccountMeta(voter,
+			True, False))
+		keys.append(AccountMeta(reputation_state_account,
+			False, True))
+		keys.append(AccountMeta(user_vote_state_account,
+			False, True))
+		keys.append(AccountMeta(voter_profile_account,
+			False, True))
+		keys.append(AccountMeta(config_account,
+			False, False))
+		if voter_reputation_account is not None:
+			keys.append(AccountMeta(voter_reputation_account,
+			False, False))
+		for k in voter_stake_accounts:
+			keys.append(AccountMeta(k,
+			False, False))
+		return TransactionInstruction(keys, programId, data)
+class CommitVoteInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+		"user_key" / borsh.U8[32],
+		"commitment" / borsh.U8[32],
+	)
+	def serialize(self,
+		user_key: List[int],
+		commitment: List[int],
+	) -> str:
+		return self.schema.build({
+			"tag": 1,
+			"user_key": user_key,
+			"commitment": commitment,
+		})
+	def getInstruction(self,
+		user_key: List[int],
+		commitment: List[int],
+programId: PublicKey,
+system_program: PublicKey,
+rent_payer: PublicKey,
+voter: PublicKey,
+user_vote_state_account: PublicKey,
+voter_profile_account: PublicKey,
+config_account: PublicKey,
+) -> TransactionInstruction:
+		data = self.serialize(
+		user_key,
+		commitment,
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(system_program,
+			False, False))
+		keys.append(AccountMeta(rent_payer,
+			True, True))
 		keys.append(AccountMeta(voter,
+			True, False))
+		keys.append(AccountMeta(user_vote_state_account,
+			False, True))
+		keys.append(AccountMeta(voter_profile_account,
+			False, True))
+		keys.append(AccountMeta(config_account,
+			False, False))
+		return TransactionInstruction(keys, programId, data)
+class RevealVoteInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+		"user_key" / borsh.U8[32],
+		"vote_value" / borsh.U8,
+		"salt" / borsh.U8[32],
+	)
+	def serialize(self,
+		user_key: List[int],
+		vote_value: int,
+		salt: List[int],
+	) -> str:
+		return self.schema.build({
+			"tag": 2,
+			"user_key": user_key,
+			"vote_value": vote_value,
+			"salt": salt,
+		})
+	def getInstruction(self,
+		user_key: List[int],
+		vote_value: int,
+		salt: List[int],
+programId: PublicKey,
+system_program: PublicKey,
+rent_payer: PublicKey,
+voter: PublicKey,
+reputation_state_account: PublicKey,
+user_vote_state_account: PublicKey,
+voter_profile_account: PublicKey,
+voter_reputation_account: Optional[PublicKey],
+voter_stake_accounts: List[PublicKey],
+) -> TransactionInstruction:
+		data = self.serialize(
+		user_key,
+		vote_value,
+		salt,
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(system_program,
+			False, False))
+		keys.append(AccountMeta(rent_payer,
 			True, True))
+		keys.append(AccountMeta(voter,
+			True, False))
 		keys.append(AccountMeta(reputation_state_account,
 			False, True))
 		keys.append(AccountMeta(user_vote_state_account,
 			False, True))
+		keys.append(AccountMeta(voter_profile_account,
+			False, True))
+		if voter_reputation_account is not None:
+			keys.append(AccountMeta(voter_reputation_account,
+			False, False))
 		for k in voter_stake_accounts:
 			keys.append(AccountMeta(k,
 			False, False))
 		return TransactionInstruction(keys, programId, data)
+class CloseExpiredCommitmentInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+	)
+	def serialize(self,
+	) -> str:
+		return self.schema.build({
+			"tag": 3,
+		})
+	def getInstruction(self,
+programId: PublicKey,
+rent_payer: PublicKey,
+user_vote_state_account: PublicKey,
+) -> TransactionInstruction:
+		data = self.serialize(
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(rent_payer,
+			False, True))
+		keys.append(AccountMeta(user_vote_state_account,
+			False, True))
+		return TransactionInstruction(keys, programId, data)
+class CreateDelegationInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+		"expiry" / borsh.I64,
+		"permissions" / borsh.U8,
+	)
+	def serialize(self,
+		expiry: int,
+		permissions: int,
+	) -> str:
+		return self.schema.build({
+			"tag": 4,
+			"expiry": expiry,
+			"permissions": permissions,
+		})
+	def getInstruction(self,
+		expiry: int,
+		permissions: int,
+programId: PublicKey,
+system_program: PublicKey,
+staker: PublicKey,
+delegate: PublicKey,
+delegation_account: PublicKey,
+) -> TransactionInstruction:
+		data = self.serialize(
+		expiry,
+		permissions,
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(system_program,
+			False, False))
+		keys.append(AccountMeta(staker,
+			True, True))
+		keys.append(AccountMeta(delegate,
+			False, False))
+		keys.append(AccountMeta(delegation_account,
+			False, True))
+		return TransactionInstruction(keys, programId, data)
+class RevokeDelegationInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+	)
+	def serialize(self,
+	) -> str:
+		return self.schema.build({
+			"tag": 5,
+		})
+	def getInstruction(self,
+programId: PublicKey,
+staker: PublicKey,
+delegation_account: PublicKey,
+) -> TransactionInstruction:
+		data = self.serialize(
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(staker,
+			True, True))
+		keys.append(AccountMeta(delegation_account,
+			False, True))
+		return TransactionInstruction(keys, programId, data)
+class DelegatedVoteInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+		"user_key" / borsh.U8[32],
+		"vote_value" / borsh.U8,
+	)
+	def serialize(self,
+		user_key: List[int],
+		vote_value: int,
+	) -> str:
+		return self.schema.build({
+			"tag": 6,
+			"user_key": user_key,
+			"vote_value": vote_value,
+		})
+	def getInstruction(self,
+		user_key: List[int],
+		vote_value: int,
+programId: PublicKey,
+system_program: PublicKey,
+rent_payer: PublicKey,
+delegate: PublicKey,
+staker: PublicKey,
+delegation_account: PublicKey,
+reputation_state_account: PublicKey,
+user_vote_state_account: PublicKey,
+voter_profile_account: PublicKey,
+config_account: PublicKey,
+staker_reputation_account: Optional[PublicKey],
+staker_stake_accounts: List[PublicKey],
+) -> TransactionInstruction:
+		data = self.serialize(
+		user_key,
+		vote_value,
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(system_program,
+			False, False))
+		keys.append(AccountMeta(rent_payer,
+			False, True))
+		keys.append(AccountMeta(delegate,
+			True, False))
+		keys.append(AccountMeta(staker,
+			False, False))
+		keys.append(AccountMeta(delegation_account,
+			False, False))
+		keys.append(AccountMeta(reputation_state_account,
+			False, True))
+		keys.append(AccountMeta(user_vote_state_account,
+			False, True))
+		keys.append(AccountMeta(voter_profile_account,
+			False, True))
+		keys.append(AccountMeta(config_account,
+			False, False))
+		if staker_reputation_account is not None:
+			keys.append(AccountMeta(staker_reputation_account,
+			False, False))
+		for k in staker_stake_accounts:
+			keys.append(AccountMeta(k,
+			False, False))
+		return TransactionInstruction(keys, programId, data)
+class FollowCuratorInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+		"curator" / borsh.U8[32],
+	)
+	def serialize(self,
+		curator: List[int],
+	) -> str:
+		return self.schema.build({
+			"tag": 7,
+			"curator": curator,
+		})
+	def getInstruction(self,
+		curator: List[int],
+programId: PublicKey,
+system_program: PublicKey,
+follower: PublicKey,
+follow_account: PublicKey,
+) -> TransactionInstruction:
+		data = self.serialize(
+		curator,
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(system_program,
+			False, False))
+		keys.append(AccountMeta(follower,
+			True, True))
+		keys.append(AccountMeta(follow_account,
+			False, True))
+		return TransactionInstruction(keys, programId, data)
+class UnfollowCuratorInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+	)
+	def serialize(self,
+	) -> str:
+		return self.schema.build({
+			"tag": 8,
+		})
+	def getInstruction(self,
+programId: PublicKey,
+follower: PublicKey,
+follow_account: PublicKey,
+) -> TransactionInstruction:
+		data = self.serialize(
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(follower,
+			True, True))
+		keys.append(AccountMeta(follow_account,
+			False, True))
+		return TransactionInstruction(keys, programId, data)
+class PropagateCuratorVoteInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+		"user_key" / borsh.U8[32],
+	)
+	def serialize(self,
+		user_key: List[int],
+	) -> str:
+		return self.schema.build({
+			"tag": 9,
+			"user_key": user_key,
+		})
+	def getInstruction(self,
+		user_key: List[int],
+programId: PublicKey,
+system_program: PublicKey,
+rent_payer: PublicKey,
+follower: PublicKey,
+follow_account: PublicKey,
+curator_user_vote_account: PublicKey,
+reputation_state_account: PublicKey,
+user_vote_state_account: PublicKey,
+voter_profile_account: PublicKey,
+config_account: PublicKey,
+follower_reputation_account: Optional[PublicKey],
+follower_stake_accounts: List[PublicKey],
+) -> TransactionInstruction:
+		data = self.serialize(
+		user_key,
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(system_program,
+			False, False))
+		keys.append(AccountMeta(rent_payer,
+			False, True))
+		keys.append(AccountMeta(follower,
+			False, False))
+		keys.append(AccountMeta(follow_account,
+			False, False))
+		keys.append(AccountMeta(curator_user_vote_account,
+			False, False))
+		keys.append(AccountMeta(reputation_state_account,
+			False, True))
+		keys.append(AccountMeta(user_vote_state_account,
+			False, True))
+		keys.append(AccountMeta(voter_profile_account,
+			False, True))
+		keys.append(AccountMeta(config_account,
+			False, False))
+		if follower_reputation_account is not None:
+			keys.append(AccountMeta(follower_reputation_account,
+			False, False))
+		for k in follower_stake_accounts:
+			keys.append(AccountMeta(k,
+			False, False))
+		return TransactionInstruction(keys, programId, data)
+class RelayVoteInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+		"user_key" / borsh.U8[32],
+		"vote_value" / borsh.U8,
+		"nonce" / borsh.U64,
+		"expiry" / borsh.I64,
+	)
+	def serialize(self,
+		user_key: List[int],
+		vote_value: int,
+		nonce: int,
+		expiry: int,
+	) -> str:
+		return self.schema.build({
+			"tag": 10,
+			"user_key": user_key,
+			"vote_value": vote_value,
+			"nonce": nonce,
+			"expiry": expiry,
+		})
+	def getInstruction(self,
+		user_key: List[int],
+		vote_value: int,
+		nonce: int,
+		expiry: int,
+programId: PublicKey,
+system_program: PublicKey,
+instructions_sysvar: PublicKey,
+rent_payer: PublicKey,
+voter: PublicKey,
+reputation_state_account: PublicKey,
+user_vote_state_account: PublicKey,
+voter_profile_account: PublicKey,
+config_account: PublicKey,
+voter_reputation_account: Optional[PublicKey],
+voter_stake_accounts: List[PublicKey],
+) -> TransactionInstruction:
+		data = self.serialize(
+		user_key,
+		vote_value,
+		nonce,
+		expiry,
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(system_program,
+			False, False))
+		keys.append(AccountMeta(instructions_sysvar,
+			False, False))
+		keys.append(AccountMeta(rent_payer,
+			True, True))
+		keys.append(AccountMeta(voter,
+			False, False))
+		keys.append(AccountMeta(reputation_state_account,
+			False, True))
+		keys.append(AccountMeta(user_vote_state_account,
+			False, True))
+		keys.append(AccountMeta(voter_profile_account,
+			False, True))
+		keys.append(AccountMeta(config_account,
+			False, False))
+		if voter_reputation_account is not None:
+			keys.append(AccountMeta(voter_reputation_account,
+			False, False))
+		for k in voter_stake_accounts:
+			keys.append(AccountMeta(k,
+			False, False))
+		return TransactionInstruction(keys, programId, data)
+class AggregateInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+		"user_key" / borsh.U8[32],
+	)
+	def serialize(self,
+		user_key: List[int],
+	) -> str:
+		return self.schema.build({
+			"tag": 11,
+			"user_key": user_key,
+		})
+	def getInstruction(self,
+		user_key: List[int],
+programId: PublicKey,
+system_program: PublicKey,
+rent_payer: PublicKey,
+reputation_state_account: PublicKey,
+reputation_shard_accounts: List[PublicKey],
+) -> TransactionInstruction:
+		data = self.serialize(
+		user_key,
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(system_program,
+			False, False))
+		keys.append(AccountMeta(rent_payer,
+			False, True))
+		keys.append(AccountMeta(reputation_state_account,
+			False, True))
+		for k in reputation_shard_accounts:
+			keys.append(AccountMeta(k,
+			False, True))
+		return TransactionInstruction(keys, programId, data)
+class MigrateInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+	)
+	def serialize(self,
+	) -> str:
+		return self.schema.build({
+			"tag": 12,
+		})
+	def getInstruction(self,
+programId: PublicKey,
+system_program: PublicKey,
+rent_payer: PublicKey,
+state_account: PublicKey,
+) -> TransactionInstruction:
+		data = self.serialize(
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(system_program,
+			False, False))
+		keys.append(AccountMeta(rent_payer,
+			True, True))
+		keys.append(AccountMeta(state_account,
+			False, True))
+		return TransactionInstruction(keys, programId, data)
+class SnapshotInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+		"user_key" / borsh.U8[32],
+	)
+	def serialize(self,
+		user_key: List[int],
+	) -> str:
+		return self.schema.build({
+			"tag": 13,
+			"user_key": user_key,
+		})
+	def getInstruction(self,
+		user_key: List[int],
+programId: PublicKey,
+system_program: PublicKey,
+rent_payer: PublicKey,
+reputation_state_account: PublicKey,
+score_history_account: PublicKey,
+reputation_shard_accounts: List[PublicKey],
+) -> TransactionInstruction:
+		data = self.serialize(
+		user_key,
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(system_program,
+			False, False))
+		keys.append(AccountMeta(rent_payer,
+			False, True))
+		keys.append(AccountMeta(reputation_state_account,
+			False, False))
+		keys.append(AccountMeta(score_history_account,
+			False, True))
+		for k in reputation_shard_accounts:
+			keys.append(AccountMeta(k,
+			False, False))
+		return TransactionInstruction(keys, programId, data)
+class SetConfigInstruction:
+	schema = borsh.CStruct(
+		"tag" / borsh.U8,
+		"max_new_votes_per_epoch" / borsh.U64,
+	)
+	def serialize(self,
+		max_new_votes_per_epoch: int,
+	) -> str:
+		return self.schema.build({
+			"tag": 14,
+			"max_new_votes_per_epoch": max_new_votes_per_epoch,
+		})
+	def getInstruction(self,
+		max_new_votes_per_epoch: int,
+programId: PublicKey,
+system_program: PublicKey,
+fee_payer: PublicKey,
+authority: PublicKey,
+program_data: PublicKey,
+config_account: PublicKey,
+) -> TransactionInstruction:
+		data = self.serialize(
+		max_new_votes_per_epoch,
+)
+		keys: List[AccountMeta] = []
+		keys.append(AccountMeta(system_program,
+			False, False))
+		keys.append(AccountMeta(fee_payer,
+			True, True))
+		keys.append(AccountMeta(authority,
+			True, False))
+		keys.append(AccountMeta(program_data,
+			False, False))
+		keys.append(AccountMeta(config_account,
+			False, True))
+		return TransactionInstruction(keys, programId, data)